@@ -2,7 +2,11 @@ use std::{collections::HashMap, fs::File, io::BufReader};
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use mhr_calculator::{
-    calc::{calc_data_manager::CalcDataManager, calculator::Calculator, types::SkillSlotCount},
+    calc::{
+        calc_data_manager::CalcDataManager, calc_reporter::EmptyReporter,
+        calc_request::CalculateRequest, calculator::Calculator, cancel_token::CancelToken,
+        types::SkillSlotCount,
+    },
     data::{
         armor::SexType,
         data_manager::DataManager,
@@ -24,8 +28,10 @@ fn bench(c: &mut Criterion) {
                     &manifest_dir.join("../src-tauri/data/armor.json"),
                     &manifest_dir.join("../src-tauri/data/skill.json"),
                     &manifest_dir.join("../src-tauri/data/deco.json"),
+                    None,
                 )
                 .await
+                .unwrap()
             });
 
         let anomalies = parse_anomaly(
@@ -33,16 +39,18 @@ fn bench(c: &mut Criterion) {
             dm.get_armors(),
             dm.get_armor_name_dict(),
             dm.get_skill_name_dict(),
-        );
+        )
+        .unwrap();
 
-        dm.set_file_anomalies(anomalies);
+        dm.set_file_anomalies(anomalies.items);
 
         let talismans = parse_talisman(
             &manifest_dir.join("../bench-data/case2/talisman.txt"),
             dm.get_skill_name_dict(),
-        );
+        )
+        .unwrap();
 
-        dm.set_file_talismans(talismans);
+        dm.set_file_talismans(talismans.items);
 
         let weapon_slots: Vec<SkillSlotCount> = serde_json::from_reader(BufReader::new(
             File::open("../bench-data/case2/weapon_slots.json").unwrap(),
@@ -70,16 +78,23 @@ fn bench(c: &mut Criterion) {
             &Calculator::convert_to_skills_container(&selected_skills),
         );
 
+        let request = CalculateRequest::new(
+            weapon_slots,
+            selected_skills,
+            free_slots,
+            SexType::Female,
+            false,
+        );
+
         b.iter(move || {
             let (_log, _result) = Calculator::calculate(
-                weapon_slots.clone(),
-                selected_skills.clone(),
-                free_slots.clone(),
-                SexType::Female,
-                false,
+                &request,
                 &dm,
                 &cm,
-            );
+                &CancelToken::default(),
+                &EmptyReporter {},
+            )
+            .unwrap();
         });
     });
 }
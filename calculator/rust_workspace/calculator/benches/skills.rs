@@ -0,0 +1,140 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mhr_calculator::{
+    calc::{calc_equipment::CalcEquipment, skills::SkillsContainer, types::SkillLevelVec},
+    data::data_manager::DataManager,
+};
+
+// Skill operations on every armor, next to the same operations on dense levels only
+fn bench(c: &mut Criterion) {
+    let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf();
+
+    let dm = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            DataManager::from_sources(
+                &manifest_dir.join("../src-tauri/data/armor.json"),
+                &manifest_dir.join("../src-tauri/data/skill.json"),
+                &manifest_dir.join("../src-tauri/data/deco.json"),
+                None,
+            )
+            .await
+            .unwrap()
+        });
+
+    let mut armor_ids = dm.get_armors().keys().collect::<Vec<_>>();
+    armor_ids.sort();
+
+    let containers = armor_ids
+        .iter()
+        .map(|id| CalcEquipment::convert_from_base_skills_armor(&dm, &dm.get_armors()[*id]))
+        .collect::<Vec<_>>();
+
+    let denses = containers
+        .iter()
+        .map(|container| {
+            let mut dense = SkillLevelVec::zeros();
+
+            for (uid, level) in container.iter() {
+                dense[uid] = level;
+            }
+
+            dense
+        })
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("skills");
+
+    group.bench_function("iter", |b| {
+        b.iter(|| {
+            containers
+                .iter()
+                .map(|container| container.iter().map(|(_, level)| level as i32).sum::<i32>())
+                .sum::<i32>()
+        })
+    });
+
+    group.bench_function("iter_dense", |b| {
+        b.iter(|| {
+            denses
+                .iter()
+                .map(|dense| {
+                    dense
+                        .iter()
+                        .filter(|&&level| 0 < level)
+                        .map(|&level| level as i32)
+                        .sum::<i32>()
+                })
+                .sum::<i32>()
+        })
+    });
+
+    group.bench_function("len", |b| {
+        b.iter(|| containers.iter().map(SkillsContainer::len).sum::<usize>())
+    });
+
+    group.bench_function("len_dense", |b| {
+        b.iter(|| {
+            denses
+                .iter()
+                .map(SkillsContainer::len_static)
+                .sum::<usize>()
+        })
+    });
+
+    group.bench_function("is_le", |b| {
+        b.iter(|| {
+            containers
+                .iter()
+                .zip(containers.iter().skip(1))
+                .filter(|(container1, container2)| container1.is_le(container2))
+                .count()
+        })
+    });
+
+    group.bench_function("is_le_dense", |b| {
+        b.iter(|| {
+            denses
+                .iter()
+                .zip(denses.iter().skip(1))
+                .filter(|(dense1, dense2)| {
+                    let diff = *dense1 - *dense2;
+
+                    diff.iter().all(|&level| level <= 0)
+                })
+                .count()
+        })
+    });
+
+    group.bench_function("sub_add", |b| {
+        b.iter(|| {
+            let mut sum = SkillsContainer::new();
+
+            for container in containers.iter() {
+                sum.add(container);
+                sum.sub(black_box(&containers[0]));
+            }
+
+            sum
+        })
+    });
+
+    group.bench_function("sub_add_dense", |b| {
+        b.iter(|| {
+            let mut sum = SkillLevelVec::zeros();
+
+            for dense in denses.iter() {
+                sum += dense;
+                sum -= black_box(&denses[0]);
+            }
+
+            sum
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::bail;
+use serde::Serialize;
+
+use crate::data::{armor::AnomalyArmor, data_manager::DataManager};
+
+use super::{
+    calc_data_manager::CalcDataManager, calc_reporter::EmptyReporter,
+    calc_request::CalculateRequest, calculator::Calculator, cancel_token::CancelToken,
+};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum AnomalyRecommendation {
+    // Some target build is impossible without it
+    Keep,
+    // Used by target builds, but other equipments can replace it in each of them
+    Optional,
+    Discard,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnomalyRanking {
+    pub anomaly: AnomalyArmor,
+    pub used_count: usize,
+    pub required_count: usize,
+    pub recommendation: AnomalyRecommendation,
+}
+
+pub struct AnomalyRanker {}
+
+impl AnomalyRanker {
+    // Calculates each target build, and again without each anomaly armor all of its answers use.
+    // A cancelled ranking is an error, since anomalies it did not reach would look unused
+    pub fn rank(
+        dm: &DataManager,
+        cm: &mut CalcDataManager,
+        requests: &[CalculateRequest],
+        cancel_token: &CancelToken,
+    ) -> anyhow::Result<Vec<AnomalyRanking>> {
+        let mut used_counts = HashMap::<String, usize>::new();
+        let mut required_counts = HashMap::<String, usize>::new();
+
+        for request in requests {
+            cm.refresh_infos(
+                dm,
+                &Calculator::convert_to_skills_container(&request.selected_skills),
+            );
+
+            let answers_anomalies = Self::get_answers_anomalies(dm, cm, request, cancel_token)?;
+
+            let used_anomalies = answers_anomalies
+                .iter()
+                .flatten()
+                .cloned()
+                .collect::<HashSet<_>>();
+
+            for anomaly_id in used_anomalies {
+                *used_counts.entry(anomaly_id.clone()).or_default() += 1;
+
+                // An answer without it already shows the build is possible
+                if !answers_anomalies
+                    .iter()
+                    .all(|ids| ids.contains(&anomaly_id))
+                {
+                    continue;
+                }
+
+                let mut excluded_request = request.clone();
+                excluded_request.excluded_equips.insert(anomaly_id.clone());
+
+                let has_excluded_answer =
+                    Self::has_answer(dm, cm, &excluded_request, cancel_token)?;
+
+                if !has_excluded_answer {
+                    *required_counts.entry(anomaly_id).or_default() += 1;
+                }
+            }
+        }
+
+        let mut rankings = dm
+            .get_anomaly_armors()
+            .iter()
+            .map(|(id, anomaly)| {
+                let used_count = used_counts.get(id).copied().unwrap_or_default();
+                let required_count = required_counts.get(id).copied().unwrap_or_default();
+
+                let recommendation = if 0 < required_count {
+                    AnomalyRecommendation::Keep
+                } else if 0 < used_count {
+                    AnomalyRecommendation::Optional
+                } else {
+                    AnomalyRecommendation::Discard
+                };
+
+                AnomalyRanking {
+                    anomaly: anomaly.as_ref().clone(),
+                    used_count,
+                    required_count,
+                    recommendation,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        rankings.sort_by(|ranking1, ranking2| {
+            ranking1
+                .recommendation
+                .cmp(&ranking2.recommendation)
+                .then(ranking2.required_count.cmp(&ranking1.required_count))
+                .then(ranking2.used_count.cmp(&ranking1.used_count))
+                .then(
+                    ranking1
+                        .anomaly
+                        .affected
+                        .id()
+                        .cmp(ranking2.anomaly.affected.id()),
+                )
+        });
+
+        Ok(rankings)
+    }
+
+    // Anomaly armor ids of each answer, pages are followed until the answers run out
+    fn get_answers_anomalies(
+        dm: &DataManager,
+        cm: &CalcDataManager,
+        request: &CalculateRequest,
+        cancel_token: &CancelToken,
+    ) -> anyhow::Result<Vec<HashSet<String>>> {
+        let mut request = request.clone();
+        request.cursor = None;
+
+        let mut answers_anomalies = Vec::new();
+
+        loop {
+            let (_, result) =
+                Calculator::calculate(&request, dm, cm, cancel_token, &EmptyReporter {})?;
+
+            Self::check_cancelled(cancel_token)?;
+
+            answers_anomalies.extend(result.full_equipments.iter().map(|equipments| {
+                equipments
+                    .armors
+                    .values()
+                    .filter(|armor| armor.is_anomaly)
+                    .map(|armor| armor.id.clone())
+                    .collect::<HashSet<_>>()
+            }));
+
+            match result.next_cursor {
+                Some(cursor) => request.cursor = Some(cursor),
+                None => break,
+            }
+        }
+
+        Ok(answers_anomalies)
+    }
+
+    fn has_answer(
+        dm: &DataManager,
+        cm: &CalcDataManager,
+        request: &CalculateRequest,
+        cancel_token: &CancelToken,
+    ) -> anyhow::Result<bool> {
+        let mut request = request.clone();
+        request.cursor = None;
+        request.answer_limit = 1;
+
+        let (_, result) = Calculator::calculate(&request, dm, cm, cancel_token, &EmptyReporter {})?;
+
+        Self::check_cancelled(cancel_token)?;
+
+        Ok(!result.full_equipments.is_empty())
+    }
+
+    // Cancelled calculations return the answers found so far, which are not all of them
+    fn check_cancelled(cancel_token: &CancelToken) -> anyhow::Result<()> {
+        if cancel_token.is_cancelled() {
+            bail!("Anomaly ranking was cancelled");
+        }
+
+        Ok(())
+    }
+}
@@ -7,19 +7,23 @@ use std::{
 use log::info;
 use nohash_hasher::IntMap;
 
-use crate::data::{
-    armor::{ArmorPart, BaseArmor, SexType, SLOT_ARMOR_PREFIX},
-    data_manager::DataManager,
+use crate::{
+    data::{
+        armor::{ArmorPart, BaseArmor, SexType, SLOT_ARMOR_PREFIX},
+        data_manager::DataManager,
+    },
+    utils::stable_hash::get_stable_hash,
 };
 
 use super::{
     calc_equipment::CalcEquipment,
     calc_point::CalcPoint,
+    calc_stat::StatBounds,
     calc_ui_generator::CalcEquipmentUid,
     constant::{EQUIP_PART_COUNT, MAX_SLOT_LEVEL},
     full_equipments::FullEquipments,
     skills::SkillsContainer,
-    types::{EquipmentsArray, PointsVec},
+    types::{EquipmentsArray, PointsVec, SkillSlotCount},
 };
 
 pub type AllRealEquipments<'a> = Vec<HashMap<String, &'a Arc<CalcEquipment>>>;
@@ -39,6 +43,7 @@ pub struct CalcDataManager {
     empty_equips: [Arc<CalcEquipment>; EQUIP_PART_COUNT],
 
     banned_decos: HashSet<String>,
+    deco_inventory: HashMap<String, SkillSlotCount>,
 }
 
 impl CalcDataManager {
@@ -94,6 +99,7 @@ impl CalcDataManager {
             empty_equips,
 
             banned_decos: HashSet::new(),
+            deco_inventory: HashMap::new(),
         }
     }
 
@@ -198,6 +204,7 @@ impl CalcDataManager {
         &self,
         sex_type: &SexType,
         include_lte_equips: bool,
+        excluded_equips: &HashSet<String>,
     ) -> (AllRealEquipments, Vec<Vec<&Arc<CalcEquipment>>>) {
         let equip_parts_len = ArmorPart::get_all_equip().len();
 
@@ -217,7 +224,9 @@ impl CalcDataManager {
             let calc_part_equips = all_calc_equips.get_mut(part).unwrap();
 
             for equip in part_equips.values() {
-                if !DataManager::is_valid_sextype(equip.as_armor().sex_type(), sex_type) {
+                if !DataManager::is_valid_sextype(equip.as_armor().sex_type(), sex_type)
+                    || excluded_equips.contains(equip.id())
+                {
                     continue;
                 }
 
@@ -231,7 +240,9 @@ impl CalcDataManager {
             let calc_part_equips = all_calc_equips.get_mut(part).unwrap();
 
             for equip in part_equips.values() {
-                if !DataManager::is_valid_sextype(equip.as_armor().sex_type(), sex_type) {
+                if !DataManager::is_valid_sextype(equip.as_armor().sex_type(), sex_type)
+                    || excluded_equips.contains(equip.id())
+                {
                     continue;
                 }
 
@@ -243,6 +254,7 @@ impl CalcDataManager {
         all_real_equips.push(
             self.all_talismans
                 .iter()
+                .filter(|(id, _)| !excluded_equips.contains(*id))
                 .map(|(id, equip)| (id.clone(), equip))
                 .collect(),
         );
@@ -254,12 +266,16 @@ impl CalcDataManager {
         let talis_vec = self
             .all_talismans
             .iter()
+            .filter(|(id, _)| !excluded_equips.contains(*id))
             .map(|(_, tali)| tali)
             .collect::<Vec<_>>();
 
         all_calc_equips.push(talis_vec);
 
         for (part, part_equips) in all_calc_equips.iter_mut().enumerate() {
+            // Which one of equal equipments is kept doesn't depend on hash map iteration
+            part_equips.sort_by(|equip1, equip2| equip1.id().cmp(equip2.id()));
+
             if part == ArmorPart::Talisman.as_usize() || !include_lte_equips {
                 *part_equips = Self::remove_le_equipments(part_equips.clone(), None);
             }
@@ -292,6 +308,25 @@ impl CalcDataManager {
         ret
     }
 
+    // Equipments of the last refresh, in uid order
+    pub fn get_equipments_key(&self) -> u64 {
+        let equip_infos = self
+            .all_equips
+            .iter()
+            .map(|equip| {
+                (
+                    equip.uid(),
+                    equip.id(),
+                    equip.skills().get_list(),
+                    equip.slots().as_slice(),
+                    StatBounds::get_stat_values(&equip.stats()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        get_stable_hash(&equip_infos)
+    }
+
     pub fn get_by_uid(&self, uid: usize) -> &Arc<CalcEquipment> {
         &self.all_equips[uid]
     }
@@ -318,6 +353,14 @@ impl CalcDataManager {
         &self.banned_decos
     }
 
+    pub fn set_deco_inventory(&mut self, deco_inventory: HashMap<String, SkillSlotCount>) {
+        self.deco_inventory = deco_inventory;
+    }
+
+    pub fn get_deco_inventory(&self) -> &HashMap<String, SkillSlotCount> {
+        &self.deco_inventory
+    }
+
     pub fn get_empty_equips(&self) -> EquipmentsArray {
         let mut ret_opt: [Option<&Arc<CalcEquipment>>; EQUIP_PART_COUNT] = Default::default();
 
@@ -332,73 +375,32 @@ impl CalcDataManager {
         self.uid_gen.flush();
         self.all_equips.clear();
 
-        // Armors
-        for equips in self.all_base_armors.iter_mut() {
-            for equip in equips.values_mut() {
-                Self::update_equipment(
-                    equip,
-                    &mut self.uid_gen,
-                    &mut self.all_equips,
-                    dm,
-                    req_skills,
-                );
-            }
-        }
-
-        for equips in self.all_anomaly_armors.iter_mut() {
-            for equip in equips.values_mut() {
-                Self::update_equipment(
-                    equip,
-                    &mut self.uid_gen,
-                    &mut self.all_equips,
-                    dm,
-                    req_skills,
-                );
-            }
-        }
+        let uid_gen = &mut self.uid_gen;
+        let all_equips = &mut self.all_equips;
 
-        for equips in self.slot_armors.iter_mut() {
-            for equip in equips.values_mut() {
-                Self::update_equipment(
-                    equip,
-                    &mut self.uid_gen,
-                    &mut self.all_equips,
-                    dm,
-                    req_skills,
-                );
-            }
+        // Armors
+        for equips in self
+            .all_base_armors
+            .iter_mut()
+            .chain(self.all_anomaly_armors.iter_mut())
+            .chain(self.slot_armors.iter_mut())
+        {
+            Self::update_equipments(equips, uid_gen, all_equips, dm, req_skills);
         }
 
         // Talismans
-        for equip in self.all_talismans.values_mut() {
-            Self::update_equipment(
-                equip,
-                &mut self.uid_gen,
-                &mut self.all_equips,
-                dm,
-                req_skills,
-            );
-        }
-
-        for equip in self.slot_talismans.values_mut() {
-            Self::update_equipment(
-                equip,
-                &mut self.uid_gen,
-                &mut self.all_equips,
-                dm,
-                req_skills,
-            );
-        }
+        Self::update_equipments(&mut self.all_talismans, uid_gen, all_equips, dm, req_skills);
+        Self::update_equipments(
+            &mut self.slot_talismans,
+            uid_gen,
+            all_equips,
+            dm,
+            req_skills,
+        );
 
         // Equipments
         for equip in self.empty_equips.iter_mut() {
-            Self::update_equipment(
-                equip,
-                &mut self.uid_gen,
-                &mut self.all_equips,
-                dm,
-                req_skills,
-            );
+            Self::update_equipment(equip, uid_gen, all_equips, dm, req_skills);
         }
     }
 
@@ -425,6 +427,22 @@ impl CalcDataManager {
         Some(sub_req_skills)
     }
 
+    // Uids follow id order, so a cursor of the same data stays valid in another run
+    fn update_equipments(
+        equips: &mut HashMap<String, Arc<CalcEquipment>>,
+        uid_gen: &mut CalcEquipmentUid,
+        all_equips: &mut Vec<Arc<CalcEquipment>>,
+        dm: &DataManager,
+        req_skills: &SkillsContainer,
+    ) {
+        let mut equips = equips.iter_mut().collect::<Vec<_>>();
+        equips.sort_by_key(|(id, _)| *id);
+
+        for (_, equip) in equips {
+            Self::update_equipment(equip, uid_gen, all_equips, dm, req_skills);
+        }
+    }
+
     fn update_equipment(
         equip: &mut Arc<CalcEquipment>,
         uid_gen: &mut CalcEquipmentUid,
@@ -525,12 +543,17 @@ impl CalcDataManager {
         for (index1, &equip1) in equipments.iter().enumerate() {
             let mut is_le = false;
 
-            for &equip2 in equipments.iter() {
+            for (index2, &equip2) in equipments.iter().enumerate() {
                 if equip1.part() != equip2.part() || equip1.uid() == equip2.uid() {
                     continue;
                 }
 
                 if equip1.is_le(equip2, false, req_skills) {
+                    // Equal equipments are le of each other, keep the first one to represent them
+                    if index1 < index2 && equip2.is_le(equip1, false, req_skills) {
+                        continue;
+                    }
+
                     is_le = true;
 
                     let existing_set = ge_equips_map.get_mut(&equip2.uid()).unwrap();
@@ -684,6 +707,7 @@ impl CalcDataManager {
         all_equips: &Vec<Vec<&'a Arc<CalcEquipment>>>,
         no_deco_skills: &SkillsContainer,
         empty_equips: &'a EquipmentsArray,
+        pinned_parts: &[bool; EQUIP_PART_COUNT],
     ) -> (
         Vec<Vec<&'a Arc<CalcEquipment>>>,
         Vec<&'a Arc<CalcEquipment>>,
@@ -692,6 +716,14 @@ impl CalcDataManager {
         let mut all_unique_equips_flat = Vec::new();
 
         for (part, equips) in all_equips.iter().enumerate() {
+            // Pinned equipment is always a key equipment of its part
+            if pinned_parts[part] {
+                all_unique_equips_flat.extend(equips.iter().copied());
+                all_unique_equips.insert(part, equips.clone());
+
+                continue;
+            }
+
             let mut part_unique_equips = equips
                 .iter()
                 .filter_map(|&equip| {
@@ -719,7 +751,9 @@ impl CalcDataManager {
         }
 
         empty_equips.iter().for_each(|&equip| {
-            all_unique_equips_flat.push(equip);
+            if !pinned_parts[equip.part()] {
+                all_unique_equips_flat.push(equip);
+            }
         });
 
         CalcEquipment::sort_by_points(&mut all_unique_equips_flat);
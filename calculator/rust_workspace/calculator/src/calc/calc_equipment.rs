@@ -217,10 +217,59 @@ impl CalcEquipment {
         other: &CalcEquipment,
         include_stat: bool,
         req_skills: Option<&SkillsContainer>,
+    ) -> bool {
+        let stats = if include_stat && self.part != ArmorPart::Talisman.as_usize() {
+            Some((self.stats(), other.stats()))
+        } else {
+            None
+        };
+
+        Self::is_le_values(
+            (self.slots_lp(), other.slots_lp()),
+            (self.skills(), other.skills()),
+            stats,
+            req_skills,
+        )
+    }
+
+    // Missing skills may be filled with decorations in the slots other has more than self
+    pub fn is_le_with_decos(&self, other: &CalcEquipment, dm: &DataManager) -> bool {
+        if self.is_le(other, false, None) {
+            return true;
+        }
+
+        let mut avail_slots_lp = *other.slots_lp();
+
+        if !DecorationCombination::is_possible_static_lp_equip_mut(
+            &mut avail_slots_lp,
+            &[self.slots_lp()],
+        ) {
+            return false;
+        }
+
+        let mut missing_skills = self.skills().clone();
+        missing_skills.sub(other.skills());
+        missing_skills.clear_zeros();
+
+        if missing_skills
+            .iter()
+            .any(|(uid, _)| !dm.has_decoration(uid))
+        {
+            return false;
+        }
+
+        dm.check_possible_deco_combs_lp(&missing_skills, &avail_slots_lp)
+    }
+
+    // Pairs of (self, other) values, stats are compared only when given
+    pub fn is_le_values(
+        slots_lp: (&SlotsVec, &SlotsVec),
+        skills: (&SkillsContainer, &SkillsContainer),
+        stats: Option<(ArmorStat, ArmorStat)>,
+        req_skills: Option<&SkillsContainer>,
     ) -> bool {
         // Slot
-        let self_slots_lp = self.slots_lp();
-        let other_slots_lp = other.slots_lp();
+        let (self_slots_lp, other_slots_lp) = slots_lp;
 
         let slots_possible =
             DecorationCombination::is_possible_static_lp(other_slots_lp, self_slots_lp);
@@ -230,8 +279,7 @@ impl CalcEquipment {
         }
 
         // Skill
-        let base_self_skills = self.skills();
-        let base_other_skills = other.skills();
+        let (base_self_skills, base_other_skills) = skills;
 
         let is_skill_le = match req_skills {
             Some(req_skills) => {
@@ -246,11 +294,7 @@ impl CalcEquipment {
 
                 is_le
             }
-            None => {
-                let diff = base_self_skills.get_diff(base_other_skills);
-
-                diff.is_empty()
-            }
+            None => base_self_skills.is_le(base_other_skills),
         };
 
         if !is_skill_le {
@@ -258,11 +302,12 @@ impl CalcEquipment {
         }
 
         // Stat
-        if include_stat
-            && self.part != ArmorPart::Talisman.as_usize()
-            && self.stats() > other.stats()
-        {
-            return false;
+        if let Some((self_stats, other_stats)) = stats {
+            let is_stat_le = self_stats <= other_stats;
+
+            if !is_stat_le {
+                return false;
+            }
         }
 
         true
@@ -316,7 +361,8 @@ impl CalcEquipment {
         let mut ret = SlotsVec::default();
 
         for slot_size in base_slots {
-            if *slot_size == 0 {
+            // 0 is no slot, sizes out of range are reported by the parsers and data checks
+            if !(1..=MAX_SLOT_LEVEL as SkillSlotCount).contains(slot_size) {
                 continue;
             }
 
@@ -327,6 +373,9 @@ impl CalcEquipment {
     }
 
     pub fn sort_by_points(equipments: &mut [&Arc<CalcEquipment>]) {
+        // Ties keep id order, so the search order doesn't depend on hash map iteration
+        equipments.sort_by(|equip1, equip2| equip1.id().cmp(equip2.id()));
+
         for slot_index in (0..MAX_SLOT_LEVEL).rev() {
             equipments.sort_by_cached_key(|equip| Reverse(equip.points()[slot_index]));
         }
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use itertools::Itertools;
 
 use nohash_hasher::{IntMap, IntSet};
+use serde::{Deserialize, Serialize};
 
 use crate::data::data_manager::DataManager;
 
@@ -16,12 +17,19 @@ use super::{
     types::{EquipmentsArray, PointsVec},
 };
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CalcEquipmentsIteratorState {
+    probe_ids: Vec<(usize, usize)>,
+}
+
 pub struct CalcEquipmentsIterator<'a> {
     req_points: &'a PointsVec,
     weapon_points: &'a PointsVec,
     left_points: PointsVec,
 
     is_initialized: bool,
+    is_resumed: bool,
     all_equips: Vec<&'a Arc<CalcEquipment>>,
 
     all_parts: Vec<usize>,
@@ -127,6 +135,7 @@ impl<'a> CalcEquipmentsIterator<'a> {
             left_points,
 
             is_initialized: false,
+            is_resumed: false,
             all_equips,
 
             all_parts_sum: all_parts.iter().sum::<usize>(),
@@ -260,6 +269,33 @@ impl<'a> CalcEquipmentsIterator<'a> {
         }
     }
 
+    pub fn get_state(&self) -> CalcEquipmentsIteratorState {
+        CalcEquipmentsIteratorState {
+            probe_ids: self.probe_ids.clone(),
+        }
+    }
+
+    // Next call of next() yields the restored case again
+    pub fn restore_state(&mut self, state: &CalcEquipmentsIteratorState) -> bool {
+        if state.probe_ids.len() != self.all_parts.len() {
+            return false;
+        }
+
+        for &(part, equip_id) in state.probe_ids.iter() {
+            if self.all_equips.len() <= equip_id || self.all_equips[equip_id].part() != part {
+                return false;
+            }
+        }
+
+        self.probe_ids = state.probe_ids.clone();
+        self.refresh_equipments();
+
+        self.is_initialized = true;
+        self.is_resumed = true;
+
+        true
+    }
+
     pub fn get_indices(&self) -> &Vec<(usize, usize)> {
         &self.probe_ids
     }
@@ -324,6 +360,12 @@ impl<'a> Iterator for CalcEquipmentsIterator<'a> {
     type Item = [usize; EQUIP_PART_COUNT];
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.is_resumed {
+            self.is_resumed = false;
+
+            return Some(self.full_equip_ids);
+        }
+
         let succeeded = self.proceed();
 
         if succeeded {
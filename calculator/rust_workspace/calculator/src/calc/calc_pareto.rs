@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::data::armor::ArmorStat;
+
+use super::{
+    calc_equipment::CalcEquipment,
+    calc_sort::AnswerSorter,
+    calc_vector::CalcVector,
+    full_equipments::FullEquipments,
+    skills::SkillsContainer,
+    types::{CalcAnswer, SlotsVec},
+};
+
+// Values of a full build compared for domination
+pub struct AnswerSummary {
+    skills: SkillsContainer,
+    total_slots_lp: SlotsVec,
+    leftover_slots_lp: SlotsVec,
+    stats: ArmorStat,
+}
+
+// Keeps only answers which are not dominated by any other kept answer
+pub struct ParetoFilter<'a> {
+    weapon_slots_lp: &'a SlotsVec,
+    req_slots_lp: &'a SlotsVec,
+
+    summaries: HashMap<u128, AnswerSummary>,
+}
+
+impl<'a> ParetoFilter<'a> {
+    pub fn new(weapon_slots_lp: &'a SlotsVec, req_slots_lp: &'a SlotsVec) -> Self {
+        Self {
+            weapon_slots_lp,
+            req_slots_lp,
+            summaries: HashMap::new(),
+        }
+    }
+
+    pub fn get_summary(&self, answer: &CalcAnswer) -> AnswerSummary {
+        let (equipments, deco_combs, _) = answer;
+
+        let mut skills = SkillsContainer::new();
+        let mut stats = ArmorStat::new_empty();
+
+        for equip in equipments {
+            skills.add(equip.skills());
+
+            let equip_stats = equip.stats();
+
+            stats.defense += equip_stats.defense;
+            stats.fire_res += equip_stats.fire_res;
+            stats.water_res += equip_stats.water_res;
+            stats.ice_res += equip_stats.ice_res;
+            stats.elec_res += equip_stats.elec_res;
+            stats.dragon_res += equip_stats.dragon_res;
+        }
+
+        let total_slots_lp = FullEquipments::calculate_slots_lp(self.weapon_slots_lp, equipments);
+        let avail_slots_lp = total_slots_lp - self.req_slots_lp;
+
+        let leftover_slots = AnswerSorter::get_best_leftover_slots(&avail_slots_lp, deco_combs);
+
+        AnswerSummary {
+            skills,
+            total_slots_lp,
+            leftover_slots_lp: CalcVector::convert_to_lp_slots(&leftover_slots),
+            stats,
+        }
+    }
+
+    fn is_le(summary: &AnswerSummary, other: &AnswerSummary) -> bool {
+        CalcEquipment::is_le_values(
+            (&summary.total_slots_lp, &other.total_slots_lp),
+            (&summary.skills, &other.skills),
+            Some((summary.stats.clone(), other.stats.clone())),
+            None,
+        ) && CalcEquipment::is_le_values(
+            (&summary.leftover_slots_lp, &other.leftover_slots_lp),
+            (&summary.skills, &other.skills),
+            None,
+            None,
+        )
+    }
+
+    fn is_strictly_le(summary: &AnswerSummary, other: &AnswerSummary) -> bool {
+        Self::is_le(summary, other) && !Self::is_le(other, summary)
+    }
+
+    pub fn is_dominated(&self, summary: &AnswerSummary) -> bool {
+        self.summaries
+            .values()
+            .any(|other| Self::is_strictly_le(summary, other))
+    }
+
+    pub fn is_dominating(&self, summary: &AnswerSummary) -> bool {
+        self.summaries
+            .values()
+            .any(|other| Self::is_strictly_le(other, summary))
+    }
+
+    // Removes kept answers dominated by the given one, and returns their ids
+    pub fn remove_dominated_by(&mut self, summary: &AnswerSummary) -> Vec<u128> {
+        let dominated_ids = self
+            .summaries
+            .iter()
+            .filter(|(_, other)| Self::is_strictly_le(other, summary))
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+
+        for id in dominated_ids.iter() {
+            self.summaries.remove(id);
+        }
+
+        dominated_ids
+    }
+
+    pub fn insert(&mut self, id: u128, summary: AnswerSummary) {
+        self.summaries.insert(id, summary);
+    }
+
+    pub fn remove(&mut self, id: &u128) {
+        self.summaries.remove(id);
+    }
+}
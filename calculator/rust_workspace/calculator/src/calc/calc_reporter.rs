@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+use super::calc_result::ResultFullEquipments;
+
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CalcProgress {
+    pub processed_candidates: usize,
+    pub candidates_count: usize,
+    pub all_candidate_len: usize,
+    pub answers_count: usize,
+    pub elapsed: f32,
+    pub is_done: bool,
+}
+
+pub trait CalcReporter {
+    fn on_progress(&self, _progress: &CalcProgress) {}
+
+    // Answers are converted only when this returns true
+    fn is_streaming_answers(&self) -> bool {
+        false
+    }
+
+    fn on_answer(&self, _answer: &ResultFullEquipments) {}
+}
+
+pub struct EmptyReporter {}
+
+impl CalcReporter for EmptyReporter {}
@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+
+use nohash_hasher::IntMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{
+        armor::{ArmorPart, SexType},
+        data_manager::DataManager,
+    },
+    utils::stable_hash::get_stable_hash,
+};
+
+use super::{
+    calc_data_manager::CalcDataManager,
+    calc_equips_iterator::CalcEquipmentsIteratorState,
+    calc_sort::SortKey,
+    calc_stat::MinStats,
+    constant::{EQUIP_PART_COUNT, MAX_ANSWER_LENGTH},
+    types::SkillSlotCount,
+};
+
+#[derive(Clone, Debug)]
+pub struct CalculateRequest {
+    pub weapon_slots: Vec<SkillSlotCount>,
+    pub selected_skills: IntMap<usize, SkillSlotCount>,
+    pub free_slots: Vec<SkillSlotCount>,
+    pub sex_type: SexType,
+    pub include_lte_equips: bool,
+    pub min_stats: MinStats,
+
+    pub pinned_equips: HashMap<ArmorPart, String>,
+    pub excluded_equips: HashSet<String>,
+
+    pub answer_limit: usize,
+    pub cursor: Option<CalcCursor>,
+
+    // Keeps the best answers in this order instead of the first found ones.
+    // Every candidate is searched for them, so a ranked result has no next_cursor
+    // and a ranked request with a cursor is an error
+    pub sort_keys: Vec<SortKey>,
+
+    // Drops answers worse than another one in skills, slots and stats.
+    // Answers of other pages can not be compared, so it searches every candidate like sort_keys
+    pub pareto_only: bool,
+}
+
+// Position to resume a calculation from, valid while loaded equipments stay the same
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CalcCursor {
+    // Request and data the cursor was created with, a string since it goes through JavaScript
+    pub fingerprint: String,
+    pub candidate_index: usize,
+    pub parts_state: Option<CalcEquipmentsIteratorState>,
+    pub le_state: Option<CalcEquipmentsIteratorState>,
+    pub answer_uids: Vec<[usize; EQUIP_PART_COUNT]>,
+}
+
+impl CalculateRequest {
+    pub fn new(
+        weapon_slots: Vec<SkillSlotCount>,
+        selected_skills: IntMap<usize, SkillSlotCount>,
+        free_slots: Vec<SkillSlotCount>,
+        sex_type: SexType,
+        include_lte_equips: bool,
+    ) -> Self {
+        Self {
+            weapon_slots,
+            selected_skills,
+            free_slots,
+            sex_type,
+            include_lte_equips,
+            min_stats: MinStats::default(),
+            pinned_equips: HashMap::new(),
+            excluded_equips: HashSet::new(),
+            answer_limit: MAX_ANSWER_LENGTH,
+            cursor: None,
+            sort_keys: Vec::new(),
+            pareto_only: false,
+        }
+    }
+
+    // Every field but the cursor and answer limit, with the loaded equipments and decorations.
+    // Uids depend on the load order, so reloading the same files changes it too
+    pub fn get_fingerprint(&self, dm: &DataManager, cm: &CalcDataManager) -> String {
+        let mut selected_skills = self.selected_skills.iter().collect::<Vec<_>>();
+        selected_skills.sort();
+
+        let mut pinned_equips = self
+            .pinned_equips
+            .iter()
+            .map(|(part, id)| (part.as_usize(), id))
+            .collect::<Vec<_>>();
+        pinned_equips.sort();
+
+        let mut excluded_equips = self.excluded_equips.iter().collect::<Vec<_>>();
+        excluded_equips.sort();
+
+        let hash = get_stable_hash(&(
+            (
+                &self.weapon_slots,
+                selected_skills,
+                &self.free_slots,
+                &self.sex_type,
+                self.include_lte_equips,
+                &self.min_stats,
+            ),
+            (
+                pinned_equips,
+                excluded_equips,
+                &self.sort_keys,
+                self.pareto_only,
+            ),
+            dm.get_deco_table_key(),
+            cm.get_equipments_key(),
+        ));
+
+        format!("{:016x}", hash)
+    }
+}
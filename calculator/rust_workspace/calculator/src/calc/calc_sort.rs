@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::data::armor::ArmorStat;
+
+use super::{
+    calc_vector::CalcVector,
+    constant::MAX_SLOT_LEVEL,
+    deco_combination::DecorationCombination,
+    full_equipments::FullEquipments,
+    skills::SkillsContainer,
+    types::{CalcAnswer, EquipmentsArray, SlotsVec},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum SortKind {
+    Defense,
+    FireRes,
+    WaterRes,
+    IceRes,
+    ElecRes,
+    DragonRes,
+    LeftoverSlots,
+    AnomalyCount,
+    CommonLeftoverSkills,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct SortKey {
+    pub kind: SortKind,
+    pub is_descending: bool,
+}
+
+pub struct AnswerSorter<'a> {
+    sort_keys: Vec<SortKey>,
+    weapon_slots_lp: &'a SlotsVec,
+    req_slots_lp: &'a SlotsVec,
+}
+
+impl<'a> AnswerSorter<'a> {
+    pub fn new(
+        sort_keys: &[SortKey],
+        weapon_slots_lp: &'a SlotsVec,
+        req_slots_lp: &'a SlotsVec,
+    ) -> Self {
+        let sort_keys = if sort_keys.is_empty() {
+            vec![SortKey {
+                kind: SortKind::LeftoverSlots,
+                is_descending: true,
+            }]
+        } else {
+            sort_keys.to_vec()
+        };
+
+        Self {
+            sort_keys,
+            weapon_slots_lp,
+            req_slots_lp,
+        }
+    }
+
+    // Answer with smaller rank comes first
+    pub fn get_rank(&self, answer: &CalcAnswer) -> Vec<i32> {
+        let (equipments, deco_combs, all_leftover_skills) = answer;
+
+        let mut rank = Vec::with_capacity(self.sort_keys.len());
+
+        for sort_key in self.sort_keys.iter() {
+            let values = match sort_key.kind {
+                SortKind::Defense => vec![Self::sum_stat(equipments, |stat| stat.defense)],
+                SortKind::FireRes => vec![Self::sum_stat(equipments, |stat| stat.fire_res)],
+                SortKind::WaterRes => vec![Self::sum_stat(equipments, |stat| stat.water_res)],
+                SortKind::IceRes => vec![Self::sum_stat(equipments, |stat| stat.ice_res)],
+                SortKind::ElecRes => vec![Self::sum_stat(equipments, |stat| stat.elec_res)],
+                SortKind::DragonRes => vec![Self::sum_stat(equipments, |stat| stat.dragon_res)],
+                SortKind::LeftoverSlots => self.get_leftover_slots(equipments, deco_combs),
+                SortKind::AnomalyCount => vec![equipments
+                    .iter()
+                    .filter(|equip| equip.is_armor() && equip.as_armor().is_anomaly())
+                    .count() as i32],
+                SortKind::CommonLeftoverSkills => {
+                    vec![
+                        SkillsContainer::get_have_in_common_skills(all_leftover_skills).len()
+                            as i32,
+                    ]
+                }
+            };
+
+            for value in values {
+                rank.push(if sort_key.is_descending {
+                    -value
+                } else {
+                    value
+                });
+            }
+        }
+
+        rank
+    }
+
+    fn sum_stat(equipments: &EquipmentsArray, stat_value: fn(&ArmorStat) -> i16) -> i32 {
+        equipments
+            .iter()
+            .map(|equip| stat_value(&equip.stats()) as i32)
+            .sum()
+    }
+
+    // Sum of leftover slots of the best deco combination, then leftover count of each slot size from the biggest
+    fn get_leftover_slots(
+        &self,
+        equipments: &EquipmentsArray,
+        deco_combs: &[DecorationCombination],
+    ) -> Vec<i32> {
+        let avail_slots_lp = FullEquipments::calculate_slots_lp(self.weapon_slots_lp, equipments)
+            - self.req_slots_lp;
+
+        let slots_diff = Self::get_best_leftover_slots(&avail_slots_lp, deco_combs);
+
+        Self::get_leftover_slots_key(&slots_diff)
+    }
+
+    fn get_leftover_slots_key(slots_diff: &SlotsVec) -> Vec<i32> {
+        let mut values = vec![slots_diff.iter().map(|&val| val as i32).sum::<i32>()];
+
+        for index in (0..MAX_SLOT_LEVEL).rev() {
+            values.push(slots_diff[index] as i32);
+        }
+
+        values
+    }
+
+    pub fn get_best_leftover_slots(
+        avail_slots_lp: &SlotsVec,
+        deco_combs: &[DecorationCombination],
+    ) -> SlotsVec {
+        deco_combs
+            .iter()
+            .map(|deco_comb| {
+                let mut slots_diff = avail_slots_lp - deco_comb.sum_lp;
+
+                CalcVector::promote_subtracted_lp_slots_to_slots(&mut slots_diff);
+
+                slots_diff
+            })
+            .max_by_key(Self::get_leftover_slots_key)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+
+use nohash_hasher::IntMap;
+use serde::{Deserialize, Serialize};
+
+use crate::data::{
+    armor::{ArmorPart, ArmorStat, BaseArmor, SexType},
+    data_manager::DataManager,
+};
+
+use super::{
+    calc_data_manager::{AllRealEquipments, CalcDataManager},
+    calc_equipment::CalcEquipment,
+    constant::{EQUIP_PART_COUNT, STAT_COUNT},
+};
+
+pub type StatValues = [i32; STAT_COUNT];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct MinStats {
+    pub defense: Option<i16>,
+    pub fire_res: Option<i16>,
+    pub water_res: Option<i16>,
+    pub ice_res: Option<i16>,
+    pub elec_res: Option<i16>,
+    pub dragon_res: Option<i16>,
+}
+
+impl MinStats {
+    pub fn is_empty(&self) -> bool {
+        self.to_values().iter().all(|value| value.is_none())
+    }
+
+    pub fn to_values(&self) -> [Option<i32>; STAT_COUNT] {
+        [
+            self.defense,
+            self.fire_res,
+            self.water_res,
+            self.ice_res,
+            self.elec_res,
+            self.dragon_res,
+        ]
+        .map(|value| value.map(|value| value as i32))
+    }
+}
+
+// Best case stats of equipments, which may be replaced by real armors later in the search
+pub struct StatBounds {
+    min_stats: [Option<i32>; STAT_COUNT],
+    is_active: bool,
+
+    part_best_stats: [StatValues; EQUIP_PART_COUNT],
+    slot_best_stats: IntMap<usize, StatValues>,
+    group_best_stats: IntMap<usize, StatValues>,
+}
+
+impl StatBounds {
+    pub fn new(
+        min_stats: &MinStats,
+        dm: &DataManager,
+        cm: &CalcDataManager,
+        all_original_equips: &AllRealEquipments,
+        sex_type: &SexType,
+    ) -> Self {
+        let mut part_best_stats = [[i16::MIN as i32; STAT_COUNT]; EQUIP_PART_COUNT];
+        let mut slot_best_stats = IntMap::default();
+
+        part_best_stats[ArmorPart::Talisman.as_usize()] = [0; STAT_COUNT];
+
+        for (part, part_equips) in all_original_equips.iter().enumerate() {
+            for equip in part_equips.values() {
+                Self::merge_best(
+                    &mut part_best_stats[part],
+                    &Self::get_stat_values(&equip.stats()),
+                );
+            }
+        }
+
+        for part_equips in cm.get_slot_equipments() {
+            for equip in part_equips {
+                if equip.part() == ArmorPart::Talisman.as_usize() {
+                    continue;
+                }
+
+                let mut best_stats = [i16::MIN as i32; STAT_COUNT];
+
+                for armor in dm.get_armors_by_slot(equip.part(), sex_type, equip.id()) {
+                    Self::merge_best(&mut best_stats, &Self::get_stat_values(&armor.stat));
+                }
+
+                slot_best_stats.insert(equip.uid(), best_stats);
+            }
+        }
+
+        Self {
+            min_stats: min_stats.to_values(),
+            is_active: !min_stats.is_empty(),
+            part_best_stats,
+            slot_best_stats,
+            group_best_stats: IntMap::default(),
+        }
+    }
+
+    pub fn get_stat_values(stat: &ArmorStat) -> StatValues {
+        [
+            stat.defense,
+            stat.fire_res,
+            stat.water_res,
+            stat.ice_res,
+            stat.elec_res,
+            stat.dragon_res,
+        ]
+        .map(|value| value as i32)
+    }
+
+    fn merge_best(best_stats: &mut StatValues, stats: &StatValues) {
+        for (best, &value) in best_stats.iter_mut().zip(stats) {
+            *best = (*best).max(value);
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.is_active
+    }
+
+    pub fn get_best_stats(&self, equip: &CalcEquipment) -> StatValues {
+        if equip.is_talisman() {
+            return [0; STAT_COUNT];
+        }
+
+        if BaseArmor::is_empty_armor(equip.id()) {
+            return self.part_best_stats[equip.part()];
+        }
+
+        if let Some(best_stats) = self.slot_best_stats.get(&equip.uid()) {
+            return *best_stats;
+        }
+
+        Self::get_stat_values(&equip.stats())
+    }
+
+    // Equipments hiding less or equal ones can be replaced by them, which may have better stats
+    pub fn set_group_bounds(
+        &mut self,
+        ge_equips_map: &IntMap<usize, Vec<usize>>,
+        cm: &CalcDataManager,
+    ) {
+        if !self.is_active {
+            return;
+        }
+
+        for (&ge_uid, le_uids) in ge_equips_map.iter() {
+            let mut best_stats = self.get_best_stats(cm.get_by_uid(ge_uid));
+
+            for &le_uid in le_uids {
+                Self::merge_best(&mut best_stats, &self.get_best_stats(cm.get_by_uid(le_uid)));
+            }
+
+            self.group_best_stats.insert(ge_uid, best_stats);
+        }
+    }
+
+    pub fn get_group_best_stats(&self, equip: &CalcEquipment) -> StatValues {
+        match self.group_best_stats.get(&equip.uid()) {
+            Some(best_stats) => *best_stats,
+            None => self.get_best_stats(equip),
+        }
+    }
+
+    pub fn is_possible(&self, all_stats: impl Iterator<Item = StatValues>) -> bool {
+        if !self.is_active {
+            return true;
+        }
+
+        let mut sum_stats = [0; STAT_COUNT];
+
+        for stats in all_stats {
+            for (sum, value) in sum_stats.iter_mut().zip(stats) {
+                *sum += value;
+            }
+        }
+
+        self.min_stats
+            .iter()
+            .zip(sum_stats)
+            .all(|(min_stat, sum)| match min_stat {
+                Some(min_stat) => *min_stat <= sum,
+                None => true,
+            })
+    }
+
+    pub fn is_possible_equips(&self, equipments: &[&Arc<CalcEquipment>]) -> bool {
+        self.is_possible(equipments.iter().map(|equip| self.get_best_stats(equip)))
+    }
+}
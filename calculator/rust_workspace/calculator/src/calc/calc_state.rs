@@ -0,0 +1,110 @@
+use std::{pin::pin, sync::Mutex};
+
+use tokio::sync::Notify;
+
+use super::{calc_result::CalculateResult, cancel_token::CancelToken};
+
+#[derive(Default)]
+struct CalcRun {
+    running_id: Option<u64>,
+    last_id: u64,
+    finished_id: u64,
+    cancelled_result: Option<(u64, CalculateResult)>,
+}
+
+// Calculations run one at a time. A cancel only reaches the running calculation,
+// so one sent while idle doesn't cancel the next calculation
+#[derive(Default)]
+pub struct CalcState {
+    cancel_token: CancelToken,
+    run: Mutex<CalcRun>,
+    finished: Notify,
+}
+
+impl CalcState {
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.cancel_token
+    }
+
+    pub async fn start(&self) -> CalcGuard<'_> {
+        loop {
+            // Registered before checking, a finish in between still wakes this up
+            let mut finished = pin!(self.finished.notified());
+            finished.as_mut().enable();
+
+            {
+                let mut run = self.run.lock().unwrap();
+
+                if run.running_id.is_none() {
+                    run.last_id += 1;
+                    run.running_id = Some(run.last_id);
+
+                    self.cancel_token.reset();
+
+                    return CalcGuard {
+                        state: self,
+                        id: run.last_id,
+                    };
+                }
+            }
+
+            finished.await;
+        }
+    }
+
+    // Returns the partial result of the cancelled calculation, if it left one
+    pub async fn cancel(&self) -> Option<CalculateResult> {
+        let id = {
+            let run = self.run.lock().unwrap();
+
+            let id = run.running_id?;
+            self.cancel_token.cancel();
+
+            id
+        };
+
+        loop {
+            let mut finished = pin!(self.finished.notified());
+            finished.as_mut().enable();
+
+            {
+                let mut run = self.run.lock().unwrap();
+
+                if id <= run.finished_id {
+                    return match run.cancelled_result.take() {
+                        Some((result_id, result)) if result_id == id => Some(result),
+                        _ => None,
+                    };
+                }
+            }
+
+            finished.await;
+        }
+    }
+}
+
+pub struct CalcGuard<'a> {
+    state: &'a CalcState,
+    id: u64,
+}
+
+impl CalcGuard<'_> {
+    // Kept for the cancel waiting on it, finished results go back through the command
+    pub fn set_result(&self, result: &CalculateResult) {
+        if self.state.cancel_token.is_cancelled() {
+            self.state.run.lock().unwrap().cancelled_result = Some((self.id, result.clone()));
+        }
+    }
+}
+
+impl Drop for CalcGuard<'_> {
+    fn drop(&mut self) {
+        let mut run = self.state.run.lock().unwrap();
+
+        run.running_id = None;
+        run.finished_id = self.id;
+
+        self.state.cancel_token.reset();
+        self.state.finished.notify_waiters();
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+use crate::data::{armor::Talisman, data_manager::DataManager};
+
+use super::calc_equipment::CalcEquipment;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DominatedTalisman {
+    pub talisman: Talisman,
+    pub dominated_by: Talisman,
+}
+
+pub struct TalismanAnalyzer {}
+
+impl TalismanAnalyzer {
+    // Talismans which can be replaced by another loaded one, safe to melt
+    pub fn get_dominated_talismans(dm: &DataManager) -> Vec<DominatedTalisman> {
+        let talismans = dm.get_talismans();
+
+        let equipments = talismans
+            .iter()
+            .enumerate()
+            .map(|(uid, tali)| CalcEquipment::new_talisman(uid, tali.clone(), dm))
+            .collect::<Vec<_>>();
+
+        let all_dominators = equipments
+            .iter()
+            .map(|equip1| {
+                equipments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, equip2)| Self::is_strictly_le(equip1, equip2, dm))
+                    .map(|(index, _)| index)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        all_dominators
+            .iter()
+            .enumerate()
+            .filter(|(_, dominators)| !dominators.is_empty())
+            .map(|(index, dominators)| {
+                // Prefer a talisman which is not melted itself
+                let dominator = dominators
+                    .iter()
+                    .find(|&&dominator| all_dominators[dominator].is_empty())
+                    .unwrap_or(&dominators[0]);
+
+                DominatedTalisman {
+                    talisman: talismans[index].as_ref().clone(),
+                    dominated_by: talismans[*dominator].as_ref().clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn is_strictly_le(equip1: &CalcEquipment, equip2: &CalcEquipment, dm: &DataManager) -> bool {
+        equip1.is_le_with_decos(equip2, dm) && !equip2.is_le_with_decos(equip1, dm)
+    }
+}
@@ -1,10 +1,11 @@
 use std::{
     cmp::Reverse,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, RwLock},
     time::Instant,
 };
 
+use anyhow::bail;
 use itertools::iproduct;
 use log::{debug, info};
 use nohash_hasher::IntMap;
@@ -15,7 +16,7 @@ use crate::{
         calc_equipment::CalcEquipment,
         calc_equips_iterator::CalcEquipmentsIterator,
         calc_result::CalcResultGenerator,
-        constant::{EQUIP_PART_COUNT, MAX_ANSWER_LENGTH, MAX_SLOT_LEVEL},
+        constant::{EQUIP_PART_COUNT, MAX_SLOT_LEVEL},
     },
     data::{
         armor::{ArmorPart, BaseArmor, SexType},
@@ -26,15 +27,57 @@ use crate::{
 
 use super::{
     calc_data_manager::{AllRealEquipments, CalcDataManager},
+    calc_pareto::ParetoFilter,
     calc_point::CalcPoint,
-    calc_result::CalculateResult,
+    calc_reporter::{CalcProgress, CalcReporter},
+    calc_request::{CalcCursor, CalculateRequest},
+    calc_result::{AdditionalSkillsResult, CalculateResult},
+    calc_sort::AnswerSorter,
+    calc_stat::StatBounds,
     calc_vector::CalcVector,
+    cancel_token::CancelToken,
     deco_combination::DecorationCombination,
     full_equipments::FullEquipments,
     skills::SkillsContainer,
-    types::{EquipmentsArray, PointsVec, SkillSlotCount, SlotsVec},
+    types::{CalcAnswer, EquipmentsArray, PointsVec, SkillSlotCount, SlotsVec},
 };
 
+// Inputs of one search shared by its condition checks and full equipment calculations
+pub struct CalcSearchContext {
+    pub sex_type: SexType,
+    pub selected_skills: SkillsContainer,
+    pub req_uids: Vec<usize>,
+    pub yes_deco_skills: SkillsContainer,
+    pub no_deco_skills: SkillsContainer,
+    pub weapon_slots_lp: SlotsVec,
+    pub free_slots_lp: SlotsVec,
+}
+
+impl CalcSearchContext {
+    pub fn new(request: &CalculateRequest, dm: &DataManager) -> Self {
+        let selected_skills = Calculator::convert_to_skills_container(&request.selected_skills);
+        let req_uids = selected_skills.get_indices();
+
+        let (no_deco_skills, yes_deco_skills) = dm.get_leftover_skills(&selected_skills);
+
+        let (weapon_slots_lp, _, free_slots_lp) = Calculator::get_converted_input_data(
+            &request.weapon_slots,
+            request.free_slots.clone(),
+            dm,
+        );
+
+        Self {
+            sex_type: request.sex_type.clone(),
+            selected_skills,
+            req_uids,
+            yes_deco_skills,
+            no_deco_skills,
+            weapon_slots_lp,
+            free_slots_lp,
+        }
+    }
+}
+
 pub struct Calculator {}
 
 impl Calculator {
@@ -75,8 +118,11 @@ impl Calculator {
         for (uid, level) in multi_req_skills.iter_mut() {
             let decos = dm.get_deco_by_skill_id(uid);
             if *level == 1 || decos.len() == 1 {
+                // Single decoration may have more than one level, e.g. when lower ones are not owned
+                let deco_count = (*level + decos[0].skill_level - 1) / decos[0].skill_level;
+
                 for index in 0..decos[0].slot_size {
-                    avail_slots_lp[index as usize] -= *level;
+                    avail_slots_lp[index as usize] -= deco_count;
                 }
 
                 *level = 0;
@@ -99,6 +145,30 @@ impl Calculator {
         Some((multi_req_skills, avail_slots_lp))
     }
 
+    // Static conditions and decoration combinations of multi level skills
+    fn check_search_conditions(
+        dm: &DataManager,
+        cm: &CalcDataManager,
+        ctx: &CalcSearchContext,
+        equipments: &EquipmentsArray,
+    ) -> Option<(SkillsContainer, SlotsVec)> {
+        let (multi_req_skills, avail_slots_lp) = Self::check_static_conditions(
+            dm,
+            cm,
+            &ctx.weapon_slots_lp,
+            equipments,
+            &ctx.free_slots_lp,
+            &ctx.selected_skills,
+            &ctx.req_uids,
+        )?;
+
+        if !dm.check_possible_deco_combs_lp(&multi_req_skills, &avail_slots_lp) {
+            return None;
+        }
+
+        Some((multi_req_skills, avail_slots_lp))
+    }
+
     pub fn convert_to_skills_container(skills: &IntMap<usize, SkillSlotCount>) -> SkillsContainer {
         let mut ret = SkillsContainer::new();
 
@@ -109,27 +179,7 @@ impl Calculator {
         ret
     }
 
-    fn get_init_calc_data(
-        ori_weapon_slots: &Vec<SkillSlotCount>,
-        selected_skills: &SkillsContainer,
-        free_slots: Vec<SkillSlotCount>,
-        dm: &DataManager,
-    ) -> (SlotsVec, PointsVec, SlotsVec, PointsVec, Vec<usize>) {
-        let (weapon_slots_lp, weapon_points, free_slots_lp) =
-            Self::get_converted_input_data(ori_weapon_slots, free_slots, dm);
-
-        let (req_points, req_uids) = Self::get_req_data(selected_skills, &free_slots_lp, dm);
-
-        (
-            weapon_slots_lp,
-            weapon_points,
-            free_slots_lp,
-            req_points,
-            req_uids,
-        )
-    }
-
-    fn get_converted_input_data(
+    pub fn get_converted_input_data(
         ori_weapon_slots: &Vec<SkillSlotCount>,
         free_slots: Vec<SkillSlotCount>,
         dm: &DataManager,
@@ -144,17 +194,6 @@ impl Calculator {
         (weapon_slots_lp, weapon_points, free_slots_lp)
     }
 
-    fn get_req_data(
-        selected_skills: &SkillsContainer,
-        free_slots_lp: &SlotsVec,
-        dm: &DataManager,
-    ) -> (PointsVec, Vec<usize>) {
-        let req_points = dm.calc_req_point_slots_lp(selected_skills, &free_slots_lp);
-        let req_uids = selected_skills.get_indices();
-
-        (req_points, req_uids)
-    }
-
     fn get_multi_deco_skills(
         dm: &DataManager,
         yes_deco_skills: &SkillsContainer,
@@ -175,17 +214,28 @@ impl Calculator {
     fn get_equipment_containers<'a>(
         cm: &'a CalcDataManager,
         yes_deco_skills: &'a SkillsContainer,
-        sex_type: &'a SexType,
-        include_lte_equips: bool,
+        request: &CalculateRequest,
     ) -> (
         AllRealEquipments<'a>,
         Vec<Vec<&'a Arc<CalcEquipment>>>,
         EquipmentsArray<'a>,
         Vec<&'a Arc<CalcEquipment>>,
     ) {
-        let (all_original_equips, all_equips) =
-            cm.get_all_equipments(&sex_type, include_lte_equips);
-        let all_slot_equips = cm.get_slot_equipments();
+        let (mut all_original_equips, mut all_equips) = cm.get_all_equipments(
+            &request.sex_type,
+            request.include_lte_equips,
+            &request.excluded_equips,
+        );
+        let mut all_slot_equips = cm.get_slot_equipments();
+
+        for (part, equip_id) in request.pinned_equips.iter() {
+            let part = part.as_usize();
+            let pinned_equip = all_original_equips[part].get(equip_id).copied();
+
+            all_original_equips[part].retain(|id, _| id == equip_id);
+            all_equips[part] = pinned_equip.into_iter().collect();
+            all_slot_equips[part].clear();
+        }
         let all_deco_equips =
             CalcDataManager::get_equips_with_deco_skill(&all_equips, &yes_deco_skills);
         let empty_equips = cm.get_empty_equips();
@@ -245,49 +295,78 @@ impl Calculator {
     }
 
     pub fn calculate(
-        ori_weapon_slots: Vec<SkillSlotCount>,
-        selected_skills: IntMap<usize, SkillSlotCount>,
-        free_slots: Vec<SkillSlotCount>,
-        sex_type: SexType,
-        include_lte_equips: bool,
+        request: &CalculateRequest,
         dm: &DataManager,
         cm: &CalcDataManager,
-    ) -> (String, CalculateResult) {
+        cancel_token: &CancelToken,
+        reporter: &dyn CalcReporter,
+    ) -> anyhow::Result<(String, CalculateResult)> {
         let start_time = Instant::now();
 
-        let selected_skills = Self::convert_to_skills_container(&selected_skills);
+        let ori_weapon_slots = &request.weapon_slots;
+        let sex_type = &request.sex_type;
+        let answer_limit = request.answer_limit.max(1);
 
-        let (weapon_slots_lp, weapon_points, free_slots_lp, req_points, req_uids) =
-            Self::get_init_calc_data(&ori_weapon_slots, &selected_skills, free_slots, dm);
+        let ctx = CalcSearchContext::new(request, dm);
+
+        let CalcSearchContext {
+            selected_skills,
+            yes_deco_skills,
+            no_deco_skills,
+            weapon_slots_lp,
+            free_slots_lp,
+            ..
+        } = &ctx;
+
+        let req_points = dm.calc_req_point_slots_lp(selected_skills, free_slots_lp);
+
+        let weapon_points = dm.calc_slot_point_slots_lp(weapon_slots_lp);
 
         let mut ret = String::from("\n");
 
-        let (no_deco_skills, yes_deco_skills) = dm.get_leftover_skills(&selected_skills);
         let has_unique_skill = !no_deco_skills.is_empty();
 
         info!(
             "Skills with yes deco: {:?}",
-            dm.skills_to_ids(&yes_deco_skills)
+            dm.skills_to_ids(yes_deco_skills)
         );
         info!(
             "Skills with no deco: {:?}",
-            dm.skills_to_ids(&no_deco_skills)
+            dm.skills_to_ids(no_deco_skills)
         );
 
         info!("Sex type: {:?}", sex_type);
 
-        let multi_skills = Self::get_multi_deco_skills(dm, &yes_deco_skills);
+        let multi_skills = Self::get_multi_deco_skills(dm, yes_deco_skills);
 
         info!("Multi skills: {:?}", multi_skills.debug(dm));
 
         let (all_original_equips, all_equips, empty_equips, all_deco_slot_equips_flat) =
-            Self::get_equipment_containers(cm, &yes_deco_skills, &sex_type, include_lte_equips);
+            Self::get_equipment_containers(cm, yes_deco_skills, request);
+
+        let mut pinned_parts = [false; EQUIP_PART_COUNT];
+
+        for (part, equip_id) in request.pinned_equips.iter() {
+            pinned_parts[part.as_usize()] = true;
+
+            if all_equips[part.as_usize()].is_empty() {
+                bail!(
+                    "Pinned equipment not found: {} ({})",
+                    equip_id,
+                    part.as_str()
+                );
+            }
+        }
+
+        let mut stat_bounds =
+            StatBounds::new(&request.min_stats, dm, cm, &all_original_equips, sex_type);
 
         let (possible_candidate_vecs, possible_candidate_flat) =
             CalcDataManager::get_possible_unique_equips(
                 &all_equips,
-                &no_deco_skills,
+                no_deco_skills,
                 &empty_equips,
+                &pinned_parts,
             );
 
         for (part, part_equips) in all_equips.iter().enumerate() {
@@ -313,9 +392,48 @@ impl Calculator {
             ),
         );
 
-        let answers = RwLock::new(HashMap::with_capacity(MAX_ANSWER_LENGTH));
+        let answers: RwLock<HashMap<u128, CalcAnswer>> =
+            RwLock::new(HashMap::with_capacity(answer_limit));
+
+        let fingerprint = request.get_fingerprint(dm, cm);
 
-        let is_answer_full = || MAX_ANSWER_LENGTH <= answers.read().unwrap().len();
+        let is_ranked_search = !request.sort_keys.is_empty();
+
+        let (start_index, mut parts_state, mut le_state, prev_answer_uids) = match &request.cursor {
+            Some(_) if is_ranked_search => {
+                bail!("Ranked search goes through every candidate and can not resume from a cursor")
+            }
+            Some(_) if request.pareto_only => {
+                bail!("Pareto search compares every candidate and can not resume from a cursor")
+            }
+            Some(cursor) if cursor.fingerprint != fingerprint => {
+                bail!("Cursor was created for another request or other loaded equipments")
+            }
+            Some(cursor) => (
+                cursor.candidate_index,
+                cursor.parts_state.clone(),
+                cursor.le_state.clone(),
+                cursor.answer_uids.clone(),
+            ),
+            None => (0, None, None, Vec::new()),
+        };
+
+        let prev_answer_ids = prev_answer_uids
+            .iter()
+            .map(FullEquipments::get_full_equip_id_by_uids)
+            .collect::<HashSet<_>>();
+
+        let mut next_cursor = None;
+
+        let sorter = AnswerSorter::new(&request.sort_keys, weapon_slots_lp, free_slots_lp);
+
+        let mut answer_ranks = HashMap::new();
+        let mut worst_answer = None;
+
+        // Answers of the same rank keep the order they were found and streamed in
+        let mut found_orders = HashMap::new();
+
+        let mut pareto_filter = ParetoFilter::new(weapon_slots_lp, free_slots_lp);
 
         info!(
             "Theoretically possible count: {}, equips count: {}",
@@ -325,23 +443,60 @@ impl Calculator {
             possible_candidate_flat.len()
         );
 
-        let candidates =
-            Self::get_candidates(&possible_candidate_vecs, &selected_skills, &no_deco_skills);
+        let mut candidates =
+            Self::get_candidates(&possible_candidate_vecs, selected_skills, no_deco_skills);
 
         info!("Le removed candidates length: {}", candidates.len());
 
+        if stat_bounds.is_active() {
+            candidates.retain(|candidate| stat_bounds.is_possible_equips(candidate));
+
+            info!("Stat removed candidates length: {}", candidates.len());
+        }
+
+        let candidates_count = candidates.len();
+
+        if candidates_count < start_index {
+            bail!(
+                "Cursor candidate index {} is over candidates count {}",
+                start_index,
+                candidates_count
+            );
+        }
+
+        let mut processed_candidates = start_index;
+
+        let report_progress = |processed_candidates: usize, is_done: bool| {
+            reporter.on_progress(&CalcProgress {
+                processed_candidates,
+                candidates_count,
+                all_candidate_len,
+                answers_count: answers.read().unwrap().len(),
+                elapsed: start_time.elapsed().as_secs_f32(),
+                is_done,
+            });
+        };
+
+        report_progress(processed_candidates, false);
+
         // TODO more optimization possible?
         let (ge_parts, ge_equips_map) = CalcDataManager::get_possible_general_part_equips(
             &all_deco_slot_equips_flat,
-            &yes_deco_skills,
+            yes_deco_skills,
             true,
         );
 
-        candidates.into_iter().all(|possible_candidate_vec| {
-            if is_answer_full() {
-                return false;
+        stat_bounds.set_group_bounds(&ge_equips_map, cm);
+
+        'candidate_loop: for (candidate_index, possible_candidate_vec) in
+            candidates.into_iter().enumerate().skip(start_index)
+        {
+            if cancel_token.is_cancelled() {
+                break;
             }
 
+            processed_candidates = candidate_index + 1;
+
             let mut key_equips = Vec::new();
             let mut key_parts = [false; EQUIP_PART_COUNT];
 
@@ -379,36 +534,42 @@ impl Calculator {
                 &empty_equips,
                 dm,
                 cm,
-                &selected_skills,
+                selected_skills,
             );
 
-            parts_iterator.all(|uids| {
-                if is_answer_full() {
-                    return false;
+            if let Some(state) = parts_state.take() {
+                if !parts_iterator.restore_state(&state) {
+                    bail!("Cursor state does not match the candidate equipments");
+                }
+            }
+
+            while let Some(uids) = parts_iterator.next() {
+                if cancel_token.is_cancelled() {
+                    break 'candidate_loop;
                 }
 
                 let equipments = cm.get_full_equipments(&uids);
 
-                let multi_deco_leftovers = Self::check_static_conditions(
-                    dm,
-                    cm,
-                    &weapon_slots_lp,
-                    &equipments,
-                    &free_slots_lp,
-                    &selected_skills,
-                    &req_uids,
-                );
+                let is_possible_stats = stat_bounds.is_possible(equipments.iter().map(|equip| {
+                    if has_unique_skill && key_parts[equip.part()] {
+                        stat_bounds.get_best_stats(equip)
+                    } else {
+                        stat_bounds.get_group_best_stats(equip)
+                    }
+                }));
 
-                if multi_deco_leftovers.is_none() {
-                    return true;
+                if !is_possible_stats {
+                    continue;
                 }
 
-                let (multi_deco_req_skills, avail_slots_lp) = multi_deco_leftovers.unwrap();
+                let multi_deco_leftovers = Self::check_search_conditions(dm, cm, &ctx, &equipments);
 
-                if !dm.check_possible_deco_combs_lp(&multi_deco_req_skills, &avail_slots_lp) {
-                    return true;
+                if multi_deco_leftovers.is_none() {
+                    continue;
                 }
 
+                let (multi_deco_req_skills, avail_slots_lp) = multi_deco_leftovers.unwrap();
+
                 debug!(
                     "Possible candidates: {:?}\nleft skills: {:?}, slots: {:?}",
                     equipments.map(|part| part.id()),
@@ -448,74 +609,147 @@ impl Calculator {
                     &empty_equips,
                     dm,
                     cm,
-                    &selected_skills,
+                    selected_skills,
                 );
 
-                le_iterator.all(|le_uids| {
-                    if is_answer_full() {
-                        return false;
+                if let Some(state) = le_state.take() {
+                    if !le_iterator.restore_state(&state) {
+                        bail!("Cursor state does not match the lesser equipments");
                     }
+                }
 
-                    let le_equips = cm.get_full_equipments(&le_uids);
+                while let Some(le_uids) = le_iterator.next() {
+                    if cancel_token.is_cancelled() {
+                        break 'candidate_loop;
+                    }
 
-                    let multi_deco_leftovers = Self::check_static_conditions(
-                        dm,
-                        cm,
-                        &weapon_slots_lp,
-                        &le_equips,
-                        &free_slots_lp,
-                        &selected_skills,
-                        &req_uids,
-                    );
+                    let le_equips = cm.get_full_equipments(&le_uids);
 
-                    if multi_deco_leftovers.is_none() {
-                        return true;
+                    if !stat_bounds.is_possible_equips(&le_equips) {
+                        continue;
                     }
 
-                    let (multi_deco_req_skills, avail_slots_lp) = multi_deco_leftovers.unwrap();
+                    let multi_deco_leftovers =
+                        Self::check_search_conditions(dm, cm, &ctx, &le_equips);
 
-                    if !dm.check_possible_deco_combs_lp(&multi_deco_req_skills, &avail_slots_lp) {
-                        return true;
+                    if multi_deco_leftovers.is_none() {
+                        continue;
                     }
 
                     {
                         let mut answers = answers.write().unwrap();
 
-                        if MAX_ANSWER_LENGTH <= answers.len() {
-                            return false;
-                        }
+                        let mut local_answers =
+                            Self::calculate_full_equip(dm, &all_original_equips, &ctx, &le_equips);
 
-                        let local_answers = Self::calculate_full_equip(
-                            dm,
-                            &all_original_equips,
-                            &selected_skills,
-                            &free_slots_lp,
-                            &yes_deco_skills,
-                            &weapon_slots_lp,
-                            &le_equips,
-                            &sex_type,
-                        );
+                        local_answers.retain(|answer| stat_bounds.is_possible_equips(&answer.0));
 
                         for local_answer in local_answers {
                             let local_equips_id =
                                 FullEquipments::get_full_equip_id(&local_answer.0);
 
-                            if answers.contains_key(&local_equips_id) {
+                            if answers.contains_key(&local_equips_id)
+                                || prev_answer_ids.contains(&local_equips_id)
+                            {
                                 continue;
                             }
 
-                            if MAX_ANSWER_LENGTH <= answers.len() {
-                                return false;
+                            let pareto_summary = if request.pareto_only {
+                                let summary = pareto_filter.get_summary(&local_answer);
+
+                                if pareto_filter.is_dominated(&summary) {
+                                    continue;
+                                }
+
+                                Some(summary)
+                            } else {
+                                None
+                            };
+
+                            // Current le case is visited again on resume, found answers are skipped by ids
+                            if answer_limit <= answers.len()
+                                && !is_ranked_search
+                                && !request.pareto_only
+                            {
+                                let mut answer_uids = prev_answer_uids.clone();
+
+                                answer_uids.extend(
+                                    answers
+                                        .values()
+                                        .map(|answer| FullEquipments::get_uids_by_part(&answer.0)),
+                                );
+
+                                next_cursor = Some(CalcCursor {
+                                    fingerprint: fingerprint.clone(),
+                                    candidate_index,
+                                    parts_state: Some(parts_iterator.get_state()),
+                                    le_state: Some(le_iterator.get_state()),
+                                    answer_uids,
+                                });
+
+                                break 'candidate_loop;
                             }
 
+                            if is_ranked_search {
+                                let rank = sorter.get_rank(&local_answer);
+
+                                // Pareto search cuts the whole front at the end instead
+                                if answer_limit <= answers.len() && !request.pareto_only {
+                                    let (worst_id, worst_rank) = worst_answer
+                                        .get_or_insert_with(|| Self::get_worst_rank(&answer_ranks));
+
+                                    if *worst_rank <= rank {
+                                        continue;
+                                    }
+
+                                    let worst_id = *worst_id;
+
+                                    answers.remove(&worst_id);
+                                    answer_ranks.remove(&worst_id);
+                                    pareto_filter.remove(&worst_id);
+                                    worst_answer = None;
+                                }
+
+                                answer_ranks.insert(local_equips_id, rank);
+                            }
+
+                            // Streamed answers could be replaced by better ones later in ranked or pareto search
+                            if reporter.is_streaming_answers()
+                                && !is_ranked_search
+                                && !request.pareto_only
+                            {
+                                reporter.on_answer(&CalcResultGenerator::generate_full_equipments(
+                                    dm,
+                                    sex_type,
+                                    ori_weapon_slots,
+                                    weapon_slots_lp,
+                                    free_slots_lp,
+                                    &local_answer,
+                                ));
+                            }
+
+                            // Dominated answers are removed only once the new one is kept
+                            if let Some(summary) = pareto_summary {
+                                for dominated_id in pareto_filter.remove_dominated_by(&summary) {
+                                    answers.remove(&dominated_id);
+                                    answer_ranks.remove(&dominated_id);
+                                    worst_answer = None;
+                                }
+
+                                pareto_filter.insert(local_equips_id, summary);
+                            }
+
+                            found_orders.insert(local_equips_id, found_orders.len());
                             answers.insert(local_equips_id, local_answer);
                         }
                     }
+                }
+            }
 
-                    true
-                })
-            })
-        });
+            report_progress(processed_candidates, false);
+        }
+
+        report_progress(processed_candidates, true);
 
         let answers = answers.read().unwrap();
 
@@ -529,6 +763,10 @@ impl Calculator {
             }
         }
 
+        if cancel_token.is_cancelled() {
+            Self::info(&mut ret, "Calculation cancelled");
+        }
+
         Self::info(
             &mut ret,
             &format!(
@@ -540,37 +778,59 @@ impl Calculator {
         );
         info!("{}", ret);
 
-        let calculate_result = CalcResultGenerator::generate(
+        let mut sorted_answers = answers.iter().collect::<Vec<_>>();
+        sorted_answers
+            .sort_by_cached_key(|(id, answer)| (sorter.get_rank(answer), found_orders[*id]));
+
+        // Whole front is kept while searching, so the page is its best answers whatever the search order
+        if request.pareto_only && answer_limit < sorted_answers.len() {
+            Self::info(
+                &mut ret,
+                &format!(
+                    "Pareto answers cut to the answer limit: {} -> {}",
+                    sorted_answers.len(),
+                    answer_limit
+                ),
+            );
+
+            sorted_answers.truncate(answer_limit);
+        }
+
+        let sorted_answers = sorted_answers
+            .into_iter()
+            .map(|(_, answer)| answer.clone())
+            .collect::<Vec<_>>();
+
+        let mut calculate_result = CalcResultGenerator::generate(
             dm,
-            &sex_type,
-            &ori_weapon_slots,
-            &weapon_slots_lp,
-            &free_slots_lp,
-            &answers
-                .iter()
-                .map(|(_, equips)| equips.clone())
-                .collect::<Vec<_>>(),
+            sex_type,
+            ori_weapon_slots,
+            weapon_slots_lp,
+            free_slots_lp,
+            &sorted_answers,
             start_time.elapsed(),
         );
 
-        (ret, calculate_result)
+        calculate_result.next_cursor = next_cursor;
+
+        Ok((ret, calculate_result))
+    }
+
+    fn get_worst_rank(answer_ranks: &HashMap<u128, Vec<i32>>) -> (u128, Vec<i32>) {
+        answer_ranks
+            .iter()
+            .max_by(|(_, rank1), (_, rank2)| rank1.cmp(rank2))
+            .map(|(&id, rank)| (id, rank.clone()))
+            .unwrap()
     }
 
     pub fn calculate_additional_skills(
-        ori_weapon_slots: Vec<SkillSlotCount>,
-        selected_skills: IntMap<usize, SkillSlotCount>,
-        free_slots: Vec<SkillSlotCount>,
-        sex_type: SexType,
-        include_lte_equips: bool,
+        request: &CalculateRequest,
         dm: &DataManager,
         cm: &CalcDataManager,
-    ) -> (
-        String,
-        usize,
-        f32,
-        HashMap<String, (SkillSlotCount, SkillSlotCount)>,
-        Vec<SkillSlotCount>,
-    ) {
+        cancel_token: &CancelToken,
+        reporter: &dyn CalcReporter,
+    ) -> anyhow::Result<(String, AdditionalSkillsResult)> {
         let start_time = Instant::now();
         let mut log = String::new();
 
@@ -582,29 +842,17 @@ impl Calculator {
 
         Self::info(&mut log, &format!("Num_thread: {}", num_thread));
 
-        let (_, calc_result) = Self::calculate(
-            ori_weapon_slots.clone(),
-            selected_skills.clone(),
-            free_slots.clone(),
-            sex_type.clone(),
-            include_lte_equips,
-            dm,
-            cm,
-        );
-
-        if MAX_ANSWER_LENGTH <= calc_result.full_equipments.len() {
-            let total_time = start_time.elapsed().as_secs_f32();
+        let (_, calc_result) = Self::calculate(request, dm, cm, cancel_token, reporter)?;
 
-            return (
-                log,
-                calc_result.full_equipments.len(),
-                total_time,
-                HashMap::new(),
-                SlotsVec::default().data.0[0].to_vec(),
+        // Skills of the answers found so far, the next page adds the others
+        if calc_result.next_cursor.is_some() {
+            Self::info(
+                &mut log,
+                &format!("Answer limit ({}) reached", request.answer_limit),
             );
         }
 
-        let selected_skills = Self::convert_to_skills_container(&selected_skills);
+        let selected_skills = Self::convert_to_skills_container(&request.selected_skills);
 
         let skills = dm.get_skills();
 
@@ -627,6 +875,10 @@ impl Calculator {
             );
 
             calc_result.full_equipments.iter().for_each(|equip| {
+                if cancel_token.is_cancelled() {
+                    return;
+                }
+
                 for deco_comb in equip.deco_combs.iter() {
                     let leftover_slots_sum_lp = CalcVector::convert_to_lp_slots(
                         &SlotsVec::from_vec(deco_comb.leftover_slots_sum.clone()),
@@ -642,32 +894,32 @@ impl Calculator {
                 let mut all_raw_slots_lp = SlotsVec::from_vec(equip.total_raw_slots.clone());
                 CalcVector::convert_to_lp_slots_mut(&mut all_raw_slots_lp);
 
-                let equip_only_skills = RwLock::new(SkillsContainer::new());
+                let mut equip_only_skills = SkillsContainer::new();
 
-                {
-                    let mut equip_only_skills = equip_only_skills.write().unwrap();
-                    for armor in equip.armors.values() {
-                        for (skill_id, &level) in armor.skills.iter() {
-                            let skill_uid = dm.get_skill_uid(skill_id);
-
-                            equip_only_skills.add_level(skill_uid, level);
-                        }
-                    }
-
-                    for (skill_id, &level) in equip.talisman.skills.iter() {
+                for armor in equip.armors.values() {
+                    for (skill_id, &level) in armor.skills.iter() {
                         let skill_uid = dm.get_skill_uid(skill_id);
 
                         equip_only_skills.add_level(skill_uid, level);
                     }
                 }
 
+                for (skill_id, &level) in equip.talisman.skills.iter() {
+                    let skill_uid = dm.get_skill_uid(skill_id);
+
+                    equip_only_skills.add_level(skill_uid, level);
+                }
+
                 let new_req_skills = RwLock::new(selected_skills.clone());
 
                 debug!("Equip deco combs length: {}", equip.deco_combs.len());
 
                 skills.par_iter().for_each(|skill| {
+                    if cancel_token.is_cancelled() {
+                        return;
+                    }
+
                     let new_req_skills = new_req_skills.read().unwrap().clone();
-                    let equip_only_skills = equip_only_skills.read().unwrap().clone();
 
                     let skill_uid = dm.get_skill_uid(&skill.id);
                     let is_unique = dm.get_deco_by_skill_id(skill_uid).is_empty();
@@ -723,31 +975,47 @@ impl Calculator {
         let possible_slots = possible_slots.read().unwrap();
         let possible_slots = possible_slots.data.0[0].to_vec();
 
-        (
+        Ok((
             log,
-            calc_result.full_equipments.len(),
-            total_time,
-            possible_skills,
-            possible_slots,
-        )
+            AdditionalSkillsResult {
+                equipments_count: calc_result.full_equipments.len(),
+                calc_time: total_time,
+                skills: possible_skills,
+                slots: possible_slots,
+                next_cursor: calc_result.next_cursor,
+            },
+        ))
     }
 
     pub fn calculate_full_equip<'a>(
         dm: &DataManager,
         all_original_equips: &'a [HashMap<String, &Arc<CalcEquipment>>],
-        req_skills: &SkillsContainer,
-        req_slots_lp: &SlotsVec,
-        yes_deco_skills: &SkillsContainer,
-        weapon_slots_lp: &SlotsVec,
+        ctx: &CalcSearchContext,
         equipments: &EquipmentsArray,
-        sex_type: &SexType,
-    ) -> Vec<(
-        EquipmentsArray<'a>,
-        Vec<DecorationCombination>,
-        Vec<SkillsContainer>,
-    )> {
+    ) -> Vec<CalcAnswer<'a>> {
+        let CalcSearchContext {
+            sex_type,
+            selected_skills: req_skills,
+            yes_deco_skills,
+            no_deco_skills,
+            weapon_slots_lp,
+            free_slots_lp: req_slots_lp,
+            ..
+        } = ctx;
+
         let avail_slots_lp =
             FullEquipments::calculate_slots_lp(weapon_slots_lp, equipments) - req_slots_lp;
+
+        let mut left_skills = req_skills.clone();
+        FullEquipments::subtract_skills(equipments, &mut left_skills);
+
+        if no_deco_skills
+            .iter()
+            .any(|(uid, _)| left_skills.contains(uid))
+        {
+            return Vec::new();
+        }
+
         let mut yes_deco_skills = yes_deco_skills.clone();
 
         FullEquipments::subtract_skills(equipments, &mut yes_deco_skills);
@@ -842,8 +1110,9 @@ impl Calculator {
                     let talis_by_slot = dm.get_talismans_by_slot(equip_id);
 
                     for base_tali in talis_by_slot {
-                        let box_tali = all_original_equips[part][base_tali.id()];
-                        part_real_equips.push(box_tali);
+                        if let Some(&box_tali) = all_original_equips[part].get(base_tali.id()) {
+                            part_real_equips.push(box_tali);
+                        }
                     }
                 } else if is_empty_equip {
                     part_real_equips.append(
@@ -856,12 +1125,15 @@ impl Calculator {
                     let armors_by_slot = dm.get_armors_by_slot(part, sex_type, equip_id);
 
                     for base_armor in armors_by_slot {
-                        let box_armor = all_original_equips[part][base_armor.id()];
-
-                        part_real_equips.push(box_armor);
+                        if let Some(&box_armor) = all_original_equips[part].get(base_armor.id()) {
+                            part_real_equips.push(box_armor);
+                        }
                     }
                 }
 
+                // Same answers are cut by the answer limit on every run
+                part_real_equips.sort_by(|equip1, equip2| equip1.id().cmp(equip2.id()));
+
                 all_real_armors_case *= part_real_equips.len();
                 real_armors.push(part_real_equips);
             } else {
@@ -1,5 +1,7 @@
 pub const MAX_ANSWER_LENGTH: usize = 200;
 
 pub const MAX_SLOT_LEVEL: usize = 4;
-pub const SKILLS_COUNT: usize = 147;
+// Room for skills added by title updates, loaded skill data is checked against it
+pub const MAX_SKILLS_COUNT: usize = 192;
 pub const EQUIP_PART_COUNT: usize = 6;
+pub const STAT_COUNT: usize = 6;
@@ -18,7 +18,7 @@ use crate::{
 
 use super::{
     calc_vector::CalcVector,
-    constant::{MAX_SLOT_LEVEL, SKILLS_COUNT},
+    constant::{MAX_SKILLS_COUNT, MAX_SLOT_LEVEL},
     types::SlotsVec,
     types::{SkillSlotCount, SkillsTuple},
 };
@@ -41,7 +41,12 @@ pub struct DecorationCombination {
 }
 
 impl DecorationCombinations {
-    pub fn new(decos_by_skill: &[Vec<Decoration>], skills: &[Skill]) -> DecorationCombinations {
+    // Decorations missing in the inventory are treated as unlimited
+    pub fn new(
+        decos_by_skill: &[Vec<Decoration>],
+        skills: &[Skill],
+        deco_inventory: &HashMap<String, SkillSlotCount>,
+    ) -> DecorationCombinations {
         let mut temp_combs = IntMap::default();
 
         for (uid, decos) in decos_by_skill.iter().enumerate() {
@@ -52,6 +57,12 @@ impl DecorationCombinations {
             let skill = &skills[uid];
             let max_level = skill.max_level;
 
+            let owned_counts = decos
+                .iter()
+                .map(|deco| deco_inventory.get(&deco.id).copied())
+                .collect::<Vec<_>>();
+            let is_limited = owned_counts.iter().any(|count| count.is_some());
+
             if decos.len() == 1 {
                 let mut skill_combs = Vec::new();
 
@@ -64,7 +75,12 @@ impl DecorationCombinations {
                         minimum_deco_count -= 1;
                     }
 
-                    skill_combs.push(vec![vec![minimum_deco_count]]);
+                    match owned_counts[0] {
+                        Some(owned_count) if owned_count < minimum_deco_count => {
+                            skill_combs.push(vec![])
+                        }
+                        _ => skill_combs.push(vec![vec![minimum_deco_count]]),
+                    }
                 }
 
                 temp_combs.insert(uid, skill_combs);
@@ -74,13 +90,17 @@ impl DecorationCombinations {
                 let mut max_deco_counts = Vec::new();
                 let mut init_case = Vec::new();
 
-                for deco in decos {
+                for (deco, owned_count) in decos.iter().zip(&owned_counts) {
                     let mut max_required = max_level / deco.skill_level + 1;
 
                     if max_level % deco.skill_level == 0 {
                         max_required -= 1;
                     }
 
+                    if let Some(owned_count) = owned_count {
+                        max_required = max_required.min(*owned_count);
+                    }
+
                     max_deco_counts.push(max_required);
                     init_case.push(0);
                 }
@@ -96,16 +116,13 @@ impl DecorationCombinations {
                         let deco_temp_combs = skill_temp_combs.clone();
 
                         for temp_comb in &deco_temp_combs {
-                            for count in (1..max_deco_count + 1).rev() {
-                                let mut cur_level_sum: SkillSlotCount = temp_comb
-                                    .iter()
-                                    .enumerate()
-                                    .map(|(prev_level_index, count)| {
-                                        (prev_level_index + 1) as SkillSlotCount * count
-                                    })
-                                    .sum();
+                            // Decoration levels may skip some, e.g. Lv1 and Lv3 only
+                            let temp_level_sum: SkillSlotCount = izip!(temp_comb, decos)
+                                .map(|(count, prev_deco)| count * prev_deco.skill_level)
+                                .sum();
 
-                                cur_level_sum += count * deco.skill_level;
+                            for count in (1..max_deco_count + 1).rev() {
+                                let cur_level_sum = temp_level_sum + count * deco.skill_level;
 
                                 let mut next_temp_comb = temp_comb.clone();
                                 next_temp_comb[slot_size_index] = count;
@@ -113,9 +130,13 @@ impl DecorationCombinations {
                                 if req_level <= cur_level_sum {
                                     let mut has_better_slot_answer = false;
 
-                                    for lower_deco in decos.iter().take(slot_size_index) {
-                                        let mut lower_level_sum = temp_comb.iter().sum();
-                                        lower_level_sum += count * lower_deco.skill_level;
+                                    // Smaller decorations may run out, inferior ones are removed below
+                                    let lower_decos_count =
+                                        if is_limited { 0 } else { slot_size_index };
+
+                                    for lower_deco in decos.iter().take(lower_decos_count) {
+                                        let lower_level_sum =
+                                            temp_level_sum + count * lower_deco.skill_level;
 
                                         if req_level <= lower_level_sum {
                                             has_better_slot_answer = true;
@@ -140,23 +161,23 @@ impl DecorationCombinations {
 
         for (_, combs) in temp_combs.iter_mut() {
             for deco_size_combs in combs {
-                let mut remove_comb_indices = Vec::new();
-
-                'remove_loop: for index1 in 0..deco_size_combs.len() - 1 {
-                    //  TODO: Bug?
-                    let deco_comb1 = &deco_size_combs[index1];
+                if deco_size_combs.is_empty() {
+                    continue;
+                }
 
-                    for deco_comb2 in deco_size_combs.iter().skip(index1 + 1) {
-                        let mut is_inferior = true;
+                let mut remove_comb_indices = Vec::new();
 
-                        for (count1, count2) in izip!(deco_comb1, deco_comb2) {
-                            if count1 < count2 {
-                                is_inferior = false;
-                                break;
-                            }
+                'remove_loop: for (index1, deco_comb1) in deco_size_combs.iter().enumerate() {
+                    for (index2, deco_comb2) in deco_size_combs.iter().enumerate() {
+                        if index1 == index2 {
+                            continue;
                         }
 
-                        if is_inferior {
+                        let is_inferior =
+                            izip!(deco_comb1, deco_comb2).all(|(count1, count2)| count2 <= count1);
+
+                        // Only the last one of equal combinations is kept
+                        if is_inferior && (deco_comb1 != deco_comb2 || index1 < index2) {
                             remove_comb_indices.push(index1);
                             continue 'remove_loop;
                         }
@@ -234,11 +255,11 @@ impl DecorationCombinations {
             })
             .collect::<HashMap<usize, Vec<Vec<SlotsVec>>>>();
 
-        let mut combinations_lp = Vec::with_capacity(SKILLS_COUNT);
-        let mut combinations_lp_mins = Vec::with_capacity(SKILLS_COUNT);
+        let mut combinations_lp = Vec::with_capacity(MAX_SKILLS_COUNT);
+        let mut combinations_lp_mins = Vec::with_capacity(MAX_SKILLS_COUNT);
 
-        combinations_lp.resize(SKILLS_COUNT, Vec::new());
-        combinations_lp_mins.resize(SKILLS_COUNT, Vec::new());
+        combinations_lp.resize(MAX_SKILLS_COUNT, Vec::new());
+        combinations_lp_mins.resize(MAX_SKILLS_COUNT, Vec::new());
 
         for (uid, slot_combs) in existing_combinations.into_iter() {
             let mut min_vals = Vec::new();
@@ -253,6 +274,11 @@ impl DecorationCombinations {
                     }
                 }
 
+                // Level not reachable with owned decorations, checked with the combinations
+                if level_combs.is_empty() {
+                    min_val.fill(0);
+                }
+
                 min_vals.push(min_val);
             }
 
@@ -260,6 +286,14 @@ impl DecorationCombinations {
             combinations_lp_mins[uid] = min_vals;
         }
 
+        Self::from_lp_tables(combinations_lp, combinations_lp_mins)
+    }
+
+    // Tables already built, e.g. loaded from a file, caches start empty
+    pub fn from_lp_tables(
+        combinations_lp: Vec<Vec<Vec<SlotsVec>>>,
+        combinations_lp_mins: Vec<Vec<SlotsVec>>,
+    ) -> DecorationCombinations {
         let combinations = combinations_lp
             .iter()
             .map(|slot_combs| {
@@ -289,6 +323,12 @@ impl DecorationCombinations {
         }
     }
 
+    pub fn get_caches_len(&self) -> usize {
+        self.caches.read().unwrap().len()
+            + self.sum_caches.read().unwrap().len()
+            + self.full_caches.read().unwrap().len()
+    }
+
     pub fn debug(&self, dm: &DataManager) {
         for (uid, combs) in self.combinations.iter().enumerate() {
             for (level_index, level_combs) in combs.iter().enumerate() {
@@ -315,6 +355,10 @@ impl DecorationCombinations {
     ) -> Vec<SlotsVec> {
         let all_combs_count = self.get_total_combs_count(req_skills);
 
+        if all_combs_count == 0 {
+            return Vec::new();
+        }
+
         let mut all_possible_combs1 = Vec::with_capacity(all_combs_count);
         let mut all_possible_combs2 = Vec::with_capacity(all_combs_count);
 
@@ -404,6 +448,11 @@ impl DecorationCombinations {
         }
 
         let all_combs_count = self.get_total_combs_count(req_skills);
+
+        if all_combs_count == 0 {
+            return Arc::new(Vec::new());
+        }
+
         let mut all_possible_combs = Vec::with_capacity(all_combs_count);
 
         let (req_list, mut level_indices) = self.get_iter_init_data(req_skills);
@@ -470,6 +519,10 @@ impl DecorationCombinations {
             all_combs_lp.push(combs);
         }
 
+        if all_combs_lp.iter().any(|combs| combs.is_empty()) {
+            return false;
+        }
+
         let mut sum_lp = SlotsVec::default();
 
         loop {
@@ -115,17 +115,24 @@ impl<'a> FullEquipments<'a> {
     }
 
     pub fn get_full_equip_id(equipments: &EquipmentsArray) -> u128 {
-        let mut ret = 0;
+        Self::get_full_equip_id_by_uids(&Self::get_uids_by_part(equipments))
+    }
 
-        let mut equips_by_part: [Option<&Arc<CalcEquipment>>; EQUIP_PART_COUNT] =
-            Default::default();
+    pub fn get_uids_by_part(equipments: &EquipmentsArray) -> [usize; EQUIP_PART_COUNT] {
+        let mut uids = [0; EQUIP_PART_COUNT];
 
         for &equip in equipments {
-            equips_by_part[equip.part()] = Some(equip);
+            uids[equip.part()] = equip.uid();
         }
 
-        for (part, equip) in equips_by_part.iter().enumerate() {
-            ret += (equip.unwrap().uid() + 1) as u128 * 10000_u128.pow(part as u32);
+        uids
+    }
+
+    pub fn get_full_equip_id_by_uids(uids_by_part: &[usize; EQUIP_PART_COUNT]) -> u128 {
+        let mut ret = 0;
+
+        for (part, &uid) in uids_by_part.iter().enumerate() {
+            ret += (uid + 1) as u128 * 10000_u128.pow(part as u32);
         }
 
         ret
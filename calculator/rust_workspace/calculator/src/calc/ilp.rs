@@ -0,0 +1,569 @@
+use std::fmt::{self, Display};
+
+use super::cancel_token::CancelToken;
+
+const EPS: f64 = 1e-9;
+const FEASIBLE_EPS: f64 = 1e-7;
+const INTEGER_EPS: f64 = 1e-6;
+
+// Dantzig's rule is faster, Bland's rule takes over after this many pivots to avoid cycling
+const BLAND_RULE_PIVOTS: usize = 5_000;
+const MAX_PIVOTS: usize = 50_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowKind {
+    Le,
+    Ge,
+    Eq,
+}
+
+#[derive(Clone, Debug)]
+struct IlpRow {
+    coefs: Vec<(usize, f64)>,
+    kind: RowKind,
+    rhs: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct IlpSolution {
+    pub objective: f64,
+    pub values: Vec<f64>,
+}
+
+impl IlpSolution {
+    pub fn get_int(&self, var: usize) -> i32 {
+        self.values[var].round() as i32
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IlpError {
+    // A relaxation took more simplex pivots than the limit, so its result is unknown
+    PivotLimit(usize),
+}
+
+impl Display for IlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PivotLimit(max_pivots) => {
+                write!(f, "LP relaxation not solved within {} pivots", max_pivots)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IlpError {}
+
+type LpRow = (Vec<(usize, f64)>, RowKind, f64);
+
+enum LpResult {
+    Optimal(IlpSolution),
+    Infeasible,
+    Unbounded,
+    PivotLimit,
+}
+
+enum RunResult {
+    Optimal,
+    Unbounded,
+    PivotLimit,
+}
+
+// Minimizes a linear objective over non negative variables, integer ones are found with branch and bound
+#[derive(Clone, Debug)]
+pub struct IlpModel {
+    costs: Vec<f64>,
+    lowers: Vec<f64>,
+    uppers: Vec<Option<f64>>,
+    is_integers: Vec<bool>,
+    rows: Vec<IlpRow>,
+    max_pivots: usize,
+}
+
+impl Default for IlpModel {
+    fn default() -> Self {
+        Self {
+            costs: Vec::new(),
+            lowers: Vec::new(),
+            uppers: Vec::new(),
+            is_integers: Vec::new(),
+            rows: Vec::new(),
+            max_pivots: MAX_PIVOTS,
+        }
+    }
+}
+
+impl IlpModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_max_pivots(&mut self, max_pivots: usize) {
+        self.max_pivots = max_pivots;
+    }
+
+    pub fn add_var(&mut self, cost: f64, upper: Option<f64>, is_integer: bool) -> usize {
+        self.costs.push(cost);
+        self.lowers.push(0.0);
+        self.uppers.push(upper);
+        self.is_integers.push(is_integer);
+
+        self.costs.len() - 1
+    }
+
+    pub fn add_row(&mut self, coefs: Vec<(usize, f64)>, kind: RowKind, rhs: f64) {
+        let coefs = coefs.into_iter().filter(|&(_, coef)| coef != 0.0).collect();
+
+        self.rows.push(IlpRow { coefs, kind, rhs });
+    }
+
+    pub fn vars_len(&self) -> usize {
+        self.costs.len()
+    }
+
+    pub fn rows_len(&self) -> usize {
+        self.rows.len()
+    }
+
+    // Depth first search, nodes which can't beat the best solution found so far are pruned.
+    // None when infeasible or cancelled
+    pub fn solve(&self, cancel_token: &CancelToken) -> Result<Option<IlpSolution>, IlpError> {
+        let is_integral_objective = self
+            .costs
+            .iter()
+            .zip(self.is_integers.iter())
+            .all(|(&cost, &is_integer)| cost == 0.0 || (is_integer && cost.fract() == 0.0));
+
+        let mut best: Option<IlpSolution> = None;
+        let mut nodes = vec![(self.lowers.clone(), self.uppers.clone())];
+
+        while let Some((lowers, uppers)) = nodes.pop() {
+            if cancel_token.is_cancelled() {
+                return Ok(None);
+            }
+
+            let solution = match self.solve_lp(&lowers, &uppers) {
+                LpResult::Optimal(solution) => solution,
+                LpResult::Infeasible | LpResult::Unbounded => continue,
+                LpResult::PivotLimit => return Err(IlpError::PivotLimit(self.max_pivots)),
+            };
+
+            if let Some(best) = &best {
+                let threshold = if is_integral_objective {
+                    best.objective - 1.0 + INTEGER_EPS
+                } else {
+                    best.objective - INTEGER_EPS
+                };
+
+                if threshold < solution.objective {
+                    continue;
+                }
+            }
+
+            let branch_var = (0..self.vars_len())
+                .filter(|&var| self.is_integers[var])
+                .map(|var| {
+                    let value = solution.values[var];
+
+                    (var, (value - value.round()).abs())
+                })
+                .filter(|&(_, frac)| INTEGER_EPS < frac)
+                .max_by(|(_, frac1), (_, frac2)| frac1.partial_cmp(frac2).unwrap());
+
+            let (var, _) = match branch_var {
+                Some(branch_var) => branch_var,
+                None => {
+                    best = Some(self.round_solution(solution));
+                    continue;
+                }
+            };
+
+            let value = solution.values[var];
+
+            let mut down_uppers = uppers.clone();
+            down_uppers[var] = Some(value.floor());
+
+            let mut up_lowers = lowers.clone();
+            up_lowers[var] = value.ceil();
+
+            // Rounding up is visited first, which fixes one equipment of a part
+            nodes.push((lowers, down_uppers));
+            nodes.push((up_lowers, uppers));
+        }
+
+        Ok(best)
+    }
+
+    fn round_solution(&self, mut solution: IlpSolution) -> IlpSolution {
+        for (value, &is_integer) in solution.values.iter_mut().zip(self.is_integers.iter()) {
+            if is_integer {
+                *value = value.round();
+            }
+        }
+
+        solution.objective = self
+            .costs
+            .iter()
+            .zip(solution.values.iter())
+            .map(|(cost, value)| cost * value)
+            .sum();
+
+        solution
+    }
+
+    // Two phase simplex on a dense tableau, variables are shifted by their lower bounds
+    fn solve_lp(&self, lowers: &[f64], uppers: &[Option<f64>]) -> LpResult {
+        let vars_len = self.vars_len();
+
+        if (0..vars_len).any(|var| matches!(uppers[var], Some(upper) if upper < lowers[var] - EPS))
+        {
+            return LpResult::Infeasible;
+        }
+
+        // Fixed variables are constants and don't get a column
+        let mut columns = vec![None; vars_len];
+        let mut column_vars = Vec::new();
+
+        for var in 0..vars_len {
+            if uppers[var] != Some(lowers[var]) {
+                columns[var] = Some(column_vars.len());
+                column_vars.push(var);
+            }
+        }
+
+        let mut rows = Vec::with_capacity(self.rows.len() + vars_len);
+
+        for row in self.rows.iter() {
+            let mut coefs = Vec::with_capacity(row.coefs.len());
+            let mut rhs = row.rhs;
+
+            for &(var, coef) in row.coefs.iter() {
+                rhs -= coef * lowers[var];
+
+                if let Some(column) = columns[var] {
+                    coefs.push((column, coef));
+                }
+            }
+
+            if coefs.is_empty() {
+                let is_possible = match row.kind {
+                    RowKind::Le => -FEASIBLE_EPS <= rhs,
+                    RowKind::Ge => rhs <= FEASIBLE_EPS,
+                    RowKind::Eq => rhs.abs() <= FEASIBLE_EPS,
+                };
+
+                if !is_possible {
+                    return LpResult::Infeasible;
+                }
+
+                continue;
+            }
+
+            rows.push((coefs, row.kind, rhs));
+        }
+
+        for (column, &var) in column_vars.iter().enumerate() {
+            if let Some(upper) = uppers[var] {
+                rows.push((vec![(column, 1.0)], RowKind::Le, upper - lowers[var]));
+            }
+        }
+
+        let costs = column_vars
+            .iter()
+            .map(|&var| self.costs[var])
+            .collect::<Vec<_>>();
+
+        let constant = (0..vars_len)
+            .map(|var| self.costs[var] * lowers[var])
+            .sum::<f64>();
+
+        let mut tableau = Tableau::new(column_vars.len(), &rows, self.max_pivots);
+
+        let column_values = match tableau.solve(&costs) {
+            LpResult::Optimal(solution) => solution.values,
+            result => return result,
+        };
+
+        let mut values = lowers.to_vec();
+
+        for (column, &var) in column_vars.iter().enumerate() {
+            values[var] += column_values[column];
+        }
+
+        let objective = constant
+            + costs
+                .iter()
+                .zip(column_values.iter())
+                .map(|(cost, value)| cost * value)
+                .sum::<f64>();
+
+        LpResult::Optimal(IlpSolution { objective, values })
+    }
+}
+
+struct Tableau {
+    // Each row ends with its right hand side
+    rows: Vec<Vec<f64>>,
+    basis: Vec<usize>,
+
+    vars_len: usize,
+    artificial_start: usize,
+    columns_len: usize,
+    max_pivots: usize,
+}
+
+impl Tableau {
+    // Rows are flipped to non negative right hand sides, then get a slack and maybe an artificial variable
+    fn new(vars_len: usize, rows: &[LpRow], max_pivots: usize) -> Self {
+        let slacks_len = rows
+            .iter()
+            .filter(|(_, kind, _)| *kind != RowKind::Eq)
+            .count();
+
+        let artificial_start = vars_len + slacks_len;
+        let columns_len = artificial_start + rows.len();
+
+        let mut tableau_rows = Vec::with_capacity(rows.len());
+        let mut basis = Vec::with_capacity(rows.len());
+        let mut slack = vars_len;
+
+        for (index, (coefs, kind, rhs)) in rows.iter().enumerate() {
+            let mut row = vec![0.0; columns_len + 1];
+            let sign = if *rhs < 0.0 { -1.0 } else { 1.0 };
+
+            for &(column, coef) in coefs.iter() {
+                row[column] += sign * coef;
+            }
+
+            row[columns_len] = sign * rhs;
+
+            let slack_coef = match kind {
+                RowKind::Le => Some(sign),
+                RowKind::Ge => Some(-sign),
+                RowKind::Eq => None,
+            };
+
+            let mut basic = None;
+
+            if let Some(slack_coef) = slack_coef {
+                row[slack] = slack_coef;
+
+                if 0.0 < slack_coef {
+                    basic = Some(slack);
+                }
+
+                slack += 1;
+            }
+
+            // Rows without a positive slack start from an artificial variable
+            let basic = basic.unwrap_or_else(|| {
+                row[artificial_start + index] = 1.0;
+
+                artificial_start + index
+            });
+
+            tableau_rows.push(row);
+            basis.push(basic);
+        }
+
+        Self {
+            rows: tableau_rows,
+            basis,
+            vars_len,
+            artificial_start,
+            columns_len,
+            max_pivots,
+        }
+    }
+
+    fn solve(&mut self, costs: &[f64]) -> LpResult {
+        // Phase 1 minimizes the sum of artificial variables
+        let mut objective = vec![0.0; self.columns_len + 1];
+
+        for index in 0..self.rows.len() {
+            if self.artificial_start <= self.basis[index] {
+                for (value, row_value) in objective.iter_mut().zip(self.rows[index].iter()) {
+                    *value -= row_value;
+                }
+
+                objective[self.basis[index]] = 0.0;
+            }
+        }
+
+        // Sum of artificial variables can't go below 0, so phase 1 is never unbounded
+        match self.run(&mut objective, self.columns_len) {
+            RunResult::Optimal => {}
+            RunResult::Unbounded => return LpResult::Infeasible,
+            RunResult::PivotLimit => return LpResult::PivotLimit,
+        }
+
+        if FEASIBLE_EPS < -objective[self.columns_len] {
+            return LpResult::Infeasible;
+        }
+
+        self.remove_artificials();
+
+        // Phase 2 on the original costs
+        let mut objective = vec![0.0; self.columns_len + 1];
+        objective[..self.vars_len].copy_from_slice(costs);
+
+        for index in 0..self.rows.len() {
+            let cost = objective[self.basis[index]];
+
+            if cost != 0.0 {
+                for (value, row_value) in objective.iter_mut().zip(self.rows[index].iter()) {
+                    *value -= cost * row_value;
+                }
+            }
+        }
+
+        match self.run(&mut objective, self.artificial_start) {
+            RunResult::Optimal => {}
+            RunResult::Unbounded => return LpResult::Unbounded,
+            RunResult::PivotLimit => return LpResult::PivotLimit,
+        }
+
+        let mut values = vec![0.0; self.vars_len];
+
+        for (index, &basic) in self.basis.iter().enumerate() {
+            if basic < self.vars_len {
+                values[basic] = self.rows[index][self.columns_len];
+            }
+        }
+
+        LpResult::Optimal(IlpSolution {
+            objective: -objective[self.columns_len],
+            values,
+        })
+    }
+
+    fn run(&mut self, objective: &mut [f64], entering_len: usize) -> RunResult {
+        let mut pivots = 0;
+
+        loop {
+            let is_bland = BLAND_RULE_PIVOTS <= pivots;
+
+            let mut entering = None;
+            let mut min_cost = -EPS;
+
+            for (column, &cost) in objective[..entering_len].iter().enumerate() {
+                if cost < min_cost {
+                    entering = Some(column);
+
+                    if is_bland {
+                        break;
+                    }
+
+                    min_cost = cost;
+                }
+            }
+
+            let entering = match entering {
+                Some(entering) => entering,
+                None => return RunResult::Optimal,
+            };
+
+            if self.max_pivots <= pivots {
+                return RunResult::PivotLimit;
+            }
+
+            let mut leaving: Option<(usize, f64)> = None;
+
+            for (index, row) in self.rows.iter().enumerate() {
+                let coef = row[entering];
+
+                if coef <= EPS {
+                    continue;
+                }
+
+                let ratio = row[self.columns_len] / coef;
+
+                let is_better = match leaving {
+                    None => true,
+                    Some((leaving_index, min_ratio)) => {
+                        ratio < min_ratio - EPS
+                            || (ratio <= min_ratio + EPS
+                                && self.basis[index] < self.basis[leaving_index])
+                    }
+                };
+
+                if is_better {
+                    leaving = Some((index, ratio));
+                }
+            }
+
+            let (leaving, _) = match leaving {
+                Some(leaving) => leaving,
+                None => return RunResult::Unbounded,
+            };
+
+            self.pivot(objective, leaving, entering);
+
+            pivots += 1;
+        }
+    }
+
+    fn pivot(&mut self, objective: &mut [f64], pivot_index: usize, column: usize) {
+        let pivot_value = self.rows[pivot_index][column];
+
+        for value in self.rows[pivot_index].iter_mut() {
+            *value /= pivot_value;
+        }
+
+        let pivot_row = self.rows[pivot_index].clone();
+
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            if index == pivot_index {
+                continue;
+            }
+
+            Self::eliminate(row, &pivot_row, column);
+        }
+
+        Self::eliminate(objective, &pivot_row, column);
+
+        self.basis[pivot_index] = column;
+    }
+
+    fn eliminate(row: &mut [f64], pivot_row: &[f64], column: usize) {
+        let factor = row[column];
+
+        if factor == 0.0 {
+            return;
+        }
+
+        for (value, pivot_value) in row.iter_mut().zip(pivot_row.iter()) {
+            *value -= factor * pivot_value;
+        }
+
+        row[column] = 0.0;
+    }
+
+    // Artificial variables left in the basis are zero, they are swapped out or their redundant rows dropped
+    fn remove_artificials(&mut self) {
+        let mut index = 0;
+
+        while index < self.rows.len() {
+            if self.basis[index] < self.artificial_start {
+                index += 1;
+                continue;
+            }
+
+            let column =
+                (0..self.artificial_start).find(|&column| EPS < self.rows[index][column].abs());
+
+            match column {
+                Some(column) => {
+                    let mut objective = vec![0.0; self.columns_len + 1];
+
+                    self.pivot(&mut objective, index, column);
+                    index += 1;
+                }
+                None => {
+                    self.rows.swap_remove(index);
+                    self.basis.swap_remove(index);
+                }
+            }
+        }
+    }
+}
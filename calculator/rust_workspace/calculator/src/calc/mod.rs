@@ -1,14 +1,25 @@
+pub mod calc_anomaly_ranking;
 pub mod calc_data_manager;
 pub mod calc_equipment;
 pub mod calc_equips_iterator;
+pub mod calc_pareto;
 pub mod calc_point;
+pub mod calc_reporter;
+pub mod calc_request;
 pub mod calc_result;
+pub mod calc_sort;
+pub mod calc_stat;
+pub mod calc_state;
+pub mod calc_talisman;
 pub mod calc_ui_generator;
 pub mod calc_vector;
 pub mod calculator;
+pub mod cancel_token;
 pub mod constant;
 pub mod deco;
 pub mod deco_combination;
 pub mod full_equipments;
+pub mod ilp;
 pub mod skills;
+pub mod solver;
 pub mod types;
@@ -1,49 +1,78 @@
 use itertools::Itertools;
 
-use crate::{calc::constant::SKILLS_COUNT, data::data_manager::DataManager};
+use crate::{calc::constant::MAX_SKILLS_COUNT, data::data_manager::DataManager};
 
 use super::types::{SkillLevelVec, SkillSlotCount, SkillsTuple};
 
+const MASK_BITS: usize = u64::BITS as usize;
+const MASK_WORDS: usize = MAX_SKILLS_COUNT.div_ceil(MASK_BITS);
+
+type SkillMask = [u64; MASK_WORDS];
+
+// Levels of all skills, most of them are 0 so the mask marks the ones to look at.
+// Bit is set for every nonzero level, it may stay set after the level goes back to 0.
 #[derive(Clone, Debug)]
 pub struct SkillsContainer {
     vec: SkillLevelVec,
+    mask: SkillMask,
 }
 
 pub struct SkillsContainerIterator<'a> {
-    index: usize,
+    bits: MaskBits,
     container: &'a SkillsContainer,
 }
 
 pub struct SkillsContainerIteratorMutable<'a> {
-    index: usize,
+    bits: MaskBits,
     container: &'a mut SkillsContainer,
 }
 
+// Set bit indices of a mask in ascending order
+struct MaskBits {
+    mask: SkillMask,
+    word_index: usize,
+}
+
 impl SkillsContainer {
     pub fn new() -> Self {
-        let vec = SkillLevelVec::from_vec(vec![0; SKILLS_COUNT]);
-
-        Self::from_vec(vec)
+        Self {
+            vec: SkillLevelVec::zeros(),
+            mask: SkillMask::default(),
+        }
     }
 
+    // Levels of the loaded skills, the rest of the capacity is 0
     pub fn from(vec: Vec<SkillSlotCount>) -> Self {
-        Self::from_vec(SkillLevelVec::from_vec(vec))
+        Self::from_vec(SkillLevelVec::from_iterator(
+            vec.into_iter().chain(std::iter::repeat(0)),
+        ))
     }
 
     pub fn from_vec(vec: SkillLevelVec) -> Self {
-        Self { vec }
+        let mut ret = Self {
+            vec,
+            mask: SkillMask::default(),
+        };
+
+        for uid in 0..MAX_SKILLS_COUNT {
+            if ret.vec[uid] != 0 {
+                ret.mark(uid);
+            }
+        }
+
+        ret
     }
 
     pub fn iter(&self) -> SkillsContainerIterator {
         SkillsContainerIterator {
-            index: 0,
+            bits: MaskBits::new(self.mask),
             container: self,
         }
     }
 
     pub fn iter_mut(&mut self) -> SkillsContainerIteratorMutable {
         SkillsContainerIteratorMutable {
-            index: 0,
+            bits: MaskBits::new(self.mask),
             container: self,
         }
     }
@@ -58,61 +87,78 @@ impl SkillsContainer {
 
     pub fn set(&mut self, uid: usize, value: SkillSlotCount) {
         self.vec[uid] = value;
+
+        if value != 0 {
+            self.mark(uid);
+        }
     }
 
     pub fn add(&mut self, other: &SkillsContainer) {
-        self.vec += other.vec;
+        self.apply_active(other, |level, other_level| *level += other_level);
     }
 
     pub fn sub(&mut self, other: &SkillsContainer) {
-        self.vec -= other.vec;
+        self.apply_active(other, |level, other_level| *level -= other_level);
     }
 
+    // Other gets the negated result, the levels other has more than self
     pub fn sub_mut(&mut self, other: &mut SkillsContainer) {
-        self.vec -= other.vec;
-        other.vec = -self.vec;
+        self.sub(other);
+
+        for uid in MaskBits::new(other.mask) {
+            other.vec[uid] = 0;
+        }
+
+        for uid in MaskBits::new(self.mask) {
+            other.vec[uid] = -self.vec[uid];
+        }
+
+        other.mask = self.mask;
     }
 
     pub fn get_diff(&self, other: &SkillsContainer) -> Self {
-        Self {
-            vec: self.vec - other.vec,
+        let mut diff = self.clone();
+        diff.sub(other);
+
+        diff
+    }
+
+    // Same as get_diff being empty, without building the diff
+    pub fn is_le(&self, other: &SkillsContainer) -> bool {
+        let mut mask = self.mask;
+
+        for (word, other_word) in mask.iter_mut().zip(&other.mask) {
+            *word |= other_word;
         }
+
+        MaskBits::new(mask).all(|uid| self.vec[uid] <= other.vec[uid])
     }
 
     pub fn add_level(&mut self, uid: usize, level: SkillSlotCount) {
         self.vec[uid] += level;
+
+        self.mark(uid);
     }
 
     pub fn clear_zeros(&mut self) {
-        for i in 0..SKILLS_COUNT {
-            self.vec[i] = self.vec[i].max(0);
+        for uid in MaskBits::new(self.mask) {
+            if self.vec[uid] <= 0 {
+                self.vec[uid] = 0;
+                self.mask[uid / MASK_BITS] &= !(1 << (uid % MASK_BITS));
+            }
         }
     }
 
     pub fn get_indices(&self) -> Vec<usize> {
-        let mut vec = Vec::new();
-
-        for (uid, &level) in self.vec.iter().enumerate() {
-            if 0 < level {
-                vec.push(uid);
-            }
-        }
-
-        vec
+        self.iter().map(|(uid, _)| uid).collect()
     }
 
     pub fn len(&self) -> usize {
-        let mut length = 0;
-
-        for _ in self.iter() {
-            length += 1;
-        }
-
-        length
+        self.iter().count()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.len() == 0
+        self.iter().next().is_none()
     }
 
     pub fn debug<'a>(&self, dm: &'a DataManager) -> Vec<(&'a String, SkillSlotCount)> {
@@ -173,28 +219,73 @@ impl SkillsContainer {
 
         ret
     }
-}
 
-impl<'a> Iterator for SkillsContainerIterator<'a> {
-    type Item = (usize, SkillSlotCount);
+    fn mark(&mut self, uid: usize) {
+        self.mask[uid / MASK_BITS] |= 1 << (uid % MASK_BITS);
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let length = self.container.vec.len();
+    // Visits the active skills of other only, word by word without the iterator state
+    fn apply_active<F>(&mut self, other: &SkillsContainer, f: F)
+    where
+        F: Fn(&mut SkillSlotCount, SkillSlotCount),
+    {
+        for (word_index, &word) in other.mask.iter().enumerate() {
+            let mut bits = word;
+
+            while bits != 0 {
+                let uid = word_index * MASK_BITS + bits.trailing_zeros() as usize;
+                f(&mut self.vec[uid], other.vec[uid]);
+
+                bits &= bits - 1;
+            }
+
+            self.mask[word_index] |= word;
+        }
+    }
+}
+
+impl MaskBits {
+    fn new(mask: SkillMask) -> Self {
+        Self {
+            mask,
+            word_index: 0,
+        }
+    }
+}
 
-        while self.index != length {
-            let ret_index = self.index;
-            let val = self.container.vec[self.index];
+impl Iterator for MaskBits {
+    type Item = usize;
 
-            self.index += 1;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.word_index != MASK_WORDS {
+            let word = &mut self.mask[self.word_index];
 
-            if val <= 0 {
+            if *word == 0 {
+                self.word_index += 1;
                 continue;
-            } else {
-                return Some((ret_index, val));
             }
+
+            let bit = word.trailing_zeros() as usize;
+            *word &= *word - 1;
+
+            return Some(self.word_index * MASK_BITS + bit);
         }
 
-        self.index = 0;
+        None
+    }
+}
+
+impl<'a> Iterator for SkillsContainerIterator<'a> {
+    type Item = (usize, SkillSlotCount);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for uid in self.bits.by_ref() {
+            let val = self.container.vec[uid];
+
+            if 0 < val {
+                return Some((uid, val));
+            }
+        }
 
         None
     }
@@ -204,27 +295,19 @@ impl<'a> Iterator for SkillsContainerIteratorMutable<'a> {
     type Item = (usize, &'a mut SkillSlotCount);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let length = self.container.vec.len();
-
         let vec_mut = self.container.vec.as_mut_ptr();
 
-        while self.index != length {
-            let ret_index = self.index;
-
+        for uid in self.bits.by_ref() {
+            // Each uid is given out once, so the references never alias
             unsafe {
-                let val = vec_mut.add(ret_index);
-                self.index += 1;
+                let val = vec_mut.add(uid);
 
-                if *val <= 0 {
-                    continue;
-                } else {
-                    return Some((ret_index, &mut *val));
+                if 0 < *val {
+                    return Some((uid, &mut *val));
                 }
             }
         }
 
-        self.index = 0;
-
         None
     }
 }
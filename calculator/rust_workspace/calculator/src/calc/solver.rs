@@ -0,0 +1,471 @@
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use anyhow::bail;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::data::data_manager::DataManager;
+
+use super::{
+    calc_data_manager::CalcDataManager,
+    calc_equipment::CalcEquipment,
+    calc_pareto::ParetoFilter,
+    calc_reporter::{CalcProgress, CalcReporter},
+    calc_request::CalculateRequest,
+    calc_result::{CalcResultGenerator, CalculateResult},
+    calc_sort::{AnswerSorter, SortKey, SortKind},
+    calc_stat::StatBounds,
+    calculator::{CalcSearchContext, Calculator},
+    cancel_token::CancelToken,
+    constant::{EQUIP_PART_COUNT, MAX_SLOT_LEVEL},
+    full_equipments::FullEquipments,
+    ilp::{IlpModel, RowKind},
+    skills::SkillsContainer,
+    types::{CalcAnswer, EquipmentsArray},
+};
+
+pub trait Solver {
+    fn solve(
+        &self,
+        request: &CalculateRequest,
+        dm: &DataManager,
+        cm: &CalcDataManager,
+        cancel_token: &CancelToken,
+        reporter: &dyn CalcReporter,
+    ) -> anyhow::Result<(String, CalculateResult)>;
+}
+
+// Only the CLI picks a solver, the app pages answers with the enumeration cursor
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SolverKind {
+    #[default]
+    Enumeration,
+    Ilp,
+}
+
+impl SolverKind {
+    pub fn get_solver(&self) -> Box<dyn Solver> {
+        match self {
+            Self::Enumeration => Box::new(EnumerationSolver {}),
+            Self::Ilp => Box::new(IlpSolver {}),
+        }
+    }
+}
+
+// Searches candidate armors with skill points and slots bounds, resumable with a cursor
+pub struct EnumerationSolver {}
+
+impl Solver for EnumerationSolver {
+    fn solve(
+        &self,
+        request: &CalculateRequest,
+        dm: &DataManager,
+        cm: &CalcDataManager,
+        cancel_token: &CancelToken,
+        reporter: &dyn CalcReporter,
+    ) -> anyhow::Result<(String, CalculateResult)> {
+        Calculator::calculate(request, dm, cm, cancel_token, reporter)
+    }
+}
+
+// Finds armor sets with an integer program, best ones first when the first sort key is linear.
+// Without include_lte_equips, equipments worse than another one of the same part are skipped.
+pub struct IlpSolver {}
+
+struct IlpFormulation<'a> {
+    model: IlpModel,
+    part_equips: Vec<Vec<&'a Arc<CalcEquipment>>>,
+    part_vars: Vec<Vec<usize>>,
+}
+
+impl Solver for IlpSolver {
+    fn solve(
+        &self,
+        request: &CalculateRequest,
+        dm: &DataManager,
+        cm: &CalcDataManager,
+        cancel_token: &CancelToken,
+        reporter: &dyn CalcReporter,
+    ) -> anyhow::Result<(String, CalculateResult)> {
+        if request.cursor.is_some() {
+            bail!("ILP solver can not resume from a cursor");
+        }
+
+        let start_time = Instant::now();
+
+        let ori_weapon_slots = &request.weapon_slots;
+        let sex_type = &request.sex_type;
+        let answer_limit = request.answer_limit.max(1);
+
+        let ctx = CalcSearchContext::new(request, dm);
+
+        let weapon_slots_lp = &ctx.weapon_slots_lp;
+        let free_slots_lp = &ctx.free_slots_lp;
+
+        let mut ret = String::from("\n");
+
+        let (mut all_original_equips, _) = cm.get_all_equipments(
+            sex_type,
+            request.include_lte_equips,
+            &request.excluded_equips,
+        );
+
+        for (part, equip_id) in request.pinned_equips.iter() {
+            all_original_equips[part.as_usize()].retain(|id, _| id == equip_id);
+
+            if all_original_equips[part.as_usize()].is_empty() {
+                bail!(
+                    "Pinned equipment not found: {} ({})",
+                    equip_id,
+                    part.as_str()
+                );
+            }
+        }
+
+        let mut formulation = Self::get_formulation(request, dm, &all_original_equips);
+
+        Self::info(
+            &mut ret,
+            &format!(
+                "ILP variables: {}, rows: {}, part equips: {:?}",
+                formulation.model.vars_len(),
+                formulation.model.rows_len(),
+                formulation
+                    .part_equips
+                    .iter()
+                    .map(|equips| equips.len())
+                    .collect::<Vec<_>>()
+            ),
+        );
+
+        let sorter = AnswerSorter::new(&request.sort_keys, weapon_slots_lp, free_slots_lp);
+        let mut pareto_filter = ParetoFilter::new(weapon_slots_lp, free_slots_lp);
+
+        let mut answers: HashMap<u128, CalcAnswer> = HashMap::with_capacity(answer_limit);
+        let mut solved_count = 0;
+
+        let report_progress = |solved_count: usize, answers_count: usize, is_done: bool| {
+            reporter.on_progress(&CalcProgress {
+                processed_candidates: solved_count,
+                candidates_count: answer_limit,
+                all_candidate_len: answer_limit,
+                answers_count,
+                elapsed: start_time.elapsed().as_secs_f32(),
+                is_done,
+            });
+        };
+
+        while answers.len() < answer_limit && !cancel_token.is_cancelled() {
+            let solution = match formulation.model.solve(cancel_token)? {
+                Some(solution) => solution,
+                None => break,
+            };
+
+            solved_count += 1;
+
+            let mut chosen_vars = Vec::with_capacity(EQUIP_PART_COUNT);
+            let mut chosen_equips = Vec::with_capacity(EQUIP_PART_COUNT);
+
+            for (part, vars) in formulation.part_vars.iter().enumerate() {
+                let index = vars
+                    .iter()
+                    .position(|&var| solution.get_int(var) == 1)
+                    .unwrap();
+
+                chosen_vars.push((vars[index], 1.0));
+                chosen_equips.push(formulation.part_equips[part][index]);
+            }
+
+            // Same armor set is not found again
+            formulation
+                .model
+                .add_row(chosen_vars, RowKind::Le, (EQUIP_PART_COUNT - 1) as f64);
+
+            let equipments: EquipmentsArray = [
+                chosen_equips[0],
+                chosen_equips[1],
+                chosen_equips[2],
+                chosen_equips[3],
+                chosen_equips[4],
+                chosen_equips[5],
+            ];
+
+            let local_answers =
+                Calculator::calculate_full_equip(dm, &all_original_equips, &ctx, &equipments);
+
+            if local_answers.is_empty() {
+                Self::info(
+                    &mut ret,
+                    &format!(
+                        "ILP answer without decoration combinations: {:?}",
+                        equipments.map(|equip| equip.id())
+                    ),
+                );
+
+                continue;
+            }
+
+            for local_answer in local_answers {
+                let local_equips_id = FullEquipments::get_full_equip_id(&local_answer.0);
+
+                if request.pareto_only {
+                    let summary = pareto_filter.get_summary(&local_answer);
+
+                    if pareto_filter.is_dominated(&summary) {
+                        continue;
+                    }
+
+                    // A full page only takes answers replacing dominated ones
+                    if answer_limit <= answers.len() && !pareto_filter.is_dominating(&summary) {
+                        continue;
+                    }
+
+                    for dominated_id in pareto_filter.remove_dominated_by(&summary) {
+                        answers.remove(&dominated_id);
+                    }
+
+                    pareto_filter.insert(local_equips_id, summary);
+                } else if answer_limit <= answers.len() {
+                    // Slot only equipments would give an answer per armor they stand for
+                    break;
+                }
+
+                answers.insert(local_equips_id, local_answer);
+            }
+
+            report_progress(solved_count, answers.len(), false);
+        }
+
+        report_progress(solved_count, answers.len(), true);
+
+        if cancel_token.is_cancelled() {
+            Self::info(&mut ret, "Calculation cancelled");
+        }
+
+        Self::info(
+            &mut ret,
+            &format!(
+                "ILP calculation done - elapsed: {:?}, solved: {}, answers length: {}",
+                start_time.elapsed(),
+                solved_count,
+                answers.len()
+            ),
+        );
+
+        let mut sorted_answers = answers.into_values().collect::<Vec<_>>();
+        sorted_answers.sort_by_cached_key(|answer| sorter.get_rank(answer));
+
+        let calculate_result = CalcResultGenerator::generate(
+            dm,
+            sex_type,
+            ori_weapon_slots,
+            weapon_slots_lp,
+            free_slots_lp,
+            &sorted_answers,
+            start_time.elapsed(),
+        );
+
+        Ok((ret, calculate_result))
+    }
+}
+
+impl IlpSolver {
+    fn info(ret_log: &mut String, text: &str) {
+        info!("{}", text);
+        ret_log.push_str(&format!("{}\n", text));
+    }
+
+    // One binary variable per equipment, one integer variable per decoration
+    fn get_formulation<'a>(
+        request: &CalculateRequest,
+        dm: &DataManager,
+        all_original_equips: &[HashMap<String, &'a Arc<CalcEquipment>>],
+    ) -> IlpFormulation<'a> {
+        let selected_skills = Calculator::convert_to_skills_container(&request.selected_skills);
+        let sort_key = request.sort_keys.first().copied().unwrap_or(SortKey {
+            kind: SortKind::LeftoverSlots,
+            is_descending: true,
+        });
+
+        let mut model = IlpModel::new();
+
+        let mut part_equips = Vec::with_capacity(EQUIP_PART_COUNT);
+        let mut part_vars = Vec::with_capacity(EQUIP_PART_COUNT);
+
+        for part_originals in all_original_equips.iter() {
+            let mut equips = part_originals.values().copied().collect::<Vec<_>>();
+            equips.sort_by_key(|equip| equip.uid());
+
+            let equips = if request.include_lte_equips {
+                equips
+            } else {
+                Self::remove_dominated(equips, &selected_skills, &sort_key)
+            };
+
+            let vars = equips
+                .iter()
+                .map(|equip| model.add_var(Self::get_equip_cost(equip, &sort_key), None, true))
+                .collect::<Vec<_>>();
+
+            model.add_row(
+                vars.iter().map(|&var| (var, 1.0)).collect(),
+                RowKind::Eq,
+                1.0,
+            );
+
+            part_equips.push(equips);
+            part_vars.push(vars);
+        }
+
+        // Each decoration uses one slot, leftover slots are slots minus decorations
+        let deco_cost = match sort_key.kind {
+            SortKind::LeftoverSlots => -Self::get_sign(&sort_key),
+            _ => 0.0,
+        };
+
+        let mut skill_coefs = HashMap::<usize, Vec<(usize, f64)>>::new();
+        let mut slot_coefs = vec![Vec::new(); MAX_SLOT_LEVEL];
+
+        for (uid, level) in selected_skills.iter() {
+            let coefs = skill_coefs.entry(uid).or_default();
+
+            for (part, equips) in part_equips.iter().enumerate() {
+                for (equip, &var) in equips.iter().zip(part_vars[part].iter()) {
+                    coefs.push((var, equip.skills().get(uid) as f64));
+                }
+            }
+
+            for deco in dm.get_deco_by_skill_id(uid) {
+                let needed = (level + deco.skill_level - 1) / deco.skill_level;
+                let upper = match dm.get_deco_inventory().get(&deco.id) {
+                    Some(&count) => count.min(needed),
+                    None => needed,
+                };
+
+                let var = model.add_var(deco_cost, Some(upper as f64), true);
+
+                coefs.push((var, deco.skill_level as f64));
+
+                for coefs in slot_coefs.iter_mut().take(deco.slot_size as usize) {
+                    coefs.push((var, 1.0));
+                }
+            }
+        }
+
+        for (uid, level) in selected_skills.iter() {
+            model.add_row(skill_coefs.remove(&uid).unwrap(), RowKind::Ge, level as f64);
+        }
+
+        // Decorations of size t or more fit only in slots of size t or more
+        let (weapon_slots_lp, _, free_slots_lp) = Calculator::get_converted_input_data(
+            &request.weapon_slots,
+            request.free_slots.clone(),
+            dm,
+        );
+
+        for (index, mut coefs) in slot_coefs.into_iter().enumerate() {
+            for (part, equips) in part_equips.iter().enumerate() {
+                for (equip, &var) in equips.iter().zip(part_vars[part].iter()) {
+                    coefs.push((var, -equip.slots_lp()[index] as f64));
+                }
+            }
+
+            model.add_row(
+                coefs,
+                RowKind::Le,
+                (weapon_slots_lp[index] - free_slots_lp[index]) as f64,
+            );
+        }
+
+        for (stat_index, min_stat) in request.min_stats.to_values().iter().enumerate() {
+            let min_stat = match min_stat {
+                Some(min_stat) => *min_stat,
+                None => continue,
+            };
+
+            let mut coefs = Vec::new();
+
+            for (part, equips) in part_equips.iter().enumerate() {
+                for (equip, &var) in equips.iter().zip(part_vars[part].iter()) {
+                    let stat = StatBounds::get_stat_values(&equip.stats())[stat_index];
+
+                    coefs.push((var, stat as f64));
+                }
+            }
+
+            model.add_row(coefs, RowKind::Ge, min_stat as f64);
+        }
+
+        IlpFormulation {
+            model,
+            part_equips,
+            part_vars,
+        }
+    }
+
+    fn get_sign(sort_key: &SortKey) -> f64 {
+        if sort_key.is_descending {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    // Objective is minimized, so values sorted in descending order are negated
+    fn get_equip_cost(equip: &CalcEquipment, sort_key: &SortKey) -> f64 {
+        let stat_values = StatBounds::get_stat_values(&equip.stats());
+
+        let value = match sort_key.kind {
+            SortKind::Defense => stat_values[0],
+            SortKind::FireRes => stat_values[1],
+            SortKind::WaterRes => stat_values[2],
+            SortKind::IceRes => stat_values[3],
+            SortKind::ElecRes => stat_values[4],
+            SortKind::DragonRes => stat_values[5],
+            SortKind::LeftoverSlots => equip.slots_lp()[0] as i32,
+            SortKind::AnomalyCount => (equip.is_armor() && equip.as_armor().is_anomaly()) as i32,
+            SortKind::CommonLeftoverSkills => 0,
+        };
+
+        Self::get_sign(sort_key) * value as f64
+    }
+
+    // Worse or equal in required skills, slots, stats and the objective than another equipment
+    fn remove_dominated<'a>(
+        equips: Vec<&'a Arc<CalcEquipment>>,
+        req_skills: &SkillsContainer,
+        sort_key: &SortKey,
+    ) -> Vec<&'a Arc<CalcEquipment>> {
+        let is_le = |equip: &CalcEquipment, other: &CalcEquipment| {
+            let stats = StatBounds::get_stat_values(&equip.stats());
+            let other_stats = StatBounds::get_stat_values(&other.stats());
+
+            equip.is_le(other, false, Some(req_skills))
+                && stats
+                    .iter()
+                    .zip(other_stats.iter())
+                    .all(|(stat, other)| stat <= other)
+                && Self::get_equip_cost(other, sort_key) <= Self::get_equip_cost(equip, sort_key)
+        };
+
+        let mut left_equips = Vec::with_capacity(equips.len());
+
+        for (index, &equip) in equips.iter().enumerate() {
+            let is_dominated = equips.iter().enumerate().any(|(other_index, &other)| {
+                if index == other_index || !is_le(equip, other) {
+                    return false;
+                }
+
+                // Only the first one of equal equipments is kept
+                !is_le(other, equip) || other_index < index
+            });
+
+            if !is_dominated {
+                left_equips.push(equip);
+            }
+        }
+
+        left_equips
+    }
+}
@@ -4,7 +4,8 @@ use std::path::Path;
 use std::sync::Arc;
 
 use ::num::integer::lcm;
-use log::debug;
+use anyhow::{bail, Context};
+use log::{debug, warn};
 use nohash_hasher::IntMap;
 
 use crate::calc::calc_vector::CalcVector;
@@ -18,15 +19,21 @@ use super::armor::{
     ANOMALY_ARMOR_PREFIX,
 };
 use super::deco::Decoration;
+use super::deco_table::DecoTableStore;
 use super::skill::Skill;
+use super::validation::DataReport;
 
 type SlotOnlyArmors = Vec<HashMap<String, Arc<BaseArmor>>>;
 type ArmorsBySlot = Vec<HashMap<String, Vec<Arc<BaseArmor>>>>;
 
+const MANUAL_TALISMAN_PREFIX: &str = "talisman_manual_";
+
 pub struct DataManager {
     armors: HashMap<String, Arc<BaseArmor>>,
     skills: Vec<Skill>,
     decos: HashMap<String, Decoration>,
+    banned_decos: HashSet<String>,
+    deco_inventory: HashMap<String, SkillSlotCount>,
 
     skill_id_map: HashMap<String, usize>,
     empty_skill_levels: Vec<SkillSlotCount>,
@@ -37,6 +44,9 @@ pub struct DataManager {
     point_lcm: i32,
     single_deco_skills: Vec<Option<Decoration>>,
     deco_combinations: DecorationCombinations,
+    deco_table_key: u64,
+    deco_table_store: Option<DecoTableStore>,
+    deco_table_saved_len: Option<usize>,
 
     slot_only_armors: SlotOnlyArmors,
     armors_by_slot: ArmorsBySlot,
@@ -58,6 +68,8 @@ pub struct DataManager {
 
     armor_name_dict: HashMap<String, String>,
     skill_name_dict: HashMap<String, String>,
+
+    data_report: DataReport,
 }
 
 impl DataManager {
@@ -65,37 +77,67 @@ impl DataManager {
         armors_filename: &Path,
         skills_filename: &Path,
         decos_filename: &Path,
-    ) -> DataManager {
-        use crate::utils::parse::parse_data;
+        deco_table_dir: Option<&Path>,
+    ) -> anyhow::Result<DataManager> {
+        use crate::utils::parse::try_parse_data;
 
         let armor_task = async {
-            parse_data::<BaseArmor, _>(armors_filename)
-                .into_iter()
-                .map(|armor| (armor.id().clone(), armor))
-                .collect::<HashMap<_, _>>()
+            try_parse_data::<BaseArmor, _>(armors_filename).map(|armors| {
+                armors
+                    .into_iter()
+                    .map(|armor| (armor.id().clone(), armor))
+                    .collect::<HashMap<_, _>>()
+            })
         };
         let skill_task = async {
-            parse_data::<Skill, _>(skills_filename)
-                .into_iter()
-                .map(|skill| (skill.id.clone(), skill))
-                .collect::<HashMap<_, _>>()
+            try_parse_data::<Skill, _>(skills_filename).map(|skills| {
+                skills
+                    .into_iter()
+                    .map(|skill| (skill.id.clone(), skill))
+                    .collect::<HashMap<_, _>>()
+            })
         };
         let deco_task = async {
-            parse_data::<Decoration, _>(decos_filename)
-                .into_iter()
-                .map(|deco| (deco.id.clone(), deco))
-                .collect::<HashMap<_, _>>()
+            try_parse_data::<Decoration, _>(decos_filename).map(|decos| {
+                decos
+                    .into_iter()
+                    .map(|deco| (deco.id.clone(), deco))
+                    .collect::<HashMap<_, _>>()
+            })
         };
         let (armors, skills, decos) = futures_util::join!(armor_task, skill_task, deco_task);
 
-        Self::new(armors, skills, decos)
+        Self::new(
+            armors.with_context(|| format!("Failed to read {}", armors_filename.display()))?,
+            skills.with_context(|| format!("Failed to read {}", skills_filename.display()))?,
+            decos.with_context(|| format!("Failed to read {}", decos_filename.display()))?,
+            deco_table_dir,
+        )
     }
 
+    // Data with errors in the report would panic later, so it is refused here.
+    // Decoration combination tables are loaded from deco_table_dir instead of built when saved before,
+    // saving a built one is left to save_deco_table
     pub fn new(
         armors: HashMap<String, BaseArmor>,
         skills: HashMap<String, Skill>,
         decos: HashMap<String, Decoration>,
-    ) -> Self {
+        deco_table_dir: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let data_report = DataReport::check(&armors, &skills, &decos);
+
+        if data_report.has_errors() {
+            bail!(
+                "Game data has {} errors\n{}",
+                data_report.get_errors_count(),
+                data_report
+            );
+        }
+
+        for issue in data_report.issues.iter() {
+            warn!("{}", issue);
+        }
+
         let armors = armors
             .into_iter()
             .map(|(id, armor)| (id, Arc::new(armor)))
@@ -104,11 +146,14 @@ impl DataManager {
         let mut armor_name_dict = HashMap::<String, String>::new();
         let mut skill_name_dict = HashMap::<String, String>::new();
 
-        let skills = skills
+        let mut skills = skills
             .into_iter()
             .map(|(_, skill)| skill)
             .collect::<Vec<Skill>>();
 
+        // Uids follow id order, so a cursor stays valid in another run
+        skills.sort_by(|skill1, skill2| skill1.id.cmp(&skill2.id));
+
         let skill_id_map = skills
             .iter()
             .enumerate()
@@ -135,6 +180,22 @@ impl DataManager {
             }
         }
 
+        let deco_table_key =
+            DecoTableStore::get_key(&decos, &skills, &HashSet::new(), &HashMap::new());
+        let deco_table_store = deco_table_dir.map(DecoTableStore::new);
+
+        let prebuilt_combinations = deco_table_store
+            .as_ref()
+            .and_then(|store| store.load(deco_table_key, &skill_id_map));
+
+        if prebuilt_combinations.is_some() {
+            debug!("Decoration table loaded: {:016x}", deco_table_key);
+        }
+
+        let deco_table_saved_len = prebuilt_combinations
+            .as_ref()
+            .map(|deco_combinations| deco_combinations.get_caches_len());
+
         let (
             decos_by_skill,
             single_deco_skills,
@@ -142,7 +203,13 @@ impl DataManager {
             skills_point,
             deco_combinations,
             point_lcm,
-        ) = Self::get_deco_data(&decos, &skills, &skill_id_map);
+        ) = Self::get_deco_data(
+            &decos,
+            &skills,
+            &skill_id_map,
+            &HashMap::new(),
+            prebuilt_combinations,
+        );
 
         let (slot_only_armors, armors_by_slot) = Self::extract_slot_armors(&armors);
 
@@ -173,10 +240,12 @@ impl DataManager {
                 .push(armor.clone());
         }
 
-        DataManager {
+        Ok(DataManager {
             armors,
             skills,
             decos,
+            banned_decos: Default::default(),
+            deco_inventory: Default::default(),
             skill_id_map,
             empty_skill_levels,
             decos_by_skill,
@@ -185,6 +254,9 @@ impl DataManager {
             skills_point,
             point_lcm,
             deco_combinations,
+            deco_table_key,
+            deco_table_store,
+            deco_table_saved_len,
             slot_only_armors,
             armors_by_slot,
             empty_armors,
@@ -201,7 +273,8 @@ impl DataManager {
             manual_talismans: Default::default(),
             slot_only_talismans: Default::default(),
             talismans_by_slot: Default::default(),
-        }
+            data_report,
+        })
     }
 
     pub fn debug_deco_combs(&self) {
@@ -209,9 +282,88 @@ impl DataManager {
     }
 
     pub fn set_banned_decos(&mut self, banned_decos: &HashSet<String>) {
+        self.banned_decos = banned_decos.clone();
+
+        self.refresh_deco_data();
+    }
+
+    // Owned count of each decoration, decorations not in the inventory are unlimited
+    pub fn set_deco_inventory(&mut self, deco_inventory: &HashMap<String, SkillSlotCount>) {
+        self.deco_inventory = deco_inventory.clone();
+
+        self.refresh_deco_data();
+    }
+
+    pub fn get_deco_inventory(&self) -> &HashMap<String, SkillSlotCount> {
+        &self.deco_inventory
+    }
+
+    // Both settings with one refresh, so no table of only one of them is built and saved
+    pub fn set_deco_settings(
+        &mut self,
+        banned_decos: &HashSet<String>,
+        deco_inventory: &HashMap<String, SkillSlotCount>,
+    ) {
+        if self.banned_decos == *banned_decos && self.deco_inventory == *deco_inventory {
+            return;
+        }
+
+        self.banned_decos = banned_decos.clone();
+        self.deco_inventory = deco_inventory.clone();
+
+        self.refresh_deco_data();
+    }
+
+    // Changes with decorations, banned ones and the inventory
+    pub fn get_deco_table_key(&self) -> u64 {
+        self.deco_table_key
+    }
+
+    // Saves the current tables with the caches calculations have filled so far, if any are new
+    pub fn save_deco_table(&mut self) {
+        let store = match &self.deco_table_store {
+            Some(store) => store,
+            None => return,
+        };
+
+        let caches_len = self.deco_combinations.get_caches_len();
+
+        if self.deco_table_saved_len == Some(caches_len) {
+            return;
+        }
+
+        match store.save(self.deco_table_key, &self.deco_combinations, &self.skills) {
+            Ok(()) => self.deco_table_saved_len = Some(caches_len),
+            Err(e) => warn!("Decoration table save failed: {:?}", e),
+        }
+    }
+
+    fn refresh_deco_data(&mut self) {
+        // Caches filled with the previous settings are kept for the next time they're used
+        self.save_deco_table();
+
+        let deco_table_key = DecoTableStore::get_key(
+            &self.decos,
+            &self.skills,
+            &self.banned_decos,
+            &self.deco_inventory,
+        );
+
+        let prebuilt_combinations = if deco_table_key == self.deco_table_key {
+            Some(std::mem::take(&mut self.deco_combinations))
+        } else {
+            self.load_deco_table(deco_table_key)
+        };
+
         let mut real_decos = self.decos.clone();
 
-        for deco_id in banned_decos {
+        let not_owned_decos = self
+            .deco_inventory
+            .iter()
+            .filter(|(_, &count)| count <= 0)
+            .map(|(deco_id, _)| deco_id);
+
+        for deco_id in self.banned_decos.iter().chain(not_owned_decos) {
             real_decos.remove(deco_id);
         }
 
@@ -222,13 +374,41 @@ impl DataManager {
             self.skills_point,
             self.deco_combinations,
             self.point_lcm,
-        ) = Self::get_deco_data(&real_decos, &self.skills, &self.skill_id_map);
+        ) = Self::get_deco_data(
+            &real_decos,
+            &self.skills,
+            &self.skill_id_map,
+            &self.deco_inventory,
+            prebuilt_combinations,
+        );
+
+        self.deco_table_key = deco_table_key;
+
+        // Built tables are saved right away, loaded ones once calculations add caches
+        self.save_deco_table();
+    }
+
+    fn load_deco_table(&mut self, deco_table_key: u64) -> Option<DecorationCombinations> {
+        self.deco_table_saved_len = None;
+
+        let deco_combinations = self
+            .deco_table_store
+            .as_ref()?
+            .load(deco_table_key, &self.skill_id_map)?;
+
+        debug!("Decoration table loaded: {:016x}", deco_table_key);
+
+        self.deco_table_saved_len = Some(deco_combinations.get_caches_len());
+
+        Some(deco_combinations)
     }
 
     fn get_deco_data(
         decos: &HashMap<String, Decoration>,
         skills: &Vec<Skill>,
         skill_id_map: &HashMap<String, usize>,
+        deco_inventory: &HashMap<String, SkillSlotCount>,
+        prebuilt_combinations: Option<DecorationCombinations>,
     ) -> (
         Vec<Vec<Decoration>>,
         Vec<std::option::Option<Decoration>>,
@@ -350,7 +530,9 @@ impl DataManager {
             }
         }
 
-        let deco_combinations = DecorationCombinations::new(&decos_by_skill, &skills);
+        let deco_combinations = prebuilt_combinations.unwrap_or_else(|| {
+            DecorationCombinations::new(&decos_by_skill, skills, deco_inventory)
+        });
 
         (
             decos_by_skill,
@@ -463,13 +645,33 @@ impl DataManager {
     }
 
     pub fn add_manual_anomaly(&mut self, mut anomaly: AnomalyArmor) -> Arc<AnomalyArmor> {
-        anomaly.affected.id = format!(
-            "{}_manual_{}_{}",
-            ANOMALY_ARMOR_PREFIX,
-            self.file_anomaly_armors.len(),
-            anomaly.original.id()
+        let index = Self::get_next_manual_index(
+            self.manual_anomaly_armors.keys(),
+            &Self::get_manual_anomaly_prefix(),
         );
 
+        anomaly.affected.id = Self::get_manual_anomaly_id(index, anomaly.original.id());
+
+        self.insert_manual_anomaly(anomaly)
+    }
+
+    // Saved manual anomalies keep their ids, which pinned and excluded equipments refer to
+    pub fn restore_manual_anomaly(&mut self, anomaly: AnomalyArmor) -> Arc<AnomalyArmor> {
+        let id = &anomaly.affected.id;
+
+        let is_kept = Self::get_manual_index(id, &Self::get_manual_anomaly_prefix())
+            .map(|index| *id == Self::get_manual_anomaly_id(index, anomaly.original.id()))
+            .unwrap_or(false)
+            && !self.manual_anomaly_armors.contains_key(id);
+
+        if is_kept {
+            self.insert_manual_anomaly(anomaly)
+        } else {
+            self.add_manual_anomaly(anomaly)
+        }
+    }
+
+    fn insert_manual_anomaly(&mut self, anomaly: AnomalyArmor) -> Arc<AnomalyArmor> {
         let insert_value = Arc::new(anomaly);
 
         self.manual_anomaly_armors
@@ -494,7 +696,7 @@ impl DataManager {
     }
 
     pub fn clear_manual_anomalies(&mut self) {
-        self.manual_talismans.clear();
+        self.manual_anomaly_armors.clear();
         self.refresh_anomalies();
     }
 
@@ -538,8 +740,30 @@ impl DataManager {
     }
 
     pub fn add_manual_talisman(&mut self, talisman: &Talisman) -> Arc<Talisman> {
-        let manual_id = format!("talisman_manual_{}", self.manual_talismans.len());
+        let index = Self::get_next_manual_index(
+            self.manual_talismans.iter().map(|tali| tali.id()),
+            MANUAL_TALISMAN_PREFIX,
+        );
+
+        self.insert_manual_talisman(format!("{}{}", MANUAL_TALISMAN_PREFIX, index), talisman)
+    }
+
+    // Saved manual talismans keep their ids, which pinned equipments refer to
+    pub fn restore_manual_talisman(&mut self, talisman: &Talisman) -> Arc<Talisman> {
+        let is_kept = Self::get_manual_index(talisman.id(), MANUAL_TALISMAN_PREFIX).is_some()
+            && self
+                .manual_talismans
+                .iter()
+                .all(|tali| tali.id() != talisman.id());
 
+        if is_kept {
+            self.insert_manual_talisman(talisman.id().clone(), talisman)
+        } else {
+            self.add_manual_talisman(talisman)
+        }
+    }
+
+    fn insert_manual_talisman(&mut self, manual_id: String, talisman: &Talisman) -> Arc<Talisman> {
         let mut real_skills = Vec::new();
 
         for info in talisman.skills.iter() {
@@ -566,6 +790,34 @@ impl DataManager {
         arc
     }
 
+    fn get_manual_anomaly_prefix() -> String {
+        format!("{}_manual_", ANOMALY_ARMOR_PREFIX)
+    }
+
+    fn get_manual_anomaly_id(index: usize, original_id: &str) -> String {
+        format!(
+            "{}{}_{}",
+            Self::get_manual_anomaly_prefix(),
+            index,
+            original_id
+        )
+    }
+
+    // Number right after the prefix, manual ids are numbered in the order they were added
+    fn get_manual_index(id: &str, prefix: &str) -> Option<usize> {
+        let rest = id.strip_prefix(prefix)?;
+        let end = rest.find('_').unwrap_or(rest.len());
+
+        rest[..end].parse().ok()
+    }
+
+    // Removed manual equipments leave gaps, new ones are numbered after the last one
+    fn get_next_manual_index<'a>(ids: impl Iterator<Item = &'a String>, prefix: &str) -> usize {
+        ids.filter_map(|id| Self::get_manual_index(id, prefix))
+            .max()
+            .map_or(0, |index| index + 1)
+    }
+
     pub fn remove_manual_talisman(&mut self, id: &String) -> bool {
         let mut remove_index = -1;
 
@@ -676,6 +928,10 @@ impl DataManager {
         &self.file_anomaly_armors
     }
 
+    pub fn get_manual_anomaly_armors(&self) -> &HashMap<String, Arc<AnomalyArmor>> {
+        &self.manual_anomaly_armors
+    }
+
     pub fn get_file_talismans(&self) -> &Vec<Arc<Talisman>> {
         &self.file_talismans
     }
@@ -806,6 +1062,10 @@ impl DataManager {
         &self.armor_name_dict
     }
 
+    pub fn get_data_report(&self) -> &DataReport {
+        &self.data_report
+    }
+
     pub fn get_armors(&self) -> &HashMap<String, Arc<BaseArmor>> {
         &self.armors
     }
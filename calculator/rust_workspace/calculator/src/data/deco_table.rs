@@ -0,0 +1,416 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use log::warn;
+use nohash_hasher::IntMap;
+
+use crate::calc::constant::{MAX_SKILLS_COUNT, MAX_SLOT_LEVEL};
+use crate::calc::deco_combination::{DecorationCombination, DecorationCombinations};
+use crate::calc::types::{SkillSlotCount, SkillsTuple, SlotsVec};
+use crate::utils::stable_hash::get_stable_hash;
+
+use super::deco::Decoration;
+use super::skill::Skill;
+
+const TABLE_MAGIC: &[u8; 8] = b"MHRDECO\0";
+const TABLE_VERSION: u8 = 2;
+
+// Each ban set and inventory gets its own file, only the latest saved ones are kept
+const MAX_SAVED_TABLES: usize = 4;
+
+// Decoration combination tables saved as one binary file per key, caches included
+pub struct DecoTableStore {
+    tables_dir: PathBuf,
+}
+
+impl DecoTableStore {
+    pub fn new(tables_dir: &Path) -> Self {
+        Self {
+            tables_dir: tables_dir.to_path_buf(),
+        }
+    }
+
+    // Same decorations, skills, banned set and inventory give the same tables
+    pub fn get_key(
+        decos: &HashMap<String, Decoration>,
+        skills: &[Skill],
+        banned_decos: &HashSet<String>,
+        deco_inventory: &HashMap<String, SkillSlotCount>,
+    ) -> u64 {
+        let mut deco_infos = decos
+            .values()
+            .map(|deco| (&deco.id, &deco.skill_id, deco.skill_level, deco.slot_size))
+            .collect::<Vec<_>>();
+        deco_infos.sort();
+
+        let mut skill_infos = skills
+            .iter()
+            .map(|skill| (&skill.id, skill.max_level))
+            .collect::<Vec<_>>();
+        skill_infos.sort();
+
+        let mut banned_decos = banned_decos.iter().collect::<Vec<_>>();
+        banned_decos.sort();
+
+        let mut deco_inventory = deco_inventory.iter().collect::<Vec<_>>();
+        deco_inventory.sort();
+
+        get_stable_hash(&(
+            TABLE_VERSION,
+            deco_infos,
+            skill_infos,
+            banned_decos,
+            deco_inventory,
+        ))
+    }
+
+    // Missing, outdated or broken files are treated the same, tables are built again
+    pub fn load(
+        &self,
+        key: u64,
+        skill_id_map: &HashMap<String, usize>,
+    ) -> Option<DecorationCombinations> {
+        let bytes = fs::read(self.get_filename(key)).ok()?;
+
+        TableReader::new(&bytes).read_table(key, skill_id_map)
+    }
+
+    // Written to a temporary file first so a crash can't leave a half written table
+    pub fn save(
+        &self,
+        key: u64,
+        deco_combinations: &DecorationCombinations,
+        skills: &[Skill],
+    ) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.tables_dir).context("Failed to create table directory")?;
+
+        let mut writer = TableWriter::default();
+        writer.write_table(key, deco_combinations, skills);
+
+        let filename = self.get_filename(key);
+        let temp_filename = filename.with_extension("tmp");
+
+        fs::write(&temp_filename, writer.bytes).context("Failed to write table")?;
+        fs::rename(&temp_filename, &filename).context("Failed to replace table")?;
+
+        if let Err(e) = self.remove_old_tables(&filename) {
+            warn!("Old decoration tables remove failed: {:?}", e);
+        }
+
+        Ok(())
+    }
+
+    // Oldest saved tables past the limit are removed, the one just saved is always kept
+    fn remove_old_tables(&self, saved_filename: &Path) -> anyhow::Result<()> {
+        let mut old_tables = Vec::new();
+
+        for entry in fs::read_dir(&self.tables_dir).context("Failed to read table directory")? {
+            let filename = entry?.path();
+
+            if filename == saved_filename || !Self::is_table_filename(&filename) {
+                continue;
+            }
+
+            old_tables.push((fs::metadata(&filename)?.modified()?, filename));
+        }
+
+        old_tables.sort();
+
+        let remove_count = (old_tables.len() + 1).saturating_sub(MAX_SAVED_TABLES);
+
+        for (_, filename) in old_tables.iter().take(remove_count) {
+            fs::remove_file(filename).context("Failed to remove table")?;
+        }
+
+        Ok(())
+    }
+
+    fn get_filename(&self, key: u64) -> PathBuf {
+        self.tables_dir.join(format!("deco_table_{:016x}.bin", key))
+    }
+
+    fn is_table_filename(filename: &Path) -> bool {
+        let name = filename
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+
+        name.starts_with("deco_table_") && name.ends_with(".bin")
+    }
+}
+
+// Skills are saved by id, uids depend on the order skills were loaded in
+#[derive(Default)]
+struct TableWriter {
+    bytes: Vec<u8>,
+}
+
+impl TableWriter {
+    fn write_table(
+        &mut self,
+        key: u64,
+        deco_combinations: &DecorationCombinations,
+        skills: &[Skill],
+    ) {
+        self.bytes.extend_from_slice(TABLE_MAGIC);
+        self.write_u8(TABLE_VERSION);
+        self.write_u64(key);
+
+        self.write_len(skills.len());
+
+        for skill in skills {
+            self.write_str(&skill.id);
+        }
+
+        for (level_combs, level_mins) in deco_combinations
+            .combinations_lp
+            .iter()
+            .zip(&deco_combinations.combinations_lp_mins)
+            .take(skills.len())
+        {
+            self.write_len(level_combs.len());
+
+            for (combs, min_lp) in level_combs.iter().zip(level_mins) {
+                self.write_slots_list(combs);
+                self.write_slots(min_lp);
+            }
+        }
+
+        let caches = deco_combinations.caches.read().unwrap();
+        self.write_len(caches.len());
+
+        for (req_list, combs) in caches.iter() {
+            self.write_skills_tuple(req_list);
+            self.write_slots_list(combs);
+        }
+
+        let sum_caches = deco_combinations.sum_caches.read().unwrap();
+        self.write_len(sum_caches.len());
+
+        for (req_list, sum) in sum_caches.iter() {
+            self.write_skills_tuple(req_list);
+            self.write_slots(sum);
+        }
+
+        let full_caches = deco_combinations.full_caches.read().unwrap();
+        self.write_len(full_caches.len());
+
+        for (req_list, deco_combs) in full_caches.iter() {
+            self.write_skills_tuple(req_list);
+            self.write_len(deco_combs.len());
+
+            for deco_comb in deco_combs.iter() {
+                self.write_len(deco_comb.combs_per_skill_lp.len());
+
+                for (&uid, slots_lp) in deco_comb.combs_per_skill_lp.iter() {
+                    self.write_len(uid);
+                    self.write_slots(slots_lp);
+                }
+
+                self.write_slots(&deco_comb.sum_lp);
+            }
+        }
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.bytes.extend_from_slice(&(len as u32).to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_len(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn write_slots(&mut self, slots: &SlotsVec) {
+        self.bytes.extend(slots.iter().map(|&count| count as u8));
+    }
+
+    fn write_slots_list(&mut self, slots_list: &[SlotsVec]) {
+        self.write_len(slots_list.len());
+
+        for slots in slots_list {
+            self.write_slots(slots);
+        }
+    }
+
+    fn write_skills_tuple(&mut self, req_list: &SkillsTuple) {
+        self.write_len(req_list.len());
+
+        for &(uid, level) in req_list {
+            self.write_len(uid);
+            self.write_u8(level as u8);
+        }
+    }
+}
+
+struct TableReader<'a> {
+    bytes: &'a [u8],
+    uid_map: Vec<usize>,
+}
+
+impl<'a> TableReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            uid_map: Vec::new(),
+        }
+    }
+
+    fn read_table(
+        &mut self,
+        key: u64,
+        skill_id_map: &HashMap<String, usize>,
+    ) -> Option<DecorationCombinations> {
+        if self.read_bytes(TABLE_MAGIC.len())? != TABLE_MAGIC
+            || self.read_u8()? != TABLE_VERSION
+            || self.read_u64()? != key
+        {
+            return None;
+        }
+
+        let skills_count = self.read_len()?;
+
+        if skills_count != skill_id_map.len() {
+            return None;
+        }
+
+        for _ in 0..skills_count {
+            let skill_id = self.read_str()?;
+
+            self.uid_map.push(*skill_id_map.get(&skill_id)?);
+        }
+
+        let mut combinations_lp = vec![Vec::new(); MAX_SKILLS_COUNT];
+        let mut combinations_lp_mins = vec![Vec::new(); MAX_SKILLS_COUNT];
+
+        for saved_uid in 0..skills_count {
+            let uid = self.uid_map[saved_uid];
+
+            for _ in 0..self.read_len()? {
+                combinations_lp[uid].push(self.read_slots_list()?);
+                combinations_lp_mins[uid].push(self.read_slots()?);
+            }
+        }
+
+        let deco_combinations =
+            DecorationCombinations::from_lp_tables(combinations_lp, combinations_lp_mins);
+
+        let mut caches = HashMap::new();
+
+        for _ in 0..self.read_len()? {
+            caches.insert(self.read_skills_tuple()?, Arc::new(self.read_slots_list()?));
+        }
+
+        let mut sum_caches = HashMap::new();
+
+        for _ in 0..self.read_len()? {
+            sum_caches.insert(self.read_skills_tuple()?, Arc::new(self.read_slots()?));
+        }
+
+        let mut full_caches = HashMap::new();
+
+        for _ in 0..self.read_len()? {
+            let req_list = self.read_skills_tuple()?;
+            let mut deco_combs = Vec::new();
+
+            for _ in 0..self.read_len()? {
+                let mut combs_per_skill_lp = IntMap::default();
+
+                for _ in 0..self.read_len()? {
+                    let uid = self.read_uid()?;
+
+                    combs_per_skill_lp.insert(uid, self.read_slots()?);
+                }
+
+                let sum_lp = self.read_slots()?;
+
+                deco_combs.push(DecorationCombination {
+                    combs_per_skill_lp,
+                    sum_lp,
+                });
+            }
+
+            full_caches.insert(req_list, Arc::new(deco_combs));
+        }
+
+        if !self.bytes.is_empty() {
+            return None;
+        }
+
+        Some(DecorationCombinations {
+            caches: RwLock::new(caches),
+            sum_caches: RwLock::new(sum_caches),
+            full_caches: RwLock::new(full_caches),
+            ..deco_combinations
+        })
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+
+        let (read, left) = self.bytes.split_at(len);
+        self.bytes = left;
+
+        Some(read)
+    }
+
+    fn read_u8(&mut self) -> Option<u8> {
+        Some(self.read_bytes(1)?[0])
+    }
+
+    fn read_u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.read_bytes(8)?.try_into().ok()?))
+    }
+
+    fn read_len(&mut self) -> Option<usize> {
+        Some(u32::from_le_bytes(self.read_bytes(4)?.try_into().ok()?) as usize)
+    }
+
+    fn read_str(&mut self) -> Option<String> {
+        let len = self.read_len()?;
+
+        String::from_utf8(self.read_bytes(len)?.to_vec()).ok()
+    }
+
+    fn read_uid(&mut self) -> Option<usize> {
+        let saved_uid = self.read_len()?;
+
+        self.uid_map.get(saved_uid).copied()
+    }
+
+    fn read_slots(&mut self) -> Option<SlotsVec> {
+        let bytes = self.read_bytes(MAX_SLOT_LEVEL)?;
+
+        Some(SlotsVec::from_iterator(
+            bytes.iter().map(|&count| count as SkillSlotCount),
+        ))
+    }
+
+    fn read_slots_list(&mut self) -> Option<Vec<SlotsVec>> {
+        (0..self.read_len()?).map(|_| self.read_slots()).collect()
+    }
+
+    // Cache keys are in uid order, which differs from the saved one
+    fn read_skills_tuple(&mut self) -> Option<SkillsTuple> {
+        let mut req_list = (0..self.read_len()?)
+            .map(|_| Some((self.read_uid()?, self.read_u8()? as SkillSlotCount)))
+            .collect::<Option<SkillsTuple>>()?;
+
+        req_list.sort();
+
+        Some(req_list)
+    }
+}
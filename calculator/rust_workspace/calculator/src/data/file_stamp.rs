@@ -0,0 +1,61 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Identifies the content of a parsed file, to skip parsing it again while it's unchanged
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileStamp {
+    filename: PathBuf,
+    modified: Option<SystemTime>,
+    hash: Option<u64>,
+}
+
+impl FileStamp {
+    // Missing file gets an empty stamp, which changes once the file appears
+    pub fn read(filename: &Path) -> Self {
+        Self {
+            filename: filename.to_path_buf(),
+            modified: Self::read_modified(filename),
+            hash: Self::read_hash(filename),
+        }
+    }
+
+    // Content is only hashed when the modified time differs
+    pub fn is_unchanged(&mut self, filename: &Path) -> bool {
+        if self.filename != filename {
+            return false;
+        }
+
+        let modified = Self::read_modified(filename);
+
+        if modified == self.modified {
+            return true;
+        }
+
+        if Self::read_hash(filename) != self.hash {
+            return false;
+        }
+
+        // Touched without changing the content
+        self.modified = modified;
+
+        true
+    }
+
+    fn read_modified(filename: &Path) -> Option<SystemTime> {
+        fs::metadata(filename)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    fn read_hash(filename: &Path) -> Option<u64> {
+        fs::read(filename).ok().map(|content| {
+            let mut hasher = DefaultHasher::new();
+            content.hash(&mut hasher);
+
+            hasher.finish()
+        })
+    }
+}
@@ -3,8 +3,12 @@ use serde::{Deserialize, Serialize};
 pub mod armor;
 pub mod data_manager;
 pub mod deco;
+pub mod deco_table;
+pub mod file_stamp;
 pub mod parser;
+pub mod profile;
 pub mod skill;
+pub mod validation;
 
 #[repr(u8)]
 #[derive(Deserialize, Serialize, Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
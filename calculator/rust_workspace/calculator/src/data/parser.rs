@@ -1,172 +1,378 @@
 use csv::StringRecord;
 use log::info;
+use serde::Serialize;
+use std::fmt::{self, Display};
 use std::fs::File;
 use std::io::BufReader;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::{collections::HashMap, path::Path};
 
+use crate::calc::constant::MAX_SLOT_LEVEL;
+use crate::calc::types::SkillSlotCount;
 use crate::data::armor::{AnomalyArmor, ArmorSkill, ArmorStat, BaseArmor, SkillIdLevel, Talisman};
+use crate::utils::suggest::get_closest_matches;
 
-fn to_i16(record: &StringRecord, index: usize) -> i16 {
-    record[index].parse().unwrap()
-}
-fn to_i8(record: &StringRecord, index: usize) -> i8 {
-    record[index].parse().unwrap()
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseResult<T> {
+    pub items: Vec<T>,
+    pub errors: Vec<ParseError>,
 }
 
-pub fn parse_anomaly(
-    filename: &Path,
-    armors: &HashMap<String, Arc<BaseArmor>>,
-    armor_name_dict: &HashMap<String, String>,
-    skill_name_dict: &HashMap<String, String>,
-) -> Vec<AnomalyArmor> {
-    let file = File::open(filename);
+// Line and column start from 1
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseError {
+    pub line: u64,
+    pub column: usize,
+    pub value: String,
+    pub kind: ParseErrorKind,
+}
 
-    match file {
-        Ok(file) => {
-            let reader = BufReader::new(file);
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ParseErrorKind {
+    #[serde(rename_all = "camelCase")]
+    InvalidRecord {
+        message: String,
+    },
+    MissingValue,
+    InvalidNumber,
+    #[serde(rename_all = "camelCase")]
+    OutOfRange {
+        min: i64,
+        max: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    UnknownArmorName {
+        suggestions: Vec<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UnknownSkillName {
+        suggestions: Vec<String>,
+    },
+}
 
-            let mut csv_reader = csv::ReaderBuilder::new()
-                .has_headers(false)
-                .flexible(true)
-                .from_reader(reader);
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+
+        let suggestions = match &self.kind {
+            ParseErrorKind::InvalidRecord { message } => return write!(f, "{}", message),
+            ParseErrorKind::MissingValue => return write!(f, "missing value"),
+            ParseErrorKind::InvalidNumber => return write!(f, "invalid number \"{}\"", self.value),
+            ParseErrorKind::OutOfRange { min, max } => {
+                return write!(
+                    f,
+                    "number \"{}\" out of range {} to {}",
+                    self.value, min, max
+                )
+            }
+            ParseErrorKind::UnknownArmorName { suggestions } => {
+                write!(f, "unknown armor name \"{}\"", self.value)?;
+                suggestions
+            }
+            ParseErrorKind::UnknownSkillName { suggestions } => {
+                write!(f, "unknown skill name \"{}\"", self.value)?;
+                suggestions
+            }
+        };
 
-            let mut records = Vec::new();
+        if !suggestions.is_empty() {
+            write!(f, " (did you mean {}?)", suggestions.join(", "))?;
+        }
 
-            for result in csv_reader.records() {
-                let record = result.unwrap();
+        Ok(())
+    }
+}
 
-                records.push(record);
-            }
+// Collects every error of a record instead of stopping at the first one
+struct RecordParser<'a> {
+    record: &'a StringRecord,
+    line: u64,
+    errors: Vec<ParseError>,
+}
 
-            let mut anomaly_armors = Vec::new();
+impl<'a> RecordParser<'a> {
+    fn new(record: &'a StringRecord, index: usize) -> Self {
+        let line = match record.position() {
+            Some(position) => position.line(),
+            None => index as u64 + 1,
+        };
+
+        Self {
+            record,
+            line,
+            errors: Vec::new(),
+        }
+    }
 
-            for record in records {
-                let armor_name = &record[0];
+    fn push_error(&mut self, index: usize, value: &str, kind: ParseErrorKind) {
+        self.errors.push(ParseError {
+            line: self.line,
+            column: index + 1,
+            value: value.to_string(),
+            kind,
+        });
+    }
 
-                let defense = to_i16(&record, 1);
+    fn get_str(&mut self, index: usize) -> Option<&'a str> {
+        let value = self.record.get(index);
 
-                let fire_res = to_i16(&record, 2);
-                let water_res = to_i16(&record, 3);
-                let elec_res = to_i16(&record, 4);
-                let ice_res = to_i16(&record, 5);
-                let dragon_res = to_i16(&record, 6);
+        if value.is_none() {
+            self.push_error(index, "", ParseErrorKind::MissingValue);
+        }
 
-                let slot_size1 = to_i8(&record, 7);
-                let slot_size2 = to_i8(&record, 8);
-                let slot_size3 = to_i8(&record, 9);
+        value
+    }
 
-                let slot_sizes = vec![slot_size1, slot_size2, slot_size3];
+    fn get_number<T: FromStr>(&mut self, index: usize) -> Option<T> {
+        let value = self.get_str(index)?;
 
-                let stat = ArmorStat {
-                    defense,
-                    fire_res,
-                    water_res,
-                    elec_res,
-                    ice_res,
-                    dragon_res,
-                };
+        match value.trim().parse() {
+            Ok(number) => Some(number),
+            Err(_) => {
+                self.push_error(index, value, ParseErrorKind::InvalidNumber);
+                None
+            }
+        }
+    }
 
-                let mut anomaly_skills = HashMap::new();
+    fn get_slot_size(&mut self, index: usize) -> Option<SkillSlotCount> {
+        let slot_size = self.get_number(index)?;
+
+        if !(0..=MAX_SLOT_LEVEL as SkillSlotCount).contains(&slot_size) {
+            self.push_error(
+                index,
+                &self.record[index],
+                ParseErrorKind::OutOfRange {
+                    min: 0,
+                    max: MAX_SLOT_LEVEL as i64,
+                },
+            );
+            return None;
+        }
 
-                for i in (10..record.len()).step_by(2) {
-                    let skill_name = &record[i];
+        Some(slot_size)
+    }
 
-                    if skill_name.is_empty() {
-                        continue;
-                    }
+    fn get_skill_id(
+        &mut self,
+        index: usize,
+        skill_name_dict: &HashMap<String, String>,
+    ) -> Option<String> {
+        let skill_name = self.get_str(index)?;
+
+        match skill_name_dict.get(skill_name) {
+            Some(skill_id) => Some(skill_id.clone()),
+            None => {
+                let suggestions = get_closest_matches(skill_name, skill_name_dict.keys());
+
+                self.push_error(
+                    index,
+                    skill_name,
+                    ParseErrorKind::UnknownSkillName { suggestions },
+                );
+                None
+            }
+        }
+    }
 
-                    let skill_level = to_i8(&record, i + 1);
+    // Values of failed fields are only placeholders, so the item is dropped on any error
+    fn finish<T>(self, item: Option<T>, result: &mut ParseResult<T>) {
+        if self.errors.is_empty() {
+            result.items.extend(item);
+        } else {
+            result.errors.extend(self.errors);
+        }
+    }
+}
 
-                    let skill_id = skill_name_dict.get(skill_name).unwrap();
+fn read_records(filename: &Path) -> std::io::Result<Vec<Result<StringRecord, ParseError>>> {
+    let reader = BufReader::new(File::open(filename)?);
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+
+    let records = csv_reader
+        .records()
+        .enumerate()
+        .map(|(index, result)| {
+            result.map_err(|err| ParseError {
+                line: match err.position() {
+                    Some(position) => position.line(),
+                    None => index as u64 + 1,
+                },
+                column: 1,
+                value: String::new(),
+                kind: ParseErrorKind::InvalidRecord {
+                    message: err.to_string(),
+                },
+            })
+        })
+        .collect();
+
+    Ok(records)
+}
 
-                    let anomaly_skill = ArmorSkill { level: skill_level };
+pub fn parse_anomaly(
+    filename: &Path,
+    armors: &HashMap<String, Arc<BaseArmor>>,
+    armor_name_dict: &HashMap<String, String>,
+    skill_name_dict: &HashMap<String, String>,
+) -> std::io::Result<ParseResult<AnomalyArmor>> {
+    let records = read_records(filename)?;
+
+    let mut result = ParseResult {
+        items: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for (index, record) in records.iter().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                result.errors.push(err.clone());
+                continue;
+            }
+        };
 
-                    anomaly_skills.insert(skill_id.to_string(), anomaly_skill);
-                }
+        let mut parser = RecordParser::new(record, index);
 
-                let armor_id = armor_name_dict.get(armor_name).unwrap();
-                let armor_info = armors.get(armor_id).unwrap();
+        let armor_info = parser.get_str(0).and_then(|armor_name| {
+            let armor_info = armor_name_dict
+                .get(armor_name)
+                .and_then(|armor_id| armors.get(armor_id));
 
-                let anomaly_armor = AnomalyArmor::new(armor_info, stat, slot_sizes, anomaly_skills);
+            if armor_info.is_none() {
+                let suggestions = get_closest_matches(armor_name, armor_name_dict.keys());
 
-                anomaly_armors.push(anomaly_armor);
+                parser.push_error(
+                    0,
+                    armor_name,
+                    ParseErrorKind::UnknownArmorName { suggestions },
+                );
             }
 
-            info!("Anomaly parsed - count : {}", anomaly_armors.len());
+            armor_info
+        });
 
-            anomaly_armors
-        }
-        Err(_) => Vec::new(),
-    }
-}
+        let defense = parser.get_number(1).unwrap_or_default();
 
-pub fn parse_talisman(filename: &Path, skill_name_dict: &HashMap<String, String>) -> Vec<Talisman> {
-    let file = File::open(filename);
+        let fire_res = parser.get_number(2).unwrap_or_default();
+        let water_res = parser.get_number(3).unwrap_or_default();
+        let elec_res = parser.get_number(4).unwrap_or_default();
+        let ice_res = parser.get_number(5).unwrap_or_default();
+        let dragon_res = parser.get_number(6).unwrap_or_default();
 
-    match file {
-        Ok(file) => {
-            let reader = BufReader::new(file);
+        let slot_size1 = parser.get_slot_size(7).unwrap_or_default();
+        let slot_size2 = parser.get_slot_size(8).unwrap_or_default();
+        let slot_size3 = parser.get_slot_size(9).unwrap_or_default();
 
-            let mut csv_reader = csv::ReaderBuilder::new()
-                .has_headers(false)
-                .from_reader(reader);
+        let mut anomaly_skills = HashMap::new();
 
-            let mut records = Vec::new();
+        for i in (10..record.len()).step_by(2) {
+            if record[i].is_empty() {
+                continue;
+            }
 
-            for result in csv_reader.records() {
-                let record = result.unwrap();
+            let skill_id = parser.get_skill_id(i, skill_name_dict);
+            let skill_level = parser.get_number(i + 1);
 
-                records.push(record);
+            if let (Some(skill_id), Some(level)) = (skill_id, skill_level) {
+                anomaly_skills.insert(skill_id, ArmorSkill { level });
             }
+        }
 
-            let mut talismans = Vec::new();
+        let slot_sizes = vec![slot_size1, slot_size2, slot_size3];
 
-            for (index, record) in records.iter().enumerate() {
-                let skill_name1 = &record[0];
-                let skill_level1 = to_i8(record, 1);
-                let skill_name2 = &record[2];
-                let skill_level2 = to_i8(record, 3);
+        let stat = ArmorStat {
+            defense,
+            fire_res,
+            water_res,
+            elec_res,
+            ice_res,
+            dragon_res,
+        };
 
-                let slot_size1 = to_i8(record, 4);
-                let slot_size2 = to_i8(record, 5);
-                let slot_size3 = to_i8(record, 6);
+        let anomaly_armor = armor_info
+            .map(|armor_info| AnomalyArmor::new(armor_info, stat, slot_sizes, anomaly_skills));
 
-                let slot_sizes = vec![slot_size1, slot_size2, slot_size3];
+        parser.finish(anomaly_armor, &mut result);
+    }
 
-                let mut talisman_skills = Vec::new();
+    info!(
+        "Anomaly parsed - count : {}, errors : {}",
+        result.items.len(),
+        result.errors.len()
+    );
 
-                if !skill_name1.is_empty() {
-                    let skill_id = skill_name_dict.get(skill_name1).unwrap();
+    Ok(result)
+}
 
-                    talisman_skills.push(SkillIdLevel {
-                        id: skill_id.to_string(),
-                        level: skill_level1,
-                    });
-                }
+pub fn parse_talisman(
+    filename: &Path,
+    skill_name_dict: &HashMap<String, String>,
+) -> std::io::Result<ParseResult<Talisman>> {
+    let records = read_records(filename)?;
+
+    let mut result = ParseResult {
+        items: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for (index, record) in records.iter().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                result.errors.push(err.clone());
+                continue;
+            }
+        };
 
-                if !skill_name2.is_empty() {
-                    let skill_id = skill_name_dict.get(skill_name2).unwrap();
+        let mut parser = RecordParser::new(record, index);
 
-                    talisman_skills.push(SkillIdLevel {
-                        id: skill_id.to_string(),
-                        level: skill_level2,
-                    });
-                }
+        let mut talisman_skills = Vec::new();
 
-                let talisman = Talisman::new(
-                    format!("talisman_file_{}", index),
-                    talisman_skills,
-                    slot_sizes,
-                );
+        for i in [0, 2] {
+            let has_skill = matches!(parser.get_str(i), Some(name) if !name.is_empty());
 
-                talismans.push(talisman);
-            }
+            let skill_id = if has_skill {
+                parser.get_skill_id(i, skill_name_dict)
+            } else {
+                None
+            };
 
-            info!("Talisman parsed - count : {}", talismans.len());
+            let skill_level = parser.get_number(i + 1);
 
-            talismans
+            if let (Some(id), Some(level)) = (skill_id, skill_level) {
+                talisman_skills.push(SkillIdLevel { id, level });
+            }
         }
-        Err(_) => Vec::new(),
+
+        let slot_size1 = parser.get_slot_size(4).unwrap_or_default();
+        let slot_size2 = parser.get_slot_size(5).unwrap_or_default();
+        let slot_size3 = parser.get_slot_size(6).unwrap_or_default();
+
+        let slot_sizes = vec![slot_size1, slot_size2, slot_size3];
+
+        let talisman = Talisman::new(
+            format!("talisman_file_{}", index),
+            talisman_skills,
+            slot_sizes,
+        );
+
+        parser.finish(Some(talisman), &mut result);
     }
+
+    info!(
+        "Talisman parsed - count : {}, errors : {}",
+        result.items.len(),
+        result.errors.len()
+    );
+
+    Ok(result)
 }
@@ -0,0 +1,393 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::calc::calc_sort::SortKey;
+use crate::calc::calc_stat::MinStats;
+use crate::calc::constant::MAX_ANSWER_LENGTH;
+use crate::calc::types::SkillSlotCount;
+
+use super::armor::{AnomalyArmor, ArmorPart, SexType, Talisman};
+use super::data_manager::DataManager;
+
+pub const PROFILE_VERSION: u32 = 1;
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+
+const PROFILES_DIRNAME: &str = "profiles";
+const PROFILE_INDEX_FILENAME: &str = "profiles.json";
+const SINGLE_PROFILE_FILENAME: &str = "profile.json";
+const INVALID_NAME_CHARS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+// Parameters of the last started search, restored when the app starts again
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchParams {
+    pub sex_type: SexType,
+    pub weapon_slots: Vec<SkillSlotCount>,
+    pub selected_skills: HashMap<String, SkillSlotCount>,
+    pub free_slots: Vec<SkillSlotCount>,
+    pub include_lte_equips: bool,
+    pub min_stats: MinStats,
+    pub pinned_equips: HashMap<ArmorPart, String>,
+    pub excluded_equips: HashSet<String>,
+    pub answer_limit: usize,
+    pub sort_keys: Vec<SortKey>,
+    pub pareto_only: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Profile {
+    pub version: u32,
+    pub manual_anomalies: Vec<AnomalyArmor>,
+    pub manual_talismans: Vec<Talisman>,
+    pub banned_decos: HashSet<String>,
+    pub deco_inventory: HashMap<String, SkillSlotCount>,
+    pub anomaly_filename: String,
+    pub talisman_filename: String,
+    pub last_search: Option<SearchParams>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            version: PROFILE_VERSION,
+            manual_anomalies: Vec::new(),
+            manual_talismans: Vec::new(),
+            banned_decos: HashSet::new(),
+            deco_inventory: HashMap::new(),
+            anomaly_filename: String::new(),
+            talisman_filename: String::new(),
+            last_search: None,
+        }
+    }
+}
+
+impl Profile {
+    // Missing file is a fresh profile, not an error
+    pub fn load(filename: &Path) -> anyhow::Result<Profile> {
+        if !filename.exists() {
+            return Ok(Profile::default());
+        }
+
+        let file = File::open(filename).context("Failed to open profile")?;
+        let value: serde_json::Value =
+            serde_json::from_reader(BufReader::new(file)).context("Failed to parse profile")?;
+
+        Self::migrate(value)
+    }
+
+    // Written to a temporary file first so a crash can't leave a half written profile
+    pub fn save(&self, filename: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = filename.parent() {
+            fs::create_dir_all(dir).context("Failed to create profile directory")?;
+        }
+
+        let temp_filename = filename.with_extension("tmp");
+
+        let file = File::create(&temp_filename).context("Failed to create profile")?;
+        serde_json::to_writer_pretty(file, self).context("Failed to write profile")?;
+
+        fs::rename(&temp_filename, filename).context("Failed to replace profile")?;
+
+        Ok(())
+    }
+
+    fn migrate(mut value: Value) -> anyhow::Result<Profile> {
+        let version = value
+            .get("version")
+            .and_then(|version| version.as_u64())
+            .unwrap_or_default();
+
+        if version == 0 {
+            Self::migrate_unversioned(&mut value)?;
+        } else if version != PROFILE_VERSION as u64 {
+            bail!(
+                "Unsupported profile version {} (current: {})",
+                version,
+                PROFILE_VERSION
+            );
+        }
+
+        serde_json::from_value(value).context("Invalid profile")
+    }
+
+    // Profiles saved before the version field may keep banned decorations as the id to flag map
+    // of the local storage, and a last search without the parameters added since
+    fn migrate_unversioned(value: &mut Value) -> anyhow::Result<()> {
+        let profile = value.as_object_mut().context("Invalid profile")?;
+
+        if let Some(Value::Object(flags)) = profile.get("bannedDecos") {
+            let banned_decos = flags
+                .iter()
+                .filter(|(_, is_banned)| is_banned.as_bool() == Some(true))
+                .map(|(deco_id, _)| Value::from(deco_id.clone()))
+                .collect();
+
+            profile.insert("bannedDecos".to_string(), Value::Array(banned_decos));
+        }
+
+        if let Some(last_search) = profile.get_mut("lastSearch") {
+            if let Value::Object(params) = last_search {
+                let defaults = [
+                    ("includeLteEquips", Value::from(false)),
+                    ("minStats", serde_json::to_value(MinStats::default())?),
+                    ("pinnedEquips", Value::Object(Default::default())),
+                    ("excludedEquips", Value::Array(Vec::new())),
+                    ("answerLimit", Value::from(MAX_ANSWER_LENGTH)),
+                    ("sortKeys", Value::Array(Vec::new())),
+                    ("paretoOnly", Value::from(false)),
+                ];
+
+                for (key, default) in defaults {
+                    params.entry(key).or_insert(default);
+                }
+            }
+
+            // Only restores the search form, not worth refusing the whole profile
+            if let Err(e) = serde_json::from_value::<SearchParams>(last_search.clone()) {
+                warn!("Last search of the unversioned profile is dropped: {}", e);
+
+                *last_search = Value::Null;
+            }
+        }
+
+        profile.insert("version".to_string(), Value::from(PROFILE_VERSION));
+
+        Ok(())
+    }
+
+    pub fn apply_manuals(&self, dm: &mut DataManager) {
+        dm.clear_manual_anomalies();
+
+        for anomaly in self.manual_anomalies.iter() {
+            dm.restore_manual_anomaly(anomaly.clone());
+        }
+
+        dm.clear_manual_talismans();
+
+        for tali in self.manual_talismans.iter() {
+            dm.restore_manual_talisman(tali);
+        }
+    }
+
+    pub fn update_manuals(&mut self, dm: &DataManager) {
+        let mut anomalies = dm
+            .get_manual_anomaly_armors()
+            .values()
+            .map(|anomaly| anomaly.as_ref().clone())
+            .collect::<Vec<_>>();
+
+        anomalies.sort_by(|anomaly1, anomaly2| anomaly1.affected.id().cmp(anomaly2.affected.id()));
+
+        self.manual_anomalies = anomalies;
+
+        self.manual_talismans = dm
+            .get_manual_talismans()
+            .iter()
+            .map(|tali| tali.as_ref().clone())
+            .collect();
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ProfileIndex {
+    active: String,
+}
+
+// Named profiles saved as one file each, plus an index remembering the active one
+pub struct ProfileStore {
+    profiles_dir: PathBuf,
+    index_filename: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(data_dir: &Path) -> Self {
+        let store = Self {
+            profiles_dir: data_dir.join(PROFILES_DIRNAME),
+            index_filename: data_dir.join(PROFILE_INDEX_FILENAME),
+        };
+
+        // Profile saved before named profiles existed becomes the default one
+        let single_filename = data_dir.join(SINGLE_PROFILE_FILENAME);
+
+        if single_filename.exists() && store.get_names().is_empty() {
+            let moved = fs::create_dir_all(&store.profiles_dir).and_then(|_| {
+                fs::rename(
+                    &single_filename,
+                    store
+                        .profiles_dir
+                        .join(format!("{}.json", DEFAULT_PROFILE_NAME)),
+                )
+            });
+
+            if let Err(e) = moved {
+                warn!("Moving single profile failed: {}", e);
+            }
+        }
+
+        store
+    }
+
+    pub fn get_names(&self) -> Vec<String> {
+        let entries = match fs::read_dir(&self.profiles_dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut names = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension() == Some("json".as_ref()))
+            .filter_map(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .map(|stem| stem.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        names.sort();
+
+        names
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.get_filename(name)
+            .map(|filename| filename.exists())
+            .unwrap_or(false)
+    }
+
+    // Falls back to the first profile when the remembered one is gone
+    pub fn get_active_name(&self) -> String {
+        let index = File::open(&self.index_filename)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, ProfileIndex>(BufReader::new(file)).ok())
+            .unwrap_or_default();
+
+        if self.exists(&index.active) {
+            return index.active;
+        }
+
+        self.get_names()
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string())
+    }
+
+    pub fn set_active_name(&self, name: &str) -> anyhow::Result<()> {
+        let index = ProfileIndex {
+            active: name.to_string(),
+        };
+
+        fs::create_dir_all(&self.profiles_dir).context("Failed to create profile directory")?;
+
+        let file = File::create(&self.index_filename).context("Failed to create profile index")?;
+        serde_json::to_writer_pretty(file, &index).context("Failed to write profile index")?;
+
+        Ok(())
+    }
+
+    pub fn load(&self, name: &str) -> anyhow::Result<Profile> {
+        Profile::load(&self.get_filename(name)?)
+    }
+
+    // Unreadable profile is kept as a backup instead of being overwritten on the next save
+    pub fn load_or_default(&self, name: &str) -> Profile {
+        match self.load(name) {
+            Ok(profile) => profile,
+            Err(e) => {
+                warn!(
+                    "Profile {} load failed, starting with an empty profile: {:?}",
+                    name, e
+                );
+
+                if let Ok(filename) = self.get_filename(name) {
+                    if let Err(e) = fs::rename(&filename, filename.with_extension("bak")) {
+                        warn!("Profile backup failed: {}", e);
+                    }
+                }
+
+                Profile::default()
+            }
+        }
+    }
+
+    pub fn save(&self, name: &str, profile: &Profile) -> anyhow::Result<()> {
+        profile.save(&self.get_filename(name)?)
+    }
+
+    pub fn create(&self, name: &str) -> anyhow::Result<()> {
+        self.check_new_name(name)?;
+
+        self.save(name, &Profile::default())
+    }
+
+    pub fn duplicate(&self, name: &str, new_name: &str) -> anyhow::Result<()> {
+        self.check_new_name(new_name)?;
+
+        // Loading a missing profile gives an empty one, which isn't a copy
+        if !self.exists(name) {
+            bail!("Profile {} doesn't exist", name);
+        }
+
+        let profile = self.load(name)?;
+
+        self.save(new_name, &profile)
+    }
+
+    pub fn rename(&self, name: &str, new_name: &str) -> anyhow::Result<()> {
+        self.check_new_name(new_name)?;
+
+        let filename = self.get_filename(name)?;
+
+        if !filename.exists() {
+            bail!("Profile {} doesn't exist", name);
+        }
+
+        let is_active = self.get_active_name() == name;
+
+        fs::rename(filename, self.get_filename(new_name)?).context("Failed to rename profile")?;
+
+        if is_active {
+            self.set_active_name(new_name)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> anyhow::Result<()> {
+        let filename = self.get_filename(name)?;
+
+        fs::remove_file(filename).context("Failed to delete profile")
+    }
+
+    fn check_new_name(&self, name: &str) -> anyhow::Result<()> {
+        if self.get_filename(name)?.exists() {
+            bail!("Profile {} already exists", name);
+        }
+
+        Ok(())
+    }
+
+    fn get_filename(&self, name: &str) -> anyhow::Result<PathBuf> {
+        let is_valid = !name.is_empty()
+            && name.trim() == name
+            && !name.starts_with('.')
+            && !name
+                .chars()
+                .any(|c| c.is_control() || INVALID_NAME_CHARS.contains(&c));
+
+        if !is_valid {
+            bail!("Invalid profile name: {}", name);
+        }
+
+        Ok(self.profiles_dir.join(format!("{}.json", name)))
+    }
+}
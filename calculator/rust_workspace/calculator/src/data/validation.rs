@@ -0,0 +1,346 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+    calc::{
+        constant::{MAX_SKILLS_COUNT, MAX_SLOT_LEVEL},
+        types::SkillSlotCount,
+    },
+    utils::parse::try_parse_data,
+};
+
+use super::{armor::BaseArmor, deco::Decoration, skill::Skill};
+
+// Problems found in armor.json, skill.json and deco.json before they are loaded
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DataReport {
+    pub issues: Vec<DataIssue>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DataIssue {
+    pub file: DataFile,
+    pub id: String,
+    pub is_error: bool,
+    pub kind: DataIssueKind,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DataFile {
+    Armor,
+    Skill,
+    Deco,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum DataIssueKind {
+    #[serde(rename_all = "camelCase")]
+    InvalidFile { message: String },
+    #[serde(rename_all = "camelCase")]
+    TooManySkills { count: usize, max_count: usize },
+    #[serde(rename_all = "camelCase")]
+    UnknownSkill { skill_id: String },
+    #[serde(rename_all = "camelCase")]
+    DecoWithoutSkill { skill_id: String },
+    #[serde(rename_all = "camelCase")]
+    InvalidSkillLevel {
+        skill_id: String,
+        level: SkillSlotCount,
+        max_level: SkillSlotCount,
+    },
+    #[serde(rename_all = "camelCase")]
+    InvalidSlotSize { slot_size: SkillSlotCount },
+    #[serde(rename_all = "camelCase")]
+    DuplicateName { name: String, other_id: String },
+}
+
+impl DataFile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataFile::Armor => "armor.json",
+            DataFile::Skill => "skill.json",
+            DataFile::Deco => "deco.json",
+        }
+    }
+}
+
+impl DataIssueKind {
+    // Errors would panic or break the calculation, warnings only give odd names or levels
+    fn is_error(&self) -> bool {
+        match self {
+            DataIssueKind::InvalidSkillLevel { level, .. } => *level <= 0,
+            DataIssueKind::DuplicateName { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+impl DataReport {
+    pub fn check(
+        armors: &HashMap<String, BaseArmor>,
+        skills: &HashMap<String, Skill>,
+        decos: &HashMap<String, Decoration>,
+    ) -> Self {
+        let mut report = Self::default();
+
+        if MAX_SKILLS_COUNT < skills.len() {
+            report.push(
+                DataFile::Skill,
+                "",
+                DataIssueKind::TooManySkills {
+                    count: skills.len(),
+                    max_count: MAX_SKILLS_COUNT,
+                },
+            );
+        }
+
+        for armor in Self::sorted_values(armors) {
+            let mut skill_ids = armor.skills.keys().collect::<Vec<_>>();
+            skill_ids.sort();
+
+            for skill_id in skill_ids {
+                let level = armor.skills[skill_id].level;
+
+                match skills.get(skill_id) {
+                    Some(skill) => report.check_level(DataFile::Armor, armor.id(), skill, level),
+                    None => report.push(
+                        DataFile::Armor,
+                        armor.id(),
+                        DataIssueKind::UnknownSkill {
+                            skill_id: skill_id.clone(),
+                        },
+                    ),
+                }
+            }
+
+            // Size 0 is an empty slot
+            for &slot_size in armor.slots.iter() {
+                if slot_size != 0 {
+                    report.check_slot_size(DataFile::Armor, armor.id(), slot_size);
+                }
+            }
+        }
+
+        for deco in Self::sorted_values(decos) {
+            match skills.get(&deco.skill_id) {
+                Some(skill) => {
+                    report.check_level(DataFile::Deco, &deco.id, skill, deco.skill_level)
+                }
+                None => report.push(
+                    DataFile::Deco,
+                    &deco.id,
+                    DataIssueKind::DecoWithoutSkill {
+                        skill_id: deco.skill_id.clone(),
+                    },
+                ),
+            }
+
+            report.check_slot_size(DataFile::Deco, &deco.id, deco.slot_size);
+        }
+
+        report.check_names(
+            DataFile::Armor,
+            Self::sorted_values(armors)
+                .into_iter()
+                .map(|armor| (armor.id(), armor.names.values())),
+        );
+        report.check_names(
+            DataFile::Skill,
+            Self::sorted_values(skills)
+                .into_iter()
+                .map(|skill| (&skill.id, skill.names.values())),
+        );
+
+        report
+    }
+
+    // Files that fail to parse are reported alone, the other checks need all of them
+    pub fn check_sources(
+        armors_filename: &Path,
+        skills_filename: &Path,
+        decos_filename: &Path,
+    ) -> Self {
+        let armors = try_parse_data::<BaseArmor, _>(armors_filename);
+        let skills = try_parse_data::<Skill, _>(skills_filename);
+        let decos = try_parse_data::<Decoration, _>(decos_filename);
+
+        match (armors, skills, decos) {
+            (Ok(armors), Ok(skills), Ok(decos)) => Self::check(
+                &armors
+                    .into_iter()
+                    .map(|armor| (armor.id().clone(), armor))
+                    .collect(),
+                &skills
+                    .into_iter()
+                    .map(|skill| (skill.id.clone(), skill))
+                    .collect(),
+                &decos
+                    .into_iter()
+                    .map(|deco| (deco.id.clone(), deco))
+                    .collect(),
+            ),
+            (armors, skills, decos) => {
+                let mut report = Self::default();
+
+                for (file, result) in [
+                    (DataFile::Armor, armors.err()),
+                    (DataFile::Skill, skills.err()),
+                    (DataFile::Deco, decos.err()),
+                ] {
+                    if let Some(err) = result {
+                        report.push(
+                            file,
+                            "",
+                            DataIssueKind::InvalidFile {
+                                message: format!("{:#}", err),
+                            },
+                        );
+                    }
+                }
+
+                report
+            }
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|issue| issue.is_error)
+    }
+
+    pub fn get_errors_count(&self) -> usize {
+        self.issues.iter().filter(|issue| issue.is_error).count()
+    }
+
+    fn push(&mut self, file: DataFile, id: &str, kind: DataIssueKind) {
+        self.issues.push(DataIssue {
+            file,
+            id: id.to_string(),
+            is_error: kind.is_error(),
+            kind,
+        });
+    }
+
+    fn check_level(&mut self, file: DataFile, id: &str, skill: &Skill, level: SkillSlotCount) {
+        if level <= 0 || skill.max_level < level {
+            self.push(
+                file,
+                id,
+                DataIssueKind::InvalidSkillLevel {
+                    skill_id: skill.id.clone(),
+                    level,
+                    max_level: skill.max_level,
+                },
+            );
+        }
+    }
+
+    fn check_slot_size(&mut self, file: DataFile, id: &str, slot_size: SkillSlotCount) {
+        if !(1..=MAX_SLOT_LEVEL as SkillSlotCount).contains(&slot_size) {
+            self.push(file, id, DataIssueKind::InvalidSlotSize { slot_size });
+        }
+    }
+
+    // Names of every language share one dictionary, so a name may only belong to one id
+    fn check_names<'a, I, N>(&mut self, file: DataFile, names_by_id: I)
+    where
+        I: Iterator<Item = (&'a String, N)>,
+        N: Iterator<Item = &'a String>,
+    {
+        let mut name_owners = HashMap::<&String, &String>::new();
+
+        for (id, names) in names_by_id {
+            let mut names = names.collect::<Vec<_>>();
+            names.sort();
+            names.dedup();
+
+            for name in names {
+                match name_owners.get(name) {
+                    Some(&other_id) if other_id != id => self.push(
+                        file,
+                        id,
+                        DataIssueKind::DuplicateName {
+                            name: name.clone(),
+                            other_id: other_id.clone(),
+                        },
+                    ),
+                    Some(_) => {}
+                    None => {
+                        name_owners.insert(name, id);
+                    }
+                }
+            }
+        }
+    }
+
+    fn sorted_values<T>(items: &HashMap<String, T>) -> Vec<&T> {
+        let mut ids = items.keys().collect::<Vec<_>>();
+        ids.sort();
+
+        ids.into_iter().map(|id| &items[id]).collect()
+    }
+}
+
+impl Display for DataIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.is_error { "error" } else { "warning" };
+
+        write!(f, "{}: {}", severity, self.file.as_str())?;
+
+        if !self.id.is_empty() {
+            write!(f, ", {}", self.id)?;
+        }
+
+        write!(f, ": ")?;
+
+        match &self.kind {
+            DataIssueKind::InvalidFile { message } => write!(f, "{}", message),
+            DataIssueKind::TooManySkills { count, max_count } => write!(
+                f,
+                "{} skills, only up to {} are supported",
+                count, max_count
+            ),
+            DataIssueKind::UnknownSkill { skill_id } => {
+                write!(f, "unknown skill \"{}\"", skill_id)
+            }
+            DataIssueKind::DecoWithoutSkill { skill_id } => {
+                write!(f, "decoration of unknown skill \"{}\"", skill_id)
+            }
+            DataIssueKind::InvalidSkillLevel {
+                skill_id,
+                level,
+                max_level,
+            } => write!(
+                f,
+                "skill \"{}\" level {} is outside 1..={}",
+                skill_id, level, max_level
+            ),
+            DataIssueKind::InvalidSlotSize { slot_size } => write!(
+                f,
+                "slot size {} is outside 1..={}",
+                slot_size, MAX_SLOT_LEVEL
+            ),
+            DataIssueKind::DuplicateName { name, other_id } => {
+                write!(f, "name \"{}\" is also used by {}", name, other_id)
+            }
+        }
+    }
+}
+
+impl Display for DataReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in self.issues.iter() {
+            writeln!(f, "{}", issue)?;
+        }
+
+        Ok(())
+    }
+}
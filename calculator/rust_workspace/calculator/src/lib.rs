@@ -2,3 +2,6 @@ mod utils;
 
 pub mod calc;
 pub mod data;
+
+#[cfg(test)]
+mod test;
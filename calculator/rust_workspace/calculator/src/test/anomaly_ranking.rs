@@ -0,0 +1,130 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use nohash_hasher::IntMap;
+
+use crate::{
+    calc::{
+        calc_anomaly_ranking::AnomalyRanker,
+        calc_data_manager::CalcDataManager,
+        calc_request::CalculateRequest,
+        cancel_token::CancelToken,
+        constant::{MAX_ANSWER_LENGTH, MAX_SLOT_LEVEL},
+    },
+    data::{
+        armor::{AnomalyArmor, ArmorPart, ArmorSkill, ArmorStat, SexType},
+        data_manager::DataManager,
+    },
+};
+
+// Carving Master comes from one armor only, which the request excludes.
+// Other parts are pinned, so each answer is one of these helms with the skill added
+const ANOMALY_ORIGINALS: [&str; 3] = ["kamura_head_scarf", "leather_headgear", "hunters_helm"];
+
+const PINNED_EQUIPS: [(ArmorPart, &str); 4] = [
+    (ArmorPart::Torso, "kamura_garb"),
+    (ArmorPart::Arm, "kamura_braces"),
+    (ArmorPart::Waist, "kamura_obi"),
+    (ArmorPart::Feet, "kamura_leggings"),
+];
+
+async fn load_dm() -> DataManager {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let mut dm = DataManager::from_sources(
+        &manifest_dir.join("../src-tauri/data/armor.json"),
+        &manifest_dir.join("../src-tauri/data/skill.json"),
+        &manifest_dir.join("../src-tauri/data/deco.json"),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let anomalies = ANOMALY_ORIGINALS
+        .iter()
+        .map(|id| {
+            AnomalyArmor::new(
+                &dm.get_armors()[*id],
+                ArmorStat::new_empty(),
+                vec![0, 0, 0],
+                HashMap::from([("carving_master".to_string(), ArmorSkill { level: 1 })]),
+            )
+        })
+        .collect();
+
+    dm.set_file_anomalies(anomalies);
+
+    dm
+}
+
+fn get_request(dm: &DataManager, answer_limit: usize) -> CalculateRequest {
+    let mut selected_skills = IntMap::default();
+    selected_skills.insert(dm.get_skill_uid("carving_master"), 1);
+
+    let mut request = CalculateRequest::new(
+        vec![0, 0, 0],
+        selected_skills,
+        vec![0; MAX_SLOT_LEVEL],
+        SexType::Female,
+        false,
+    );
+
+    request.pinned_equips = PINNED_EQUIPS
+        .iter()
+        .map(|(part, id)| (part.clone(), id.to_string()))
+        .collect();
+    request.excluded_equips = HashSet::from(["feather_of_mastery".to_string()]);
+    request.answer_limit = answer_limit;
+
+    request
+}
+
+fn load_cm(dm: &DataManager) -> CalcDataManager {
+    let mut cm = CalcDataManager::new(dm);
+    cm.load_base_armors(dm);
+    cm.load_anomalies(dm);
+
+    cm
+}
+
+fn get_used_counts(dm: &DataManager, answer_limit: usize) -> HashMap<String, usize> {
+    let mut cm = load_cm(dm);
+    let request = get_request(dm, answer_limit);
+
+    AnomalyRanker::rank(dm, &mut cm, &[request], &CancelToken::default())
+        .unwrap()
+        .into_iter()
+        .map(|ranking| (ranking.anomaly.affected.id().clone(), ranking.used_count))
+        .collect()
+}
+
+#[tokio::test]
+async fn ranking_follows_every_answer_page() {
+    let dm = load_dm().await;
+
+    let all_used_counts = get_used_counts(&dm, MAX_ANSWER_LENGTH);
+
+    assert_eq!(all_used_counts.len(), ANOMALY_ORIGINALS.len());
+    assert!(all_used_counts.values().all(|&count| count == 1));
+
+    // One answer per page, so all but one anomaly only show up after the first page
+    assert_eq!(get_used_counts(&dm, 1), all_used_counts);
+}
+
+// Anomalies the cancelled ranking did not reach must not be listed as unused
+#[tokio::test]
+async fn cancelled_ranking_is_error() {
+    let dm = load_dm().await;
+
+    let mut cm = load_cm(&dm);
+    let request = get_request(&dm, MAX_ANSWER_LENGTH);
+
+    let cancel_token = CancelToken::new();
+    cancel_token.cancel();
+
+    let result = AnomalyRanker::rank(&dm, &mut cm, &[request], &cancel_token);
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,280 @@
+use std::{
+    cmp::Reverse,
+    sync::{
+        mpsc::{self, Sender},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    calc::{
+        calc_reporter::{CalcProgress, CalcReporter},
+        calc_request::CalculateRequest,
+        calc_result::{CalculateResult, ResultFullEquipments},
+        calc_state::CalcState,
+        calculator::Calculator,
+        cancel_token::CancelToken,
+        types::SkillSlotCount,
+    },
+    data::data_manager::DataManager,
+};
+
+use super::common::{get_request, load_cm, load_dm};
+
+// Leftover slots the result is sorted by, then armor ids and talisman of an answer
+type AnswerKey = (
+    Reverse<Vec<i32>>,
+    Vec<(String, String)>,
+    Vec<(String, SkillSlotCount)>,
+    Vec<SkillSlotCount>,
+);
+
+fn get_answer_key(answer: &ResultFullEquipments) -> AnswerKey {
+    let leftover_slots = answer
+        .deco_combs
+        .iter()
+        .map(|deco_comb| {
+            let slots = &deco_comb.leftover_slots_sum;
+
+            let mut values = vec![slots.iter().map(|&val| val as i32).sum::<i32>()];
+            values.extend(slots.iter().rev().map(|&val| val as i32));
+
+            values
+        })
+        .max()
+        .unwrap_or_default();
+
+    let mut armors = answer
+        .armors
+        .iter()
+        .map(|(part, armor)| (part.clone(), armor.id.clone()))
+        .collect::<Vec<_>>();
+    armors.sort();
+
+    let mut talisman_skills = answer
+        .talisman
+        .skills
+        .iter()
+        .map(|(id, level)| (id.clone(), *level))
+        .collect::<Vec<_>>();
+    talisman_skills.sort();
+
+    (
+        Reverse(leftover_slots),
+        armors,
+        talisman_skills,
+        answer.talisman.slots.clone(),
+    )
+}
+
+fn get_answer_keys(result: &CalculateResult) -> Vec<AnswerKey> {
+    result.full_equipments.iter().map(get_answer_key).collect()
+}
+
+#[derive(Default)]
+struct RecordingReporter {
+    progresses: Mutex<Vec<CalcProgress>>,
+    answers: Mutex<Vec<AnswerKey>>,
+}
+
+impl CalcReporter for RecordingReporter {
+    fn on_progress(&self, progress: &CalcProgress) {
+        self.progresses.lock().unwrap().push(progress.clone());
+    }
+
+    fn is_streaming_answers(&self) -> bool {
+        true
+    }
+
+    fn on_answer(&self, answer: &ResultFullEquipments) {
+        self.answers.lock().unwrap().push(get_answer_key(answer));
+    }
+}
+
+// Records the answers and holds the search at its first one until the cancel reached it
+struct CancelledReporter<'a> {
+    state: &'a CalcState,
+    first_answer: Mutex<Option<Sender<()>>>,
+    answers: Mutex<Vec<AnswerKey>>,
+}
+
+impl CalcReporter for CancelledReporter<'_> {
+    fn is_streaming_answers(&self) -> bool {
+        true
+    }
+
+    fn on_answer(&self, answer: &ResultFullEquipments) {
+        self.answers.lock().unwrap().push(get_answer_key(answer));
+
+        if let Some(sender) = self.first_answer.lock().unwrap().take() {
+            sender.send(()).unwrap();
+
+            while !self.state.cancel_token().is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+// Answers are streamed as found, the result sorts them keeping that order for ties
+fn assert_streamed_answers(answers: &[AnswerKey], result: &CalculateResult) {
+    let mut sorted_answers = answers.to_vec();
+    sorted_answers.sort_by(|key1, key2| key1.0.cmp(&key2.0));
+
+    assert_eq!(sorted_answers, get_answer_keys(result));
+}
+
+fn calculate(dm: &DataManager, request: &CalculateRequest) -> (CalculateResult, RecordingReporter) {
+    let reporter = RecordingReporter::default();
+
+    let (_, result) = Calculator::calculate(
+        request,
+        dm,
+        &load_cm(dm, request),
+        &CancelToken::default(),
+        &reporter,
+    )
+    .unwrap();
+
+    (result, reporter)
+}
+
+// Progress only moves forward and the done report comes once, at the end
+fn assert_progresses(progresses: &[CalcProgress]) {
+    assert!(1 < progresses.len());
+
+    assert!(progresses.windows(2).all(|pair| {
+        pair[0].processed_candidates <= pair[1].processed_candidates
+            && pair[0].candidates_count == pair[1].candidates_count
+            && pair[0].elapsed <= pair[1].elapsed
+    }));
+
+    assert!(progresses
+        .iter()
+        .all(|progress| progress.processed_candidates <= progress.candidates_count));
+
+    assert_eq!(
+        progresses
+            .iter()
+            .filter(|progress| progress.is_done)
+            .count(),
+        1
+    );
+    assert!(progresses.last().unwrap().is_done);
+}
+
+#[tokio::test]
+async fn progress_reaches_every_candidate() {
+    let dm = load_dm().await;
+    let request = get_request(&dm);
+
+    let (result, reporter) = calculate(&dm, &request);
+    let progresses = reporter.progresses.into_inner().unwrap();
+
+    assert_progresses(&progresses);
+
+    let last = progresses.last().unwrap();
+
+    assert!(result.next_cursor.is_none());
+    assert_eq!(last.processed_candidates, last.candidates_count);
+    assert_eq!(last.answers_count, result.full_equipments.len());
+}
+
+// Stopped at the answer limit, the done report still comes once
+#[tokio::test]
+async fn limited_progress_is_done_once() {
+    let dm = load_dm().await;
+
+    let mut request = get_request(&dm);
+    request.answer_limit = 7;
+
+    let (result, reporter) = calculate(&dm, &request);
+    let progresses = reporter.progresses.into_inner().unwrap();
+
+    assert_progresses(&progresses);
+
+    let last = progresses.last().unwrap();
+
+    assert!(result.next_cursor.is_some());
+    assert_eq!(last.answers_count, result.full_equipments.len());
+    assert_eq!(last.answers_count, request.answer_limit);
+}
+
+#[tokio::test]
+async fn streamed_answers_match_result() {
+    let dm = load_dm().await;
+    let request = get_request(&dm);
+
+    let (result, reporter) = calculate(&dm, &request);
+    let answers = reporter.answers.into_inner().unwrap();
+
+    assert_eq!(answers.len(), 82);
+    assert_streamed_answers(&answers, &result);
+}
+
+#[tokio::test]
+async fn streamed_answers_match_limited_result() {
+    let dm = load_dm().await;
+
+    let mut request = get_request(&dm);
+    request.answer_limit = 7;
+
+    let (result, reporter) = calculate(&dm, &request);
+    let answers = reporter.answers.into_inner().unwrap();
+
+    assert_eq!(answers.len(), request.answer_limit);
+    assert_streamed_answers(&answers, &result);
+}
+
+// The partial result handed to the cancel is the answers streamed so far
+#[tokio::test]
+async fn streamed_answers_match_cancelled_result() {
+    let dm = load_dm().await;
+    let request = get_request(&dm);
+    let cm = load_cm(&dm, &request);
+
+    let state = &CalcState::default();
+
+    let (sender, receiver) = mpsc::channel();
+
+    let reporter = CancelledReporter {
+        state,
+        first_answer: Mutex::new(Some(sender)),
+        answers: Mutex::new(Vec::new()),
+    };
+
+    let calc_guard = state.start().await;
+
+    let (result, cancelled_result) = thread::scope(|scope| {
+        let canceller = scope.spawn(move || {
+            receiver.recv().unwrap();
+
+            block_on(state.cancel())
+        });
+
+        let (_, result) =
+            Calculator::calculate(&request, &dm, &cm, state.cancel_token(), &reporter).unwrap();
+
+        calc_guard.set_result(&result);
+        drop(calc_guard);
+
+        (result, canceller.join().unwrap())
+    });
+
+    let cancelled_result = cancelled_result.expect("Cancel returns the partial result");
+    let answers = reporter.answers.into_inner().unwrap();
+
+    assert!(!answers.is_empty());
+    assert!(answers.len() < 82);
+    assert_streamed_answers(&answers, &result);
+    assert_streamed_answers(&answers, &cancelled_result);
+}
@@ -0,0 +1,132 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{
+        mpsc::{self, Sender},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::calc::{
+    calc_reporter::{CalcReporter, EmptyReporter},
+    calc_result::{CalculateResult, ResultFullEquipments},
+    calc_state::CalcState,
+    calculator::Calculator,
+};
+
+use super::common::{get_request, load_cm, load_dm};
+
+fn get_answer_keys(result: &CalculateResult) -> BTreeSet<BTreeMap<String, String>> {
+    result
+        .full_equipments
+        .iter()
+        .map(|full_equip| {
+            full_equip
+                .armors
+                .iter()
+                .map(|(part, armor)| (part.clone(), armor.id.clone()))
+                .collect()
+        })
+        .collect()
+}
+
+// Holds the search at its first answer until the cancel reached it
+struct CancelledReporter<'a> {
+    state: &'a CalcState,
+    first_answer: Mutex<Option<Sender<()>>>,
+}
+
+impl CalcReporter for CancelledReporter<'_> {
+    fn is_streaming_answers(&self) -> bool {
+        true
+    }
+
+    fn on_answer(&self, _answer: &ResultFullEquipments) {
+        if let Some(sender) = self.first_answer.lock().unwrap().take() {
+            sender.send(()).unwrap();
+
+            while !self.state.cancel_token().is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+#[tokio::test]
+async fn cancel_returns_partial_result() {
+    let dm = load_dm().await;
+    let request = get_request(&dm);
+    let cm = load_cm(&dm, &request);
+
+    let state = &CalcState::default();
+
+    let full_result = {
+        let _calc_guard = state.start().await;
+
+        Calculator::calculate(&request, &dm, &cm, state.cancel_token(), &EmptyReporter {})
+            .unwrap()
+            .1
+    };
+
+    let (sender, receiver) = mpsc::channel();
+
+    let reporter = CancelledReporter {
+        state,
+        first_answer: Mutex::new(Some(sender)),
+    };
+
+    let calc_guard = state.start().await;
+
+    let (result, cancelled_result) = thread::scope(|scope| {
+        let canceller = scope.spawn(move || {
+            receiver.recv().unwrap();
+
+            block_on(state.cancel())
+        });
+
+        let (_, result) =
+            Calculator::calculate(&request, &dm, &cm, state.cancel_token(), &reporter).unwrap();
+
+        calc_guard.set_result(&result);
+        drop(calc_guard);
+
+        (result, canceller.join().unwrap())
+    });
+
+    let cancelled_result = cancelled_result.expect("Cancel returns the partial result");
+
+    assert!(!result.full_equipments.is_empty());
+    assert!(result.full_equipments.len() < full_result.full_equipments.len());
+    assert_eq!(get_answer_keys(&cancelled_result), get_answer_keys(&result));
+    assert!(get_answer_keys(&result).is_subset(&get_answer_keys(&full_result)));
+}
+
+#[tokio::test]
+async fn cancel_while_idle_does_not_cancel_next_search() {
+    let dm = load_dm().await;
+    let request = get_request(&dm);
+    let cm = load_cm(&dm, &request);
+
+    let state = CalcState::default();
+
+    assert!(state.cancel().await.is_none());
+
+    let calc_guard = state.start().await;
+
+    assert!(!state.cancel_token().is_cancelled());
+
+    let (_, result) =
+        Calculator::calculate(&request, &dm, &cm, state.cancel_token(), &EmptyReporter {}).unwrap();
+
+    drop(calc_guard);
+
+    assert_eq!(result.full_equipments.len(), 82);
+}
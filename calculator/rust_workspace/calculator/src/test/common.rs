@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use nohash_hasher::IntMap;
+
+use crate::{
+    calc::{
+        calc_data_manager::CalcDataManager, calc_reporter::EmptyReporter,
+        calc_request::CalculateRequest, calc_result::CalculateResult, calculator::Calculator,
+        cancel_token::CancelToken, constant::MAX_SLOT_LEVEL, types::SkillSlotCount,
+    },
+    data::{
+        armor::{ArmorPart, SexType},
+        data_manager::DataManager,
+    },
+};
+
+// Search over the bundled data with 82 answers, small enough for many runs in one test
+pub(super) const PINNED_EQUIPS: [(ArmorPart, &str); 3] = [
+    (ArmorPart::Torso, "kamura_garb"),
+    (ArmorPart::Arm, "kamura_braces"),
+    (ArmorPart::Waist, "kamura_obi"),
+];
+
+pub(super) const REQ_SKILLS: [(&str, SkillSlotCount); 2] =
+    [("attack_boost", 5), ("critical_eye", 4)];
+
+pub(super) async fn load_dm() -> DataManager {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    DataManager::from_sources(
+        &manifest_dir.join("../src-tauri/data/armor.json"),
+        &manifest_dir.join("../src-tauri/data/skill.json"),
+        &manifest_dir.join("../src-tauri/data/deco.json"),
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+pub(super) fn load_cm(dm: &DataManager, request: &CalculateRequest) -> CalcDataManager {
+    let mut cm = CalcDataManager::new(dm);
+    cm.load_base_armors(dm);
+    cm.load_anomalies(dm);
+    cm.load_talismans(dm);
+    cm.refresh_infos(
+        dm,
+        &Calculator::convert_to_skills_container(&request.selected_skills),
+    );
+
+    cm
+}
+
+// Request of REQ_SKILLS with PINNED_EQUIPS
+pub(super) fn get_request(dm: &DataManager) -> CalculateRequest {
+    let selected_skills = REQ_SKILLS
+        .iter()
+        .map(|(id, level)| (dm.get_skill_uid(id), *level))
+        .collect::<IntMap<_, _>>();
+
+    let mut request = CalculateRequest::new(
+        vec![0, 0, 0],
+        selected_skills,
+        vec![0; MAX_SLOT_LEVEL],
+        SexType::Female,
+        false,
+    );
+
+    request.pinned_equips = PINNED_EQUIPS
+        .iter()
+        .map(|(part, id)| (part.clone(), id.to_string()))
+        .collect();
+
+    request
+}
+
+pub(super) fn calculate(
+    dm: &DataManager,
+    cm: &CalcDataManager,
+    request: &CalculateRequest,
+) -> anyhow::Result<CalculateResult> {
+    Calculator::calculate(request, dm, cm, &CancelToken::default(), &EmptyReporter {})
+        .map(|(_, result)| result)
+}
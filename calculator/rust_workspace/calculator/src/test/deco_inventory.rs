@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use crate::{
+    calc::types::SkillSlotCount,
+    data::{data_manager::DataManager, deco::Decoration},
+};
+
+use super::{
+    common::{calculate, load_cm, load_dm},
+    differential::{random_case, GameData},
+    rng::TestRng,
+};
+
+const INVENTORY_COUNT: u64 = 20;
+const CASES_COUNT: u64 = 100;
+
+fn get_all_decos(dm: &DataManager) -> Vec<Decoration> {
+    (0..dm.get_skills().len())
+        .flat_map(|uid| dm.get_deco_by_skill_id(uid).clone())
+        .collect()
+}
+
+// Decorations not in the inventory can be used without limit
+fn get_owned_count(
+    deco_inventory: &HashMap<String, SkillSlotCount>,
+    deco: &Decoration,
+) -> SkillSlotCount {
+    deco_inventory
+        .get(&deco.id)
+        .copied()
+        .unwrap_or(SkillSlotCount::MAX)
+}
+
+// Combinations of each skill level use owned decorations only, no more than owned,
+// and a level has combinations exactly when the owned decorations reach it
+#[tokio::test]
+async fn skill_combinations_respect_inventory() {
+    let mut dm = load_dm().await;
+    let all_decos = get_all_decos(&dm);
+
+    let mut limited_levels_count = 0;
+
+    for seed in 0..INVENTORY_COUNT {
+        let mut rng = TestRng::new(seed);
+
+        let mut deco_inventory = HashMap::new();
+
+        for deco in all_decos.iter() {
+            if rng.chance(50) {
+                deco_inventory.insert(deco.id.clone(), rng.range(0, 3) as SkillSlotCount);
+            }
+        }
+
+        dm.set_deco_inventory(&deco_inventory);
+
+        for (uid, skill) in dm.get_skills().iter().enumerate() {
+            let decos = dm.get_deco_by_skill_id(uid);
+
+            if decos.is_empty() {
+                continue;
+            }
+
+            let max_owned_level = decos
+                .iter()
+                .map(|deco| {
+                    let owned_count = get_owned_count(&deco_inventory, deco).min(skill.max_level);
+
+                    owned_count * deco.skill_level
+                })
+                .sum::<SkillSlotCount>();
+
+            for (level_index, level_combs) in dm.get_deco_combs(uid).iter().enumerate() {
+                let req_level = level_index as SkillSlotCount + 1;
+
+                assert_eq!(
+                    level_combs.is_empty(),
+                    max_owned_level < req_level,
+                    "{} Lv{} with {:?}",
+                    skill.id,
+                    req_level,
+                    deco_inventory
+                );
+
+                if max_owned_level < req_level {
+                    limited_levels_count += 1;
+                }
+
+                for comb in level_combs {
+                    let mut level_sum = 0;
+
+                    for (size_index, &count) in comb.iter().enumerate() {
+                        if count == 0 {
+                            continue;
+                        }
+
+                        let deco = decos
+                            .iter()
+                            .find(|deco| deco.slot_size as usize == size_index + 1)
+                            .unwrap();
+
+                        assert!(
+                            count <= get_owned_count(&deco_inventory, deco),
+                            "{} Lv{}: {} x{}",
+                            skill.id,
+                            req_level,
+                            deco.id,
+                            count
+                        );
+
+                        level_sum += count * deco.skill_level;
+                    }
+
+                    assert!(req_level <= level_sum);
+                }
+            }
+        }
+    }
+
+    assert!(0 < limited_levels_count);
+}
+
+// Decorations of every answer are owned ones, no more than owned
+#[test]
+fn answers_respect_inventory() {
+    let data = GameData::load();
+
+    let mut limited_used_count = 0;
+
+    for seed in 0..CASES_COUNT {
+        let case = random_case(seed, &data);
+        let dm = &case.dm;
+
+        let cm = load_cm(dm, &case.request);
+        let result = calculate(dm, &cm, &case.request).unwrap();
+
+        let deco_combs = result
+            .full_equipments
+            .iter()
+            .flat_map(|full_equip| full_equip.deco_combs.iter());
+
+        for deco_comb in deco_combs {
+            for (skill_id, counts) in deco_comb.skill_decos.iter() {
+                let decos = dm.get_deco_by_skill_id(dm.get_skill_uid(skill_id));
+
+                for (deco, &count) in decos.iter().zip(counts) {
+                    if count == 0 {
+                        continue;
+                    }
+
+                    assert!(!case.banned_decos.contains(&deco.id), "Seed {}", seed);
+                    assert!(
+                        count <= get_owned_count(&case.deco_inventory, deco),
+                        "Seed {}: {} x{}",
+                        seed,
+                        deco.id,
+                        count
+                    );
+
+                    if case.deco_inventory.contains_key(&deco.id) {
+                        limited_used_count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    assert!(0 < limited_used_count);
+}
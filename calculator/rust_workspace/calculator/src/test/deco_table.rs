@@ -0,0 +1,332 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use nohash_hasher::IntMap;
+
+use crate::{
+    calc::{
+        calculator::Calculator,
+        deco_combination::{DecorationCombination, DecorationCombinations},
+        types::SkillSlotCount,
+    },
+    data::{data_manager::DataManager, deco::Decoration, deco_table::DecoTableStore, skill::Skill},
+};
+
+const REQ_SKILLS: [(&str, SkillSlotCount); 4] = [
+    ("attack_boost", 5),
+    ("critical_eye", 7),
+    ("weakness_exploit", 3),
+    ("evade_window", 4),
+];
+
+async fn load_dm(deco_table_dir: Option<&Path>) -> DataManager {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    DataManager::from_sources(
+        &manifest_dir.join("../src-tauri/data/armor.json"),
+        &manifest_dir.join("../src-tauri/data/skill.json"),
+        &manifest_dir.join("../src-tauri/data/deco.json"),
+        deco_table_dir,
+    )
+    .await
+    .unwrap()
+}
+
+fn get_temp_dir(name: &str) -> PathBuf {
+    let temp_dir =
+        std::env::temp_dir().join(format!("mhr_calculator_{}_{}", name, std::process::id()));
+
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    temp_dir
+}
+
+fn get_skill_id_map(dm: &DataManager) -> HashMap<String, usize> {
+    dm.get_skills()
+        .iter()
+        .enumerate()
+        .map(|(uid, skill)| (skill.id.clone(), uid))
+        .collect()
+}
+
+fn build_combinations(dm: &DataManager) -> DecorationCombinations {
+    let decos_by_skill = (0..dm.get_skills().len())
+        .map(|uid| dm.get_deco_by_skill_id(uid).clone())
+        .collect::<Vec<_>>();
+
+    DecorationCombinations::new(&decos_by_skill, dm.get_skills(), &HashMap::new())
+}
+
+fn get_full_combs(
+    dm: &DataManager,
+    deco_combinations: &DecorationCombinations,
+) -> Vec<BTreeMap<String, Vec<SkillSlotCount>>> {
+    let req_skills = REQ_SKILLS
+        .iter()
+        .map(|(skill_id, level)| (dm.get_skill_uid(skill_id), *level))
+        .collect::<IntMap<_, _>>();
+
+    let full_combs = deco_combinations
+        .get_full_possible_combs(&Calculator::convert_to_skills_container(&req_skills));
+
+    let mut full_combs = full_combs
+        .iter()
+        .map(|deco_comb: &DecorationCombination| {
+            deco_comb
+                .combs_per_skill_lp
+                .iter()
+                .map(|(&uid, slots_lp)| {
+                    (
+                        dm.get_skill(uid).id.clone(),
+                        slots_lp.iter().copied().collect(),
+                    )
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+
+    full_combs.sort();
+
+    full_combs
+}
+
+fn count_files(dir: &Path) -> usize {
+    std::fs::read_dir(dir).unwrap().count()
+}
+
+#[tokio::test]
+async fn saved_table_loads_with_other_skill_order() {
+    let saved_dm = load_dm(None).await;
+    let loaded_dm = load_dm(None).await;
+
+    let saved = build_combinations(&saved_dm);
+    let saved_full_combs = get_full_combs(&saved_dm, &saved);
+
+    let tables_dir = get_temp_dir("deco_table_round_trip");
+    let store = DecoTableStore::new(&tables_dir);
+    let key = 1;
+
+    store.save(key, &saved, saved_dm.get_skills()).unwrap();
+
+    assert!(store.load(key + 1, &get_skill_id_map(&loaded_dm)).is_none());
+
+    let loaded = store.load(key, &get_skill_id_map(&loaded_dm)).unwrap();
+
+    for skill in saved_dm.get_skills() {
+        let saved_uid = saved_dm.get_skill_uid(&skill.id);
+        let loaded_uid = loaded_dm.get_skill_uid(&skill.id);
+
+        assert_eq!(
+            saved.combinations[saved_uid], loaded.combinations[loaded_uid],
+            "{}",
+            skill.id
+        );
+        assert_eq!(
+            saved.combinations_lp_mins[saved_uid], loaded.combinations_lp_mins[loaded_uid],
+            "{}",
+            skill.id
+        );
+    }
+
+    // Warmed cache is hit, not built again under another key
+    assert_eq!(loaded.get_caches_len(), 1);
+    assert_eq!(get_full_combs(&loaded_dm, &loaded), saved_full_combs);
+    assert_eq!(loaded.get_caches_len(), 1);
+
+    // Broken file is built again instead
+    let filename = std::fs::read_dir(&tables_dir)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let bytes = std::fs::read(&filename).unwrap();
+    std::fs::write(&filename, &bytes[..bytes.len() / 2]).unwrap();
+
+    assert!(store.load(key, &get_skill_id_map(&loaded_dm)).is_none());
+
+    std::fs::remove_dir_all(&tables_dir).unwrap();
+}
+
+#[tokio::test]
+async fn banned_decos_reuse_saved_tables() {
+    let tables_dir = get_temp_dir("deco_table_banned");
+
+    let banned_decos = HashSet::from(["attack_jewel_4".to_string()]);
+
+    let mut dm = load_dm(Some(&tables_dir)).await;
+
+    assert!(!tables_dir.exists());
+
+    dm.save_deco_table();
+    assert_eq!(count_files(&tables_dir), 1);
+
+    dm.set_banned_decos(&banned_decos);
+    assert_eq!(count_files(&tables_dir), 2);
+
+    let banned_combs = dm.get_deco_combs(dm.get_skill_uid("attack_boost")).clone();
+
+    dm.set_banned_decos(&HashSet::new());
+    dm.set_banned_decos(&banned_decos);
+    assert_eq!(count_files(&tables_dir), 2);
+
+    assert_eq!(
+        dm.get_deco_combs(dm.get_skill_uid("attack_boost")),
+        &banned_combs
+    );
+
+    let mut other_dm = load_dm(Some(&tables_dir)).await;
+    other_dm.set_banned_decos(&banned_decos);
+
+    assert_eq!(
+        other_dm.get_deco_combs(other_dm.get_skill_uid("attack_boost")),
+        &banned_combs
+    );
+    assert_eq!(count_files(&tables_dir), 2);
+
+    std::fs::remove_dir_all(&tables_dir).unwrap();
+}
+
+#[tokio::test]
+async fn old_tables_are_removed() {
+    let dm = load_dm(None).await;
+    let deco_combinations = build_combinations(&dm);
+
+    let tables_dir = get_temp_dir("deco_table_remove_old");
+    let store = DecoTableStore::new(&tables_dir);
+
+    std::fs::create_dir_all(&tables_dir).unwrap();
+    std::fs::write(tables_dir.join("other.bin"), []).unwrap();
+
+    for key in 1..=6 {
+        store
+            .save(key, &deco_combinations, dm.get_skills())
+            .unwrap();
+    }
+
+    // Latest saved tables and files not made by the store are left
+    assert_eq!(count_files(&tables_dir), 5);
+    assert!(tables_dir.join("other.bin").exists());
+    assert!(store.load(6, &get_skill_id_map(&dm)).is_some());
+
+    // Saving a kept table again doesn't remove it
+    store.save(3, &deco_combinations, dm.get_skills()).unwrap();
+
+    assert_eq!(count_files(&tables_dir), 5);
+    assert!(store.load(3, &get_skill_id_map(&dm)).is_some());
+
+    std::fs::remove_dir_all(&tables_dir).unwrap();
+}
+
+#[tokio::test]
+async fn deco_settings_are_applied_at_once() {
+    let tables_dir = get_temp_dir("deco_table_settings");
+
+    let banned_decos = HashSet::from(["attack_jewel_4".to_string()]);
+    let deco_inventory = HashMap::from([("critical_jewel_2".to_string(), 1)]);
+
+    let mut dm = load_dm(Some(&tables_dir)).await;
+
+    dm.set_deco_settings(&banned_decos, &deco_inventory);
+    assert_eq!(count_files(&tables_dir), 2);
+
+    let combs = dm.get_deco_combs(dm.get_skill_uid("attack_boost")).clone();
+    let key = dm.get_deco_table_key();
+
+    dm.set_deco_settings(&banned_decos, &deco_inventory);
+    assert_eq!(count_files(&tables_dir), 2);
+
+    let mut other_dm = load_dm(None).await;
+    other_dm.set_banned_decos(&banned_decos);
+    other_dm.set_deco_inventory(&deco_inventory);
+
+    assert_eq!(other_dm.get_deco_table_key(), key);
+    assert_eq!(
+        other_dm.get_deco_combs(other_dm.get_skill_uid("attack_boost")),
+        &combs
+    );
+
+    std::fs::remove_dir_all(&tables_dir).unwrap();
+}
+
+#[tokio::test]
+async fn startup_loads_saved_table() {
+    let tables_dir = get_temp_dir("deco_table_startup");
+
+    let mut dm = load_dm(Some(&tables_dir)).await;
+    dm.save_deco_table();
+    assert_eq!(count_files(&tables_dir), 1);
+
+    let filename = std::fs::read_dir(&tables_dir)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .file_name();
+    let key = u64::from_str_radix(
+        filename
+            .to_str()
+            .unwrap()
+            .trim_start_matches("deco_table_")
+            .trim_end_matches(".bin"),
+        16,
+    )
+    .unwrap();
+
+    // Table of another ban set saved under the startup key tells a load from a build
+    dm.set_banned_decos(&HashSet::from(["attack_jewel_4".to_string()]));
+
+    let banned = build_combinations(&dm);
+    let banned_combs = dm.get_deco_combs(dm.get_skill_uid("attack_boost")).clone();
+
+    DecoTableStore::new(&tables_dir)
+        .save(key, &banned, dm.get_skills())
+        .unwrap();
+
+    let loaded_dm = load_dm(Some(&tables_dir)).await;
+
+    assert_eq!(
+        loaded_dm.get_deco_combs(loaded_dm.get_skill_uid("attack_boost")),
+        &banned_combs
+    );
+
+    let built_dm = load_dm(None).await;
+
+    assert_ne!(
+        built_dm.get_deco_combs(built_dm.get_skill_uid("attack_boost")),
+        &banned_combs
+    );
+
+    std::fs::remove_dir_all(&tables_dir).unwrap();
+}
+
+// Saved files are named by the key, it must not change between builds
+#[test]
+fn table_key_is_fixed() {
+    let decos = HashMap::from([(
+        "attack_jewel".to_string(),
+        Decoration {
+            id: "attack_jewel".to_string(),
+            names: HashMap::new(),
+            skill_id: "attack_boost".to_string(),
+            skill_level: 1,
+            slot_size: 2,
+        },
+    )]);
+
+    let skills = [Skill {
+        id: "attack_boost".to_string(),
+        max_level: 7,
+        names: HashMap::new(),
+    }];
+
+    let banned_decos = HashSet::from(["attack_jewel".to_string()]);
+    let deco_inventory = HashMap::from([("attack_jewel".to_string(), 3)]);
+
+    assert_eq!(
+        DecoTableStore::get_key(&decos, &skills, &banned_decos, &deco_inventory),
+        0xac203ea07fd4296a
+    );
+}
@@ -0,0 +1,348 @@
+use std::collections::{HashMap, HashSet};
+
+use nohash_hasher::IntMap;
+use serde_json::json;
+
+use crate::{
+    calc::{calc_request::CalculateRequest, constant::MAX_SLOT_LEVEL, types::SkillSlotCount},
+    data::{
+        armor::{
+            AnomalyArmor, ArmorPart, ArmorSkill, ArmorStat, BaseArmor, SexType, SkillIdLevel,
+            Talisman,
+        },
+        data_manager::DataManager,
+        deco::Decoration,
+        skill::Skill,
+    },
+    utils::parse::try_parse_data,
+};
+
+use super::{
+    common::{calculate, load_cm},
+    oracle::BruteForceOracle,
+    rng::TestRng,
+};
+
+const CASES_COUNT: u64 = 300;
+const ANSWER_LIMIT: usize = 100_000;
+
+pub(super) struct GameData {
+    skills: HashMap<String, Skill>,
+    decos: Vec<Decoration>,
+}
+
+impl GameData {
+    pub(super) fn load() -> Self {
+        let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf();
+
+        let skills = try_parse_data::<Skill, _>(manifest_dir.join("../src-tauri/data/skill.json"))
+            .unwrap()
+            .into_iter()
+            .map(|skill| (skill.id.clone(), skill))
+            .collect();
+        let decos =
+            try_parse_data::<Decoration, _>(manifest_dir.join("../src-tauri/data/deco.json"))
+                .unwrap();
+
+        Self { skills, decos }
+    }
+}
+
+pub(super) struct TestCase {
+    pub(super) dm: DataManager,
+    pub(super) decos: Vec<Decoration>,
+    pub(super) banned_decos: HashSet<String>,
+    pub(super) deco_inventory: HashMap<String, SkillSlotCount>,
+    pub(super) req_skills: HashMap<String, SkillSlotCount>,
+    pub(super) request: CalculateRequest,
+}
+
+fn random_slots(rng: &mut TestRng, len: usize, max_size: i64) -> Vec<SkillSlotCount> {
+    let mut slots = (0..len)
+        .map(|_| {
+            if rng.chance(40) {
+                0
+            } else {
+                rng.range(1, max_size) as SkillSlotCount
+            }
+        })
+        .collect::<Vec<_>>();
+
+    slots.sort_unstable_by(|size1, size2| size2.cmp(size1));
+
+    slots
+}
+
+fn random_skills(
+    rng: &mut TestRng,
+    skill_ids: &[String],
+    max_count: i64,
+) -> Vec<(String, SkillSlotCount)> {
+    let mut skills = HashMap::new();
+
+    for _ in 0..rng.range(0, max_count) {
+        skills.insert(
+            rng.pick(skill_ids).clone(),
+            rng.range(1, 2) as SkillSlotCount,
+        );
+    }
+
+    let mut skills = skills.into_iter().collect::<Vec<_>>();
+    skills.sort();
+
+    skills
+}
+
+fn random_armor(
+    rng: &mut TestRng,
+    part: &ArmorPart,
+    index: usize,
+    skill_ids: &[String],
+) -> BaseArmor {
+    let skills = random_skills(rng, skill_ids, 2)
+        .into_iter()
+        .map(|(id, level)| (id, json!({ "level": level })))
+        .collect::<serde_json::Map<_, _>>();
+
+    // Different defense keeps armors apart, equal armors are merged by the calculator
+    serde_json::from_value(json!({
+        "id": format!("test_{}_{}", part.as_str(), index),
+        "part": part,
+        "sexType": SexType::All,
+        "names": {},
+        "rarity": 10,
+        "stat": {
+            "defense": 10 * index + rng.range(0, 9) as usize,
+            "fireRes": 0,
+            "waterRes": 0,
+            "iceRes": 0,
+            "elecRes": 0,
+            "dragonRes": 0,
+        },
+        "skills": skills,
+        "slots": random_slots(rng, 3, 4),
+    }))
+    .unwrap()
+}
+
+pub(super) fn random_case(seed: u64, data: &GameData) -> TestCase {
+    let mut rng = TestRng::new(seed);
+
+    let deco_skill_ids = data
+        .decos
+        .iter()
+        .map(|deco| deco.skill_id.clone())
+        .collect::<HashSet<_>>();
+
+    let mut all_skill_ids = data.skills.keys().cloned().collect::<Vec<_>>();
+    all_skill_ids.sort();
+
+    let (with_decos, without_decos): (Vec<_>, Vec<_>) = all_skill_ids
+        .into_iter()
+        .partition(|id| deco_skill_ids.contains(id));
+
+    let mut skill_ids = HashSet::new();
+
+    for _ in 0..rng.range(2, 4) {
+        skill_ids.insert(rng.pick(&with_decos).clone());
+    }
+
+    if rng.chance(30) {
+        skill_ids.insert(rng.pick(&without_decos).clone());
+    }
+
+    let mut skill_ids = skill_ids.into_iter().collect::<Vec<_>>();
+    skill_ids.sort();
+
+    let skills = skill_ids
+        .iter()
+        .map(|id| (id.clone(), data.skills[id].clone()))
+        .collect::<HashMap<_, _>>();
+
+    let decos = data
+        .decos
+        .iter()
+        .filter(|deco| skills.contains_key(&deco.skill_id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut armors = HashMap::new();
+
+    for part in ArmorPart::get_all_armor() {
+        for index in 0..rng.range(1, 3) as usize {
+            let armor = random_armor(&mut rng, &part, index, &skill_ids);
+
+            armors.insert(armor.id().clone(), armor);
+        }
+    }
+
+    // Skill uids must cover every skill, only the equipments are limited to a few skills
+    let mut dm = DataManager::new(
+        armors.clone(),
+        data.skills.clone(),
+        data.decos
+            .iter()
+            .map(|deco| (deco.id.clone(), deco.clone()))
+            .collect(),
+        None,
+    )
+    .unwrap();
+
+    let mut armor_ids = armors.keys().cloned().collect::<Vec<_>>();
+    armor_ids.sort();
+
+    let mut anomalies = Vec::new();
+
+    for _ in 0..rng.range(0, 2) {
+        let original = &armors[rng.pick(&armor_ids)];
+
+        let skill_diffs = random_skills(&mut rng, &skill_ids, 1)
+            .into_iter()
+            .map(|(id, level)| (id, ArmorSkill { level }))
+            .collect();
+
+        let mut stat_diff = ArmorStat::new_empty();
+        stat_diff.defense = 1 + anomalies.len() as i16;
+
+        let slot_diffs = (0..3).map(|_| rng.range(0, 1) as SkillSlotCount).collect();
+
+        anomalies.push(AnomalyArmor::new(
+            original,
+            stat_diff,
+            slot_diffs,
+            skill_diffs,
+        ));
+    }
+
+    dm.set_file_anomalies(anomalies);
+
+    let mut talismans = Vec::new();
+    let mut talisman_keys = HashSet::new();
+
+    for index in 0..rng.range(0, 3) {
+        let skills = random_skills(&mut rng, &skill_ids, 2);
+        let slot_sizes = random_slots(&mut rng, 3, 3);
+
+        // Results tell talismans apart only by skills and slots
+        if !talisman_keys.insert((skills.clone(), slot_sizes.clone())) {
+            continue;
+        }
+
+        talismans.push(Talisman::new(
+            format!("test_talisman_{}", index),
+            skills
+                .into_iter()
+                .map(|(id, level)| SkillIdLevel { id, level })
+                .collect(),
+            slot_sizes,
+        ));
+    }
+
+    dm.set_file_talismans(talismans);
+
+    let mut banned_decos = HashSet::new();
+    let mut deco_inventory = HashMap::new();
+
+    for deco in decos.iter() {
+        if rng.chance(10) {
+            banned_decos.insert(deco.id.clone());
+        } else if rng.chance(25) {
+            deco_inventory.insert(deco.id.clone(), rng.range(0, 2) as SkillSlotCount);
+        }
+    }
+
+    dm.set_deco_settings(&banned_decos, &deco_inventory);
+
+    let mut req_skills = HashMap::new();
+
+    for _ in 0..rng.range(1, 3) {
+        let skill_id = rng.pick(&skill_ids);
+        let max_level = skills[skill_id].max_level.min(4);
+
+        req_skills.insert(
+            skill_id.clone(),
+            rng.range(1, max_level as i64) as SkillSlotCount,
+        );
+    }
+
+    let selected_skills = req_skills
+        .iter()
+        .map(|(id, &level)| (dm.get_skill_uid(id), level))
+        .collect::<IntMap<_, _>>();
+
+    let mut free_slots = vec![0; MAX_SLOT_LEVEL];
+
+    if rng.chance(20) {
+        free_slots[rng.range(0, MAX_SLOT_LEVEL as i64 - 1) as usize] = 1;
+    }
+
+    let mut request = CalculateRequest::new(
+        random_slots(&mut rng, 3, 3),
+        selected_skills,
+        free_slots,
+        SexType::Male,
+        true,
+    );
+
+    request.answer_limit = ANSWER_LIMIT;
+
+    TestCase {
+        dm,
+        decos: data.decos.clone(),
+        banned_decos,
+        deco_inventory,
+        req_skills,
+        request,
+    }
+}
+
+// Every answer must be feasible, and every feasible set must be covered by an answer,
+// since the calculator leaves out talismans and armors dominated by others
+#[test]
+fn calculate_matches_oracle() {
+    let data = GameData::load();
+
+    let mut failures = Vec::new();
+
+    for seed in 0..CASES_COUNT {
+        let case = random_case(seed, &data);
+        let dm = &case.dm;
+
+        let cm = load_cm(dm, &case.request);
+        let result = calculate(dm, &cm, &case.request).unwrap();
+
+        assert!(result.full_equipments.len() < ANSWER_LIMIT);
+
+        let oracle = BruteForceOracle::new(
+            dm,
+            &case.decos,
+            &case.banned_decos,
+            &case.deco_inventory,
+            &case.request,
+        );
+
+        let expected = oracle.find_feasible(&case.req_skills, &case.request);
+        let actual = BruteForceOracle::get_result_keys(&result);
+
+        let extra = actual.difference(&expected).collect::<Vec<_>>();
+        let uncovered = oracle.find_uncovered(&expected, &actual, &case.req_skills);
+
+        if !extra.is_empty() || !uncovered.is_empty() {
+            failures.push(format!(
+                "Seed {}: skills {:?}, weapon slots {:?}, free slots {:?}\nuncovered: {:?}\nextra: {:?}",
+                seed,
+                case.req_skills,
+                case.request.weapon_slots,
+                case.request.free_slots,
+                uncovered,
+                extra,
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Failed cases:\n{}",
+        failures.join("\n")
+    );
+}
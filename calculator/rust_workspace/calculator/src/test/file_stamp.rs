@@ -0,0 +1,94 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::data::file_stamp::FileStamp;
+
+fn create_temp_file(name: &str, content: &str) -> PathBuf {
+    let filename = std::env::temp_dir().join(format!(
+        "mhr_calculator_file_stamp_{}_{}.txt",
+        name,
+        std::process::id()
+    ));
+
+    fs::write(&filename, content).unwrap();
+    set_modified(&filename, SystemTime::now() - Duration::from_secs(60));
+
+    filename
+}
+
+fn set_modified(filename: &Path, modified: SystemTime) {
+    File::options()
+        .write(true)
+        .open(filename)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+#[test]
+fn untouched_file_is_unchanged() {
+    let filename = create_temp_file("untouched", "a");
+    let mut stamp = FileStamp::read(&filename);
+
+    assert!(stamp.is_unchanged(&filename));
+
+    fs::remove_file(&filename).unwrap();
+}
+
+#[test]
+fn touched_file_with_same_content_is_unchanged() {
+    let filename = create_temp_file("touched", "a");
+    let mut stamp = FileStamp::read(&filename);
+
+    fs::write(&filename, "a").unwrap();
+    set_modified(&filename, SystemTime::now());
+
+    assert!(stamp.is_unchanged(&filename));
+
+    // New modified time is kept, still unchanged without hashing again
+    assert_eq!(stamp, FileStamp::read(&filename));
+    assert!(stamp.is_unchanged(&filename));
+
+    fs::remove_file(&filename).unwrap();
+}
+
+#[test]
+fn changed_content_is_changed() {
+    let filename = create_temp_file("changed", "a");
+    let mut stamp = FileStamp::read(&filename);
+
+    fs::write(&filename, "b").unwrap();
+    set_modified(&filename, SystemTime::now());
+
+    assert!(!stamp.is_unchanged(&filename));
+
+    fs::remove_file(&filename).unwrap();
+}
+
+#[test]
+fn other_or_missing_file_is_changed() {
+    let filename = create_temp_file("other", "a");
+    let other_filename = create_temp_file("other_2", "a");
+    let mut stamp = FileStamp::read(&filename);
+
+    assert!(!stamp.is_unchanged(&other_filename));
+
+    fs::remove_file(&filename).unwrap();
+    fs::remove_file(&other_filename).unwrap();
+
+    assert!(!stamp.is_unchanged(&filename));
+
+    // Appears after being read as missing
+    let mut stamp = FileStamp::read(&filename);
+
+    assert!(stamp.is_unchanged(&filename));
+
+    fs::write(&filename, "a").unwrap();
+
+    assert!(!stamp.is_unchanged(&filename));
+
+    fs::remove_file(&filename).unwrap();
+}
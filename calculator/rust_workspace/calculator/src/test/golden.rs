@@ -0,0 +1,292 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use nohash_hasher::IntMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    calc::{
+        calc_reporter::EmptyReporter,
+        calc_request::CalculateRequest,
+        calc_result::{CalculateResult, ResultDecorationCombination, ResultFullEquipments},
+        calculator::Calculator,
+        cancel_token::CancelToken,
+        types::SkillSlotCount,
+    },
+    data::{
+        armor::SexType,
+        data_manager::DataManager,
+        parser::{parse_anomaly, parse_talisman},
+    },
+};
+
+use super::common::{calculate, load_cm};
+
+// Set to rewrite golden files with the current results, e.g. BLESS_GOLDEN=1 cargo test golden
+const BLESS_ENV: &str = "BLESS_GOLDEN";
+const GOLDEN_FILE_NAME: &str = "golden.json";
+
+// Results without timings, with every map and list in a fixed order
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GoldenResult {
+    answers_count: usize,
+    has_next_cursor: bool,
+    answers: Vec<GoldenAnswer>,
+    additional_skills: BTreeMap<String, (SkillSlotCount, SkillSlotCount)>,
+    additional_slots: Vec<SkillSlotCount>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GoldenAnswer {
+    armors: BTreeMap<String, String>,
+    talisman_skills: BTreeMap<String, SkillSlotCount>,
+    talisman_slots: Vec<SkillSlotCount>,
+    deco_combs: Vec<GoldenDecoComb>,
+    common_leftover_skills: BTreeMap<String, i8>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GoldenDecoComb {
+    skill_decos: BTreeMap<String, Vec<SkillSlotCount>>,
+    slots_sum: Vec<SkillSlotCount>,
+    leftover_slots_sum: Vec<SkillSlotCount>,
+    leftover_skills: BTreeMap<String, i8>,
+}
+
+impl GoldenResult {
+    fn new(
+        result: &CalculateResult,
+        additional_skills: HashMap<String, (SkillSlotCount, SkillSlotCount)>,
+        additional_slots: Vec<SkillSlotCount>,
+    ) -> Self {
+        let mut answers = result
+            .full_equipments
+            .iter()
+            .map(GoldenAnswer::new)
+            .collect::<Vec<_>>();
+
+        answers.sort();
+
+        Self {
+            answers_count: answers.len(),
+            has_next_cursor: result.next_cursor.is_some(),
+            answers,
+            additional_skills: additional_skills.into_iter().collect(),
+            additional_slots,
+        }
+    }
+
+    // One answer per line keeps the file small and diffs readable
+    fn to_golden_string(&self) -> String {
+        let answers = self
+            .answers
+            .iter()
+            .map(|answer| format!("    {}", serde_json::to_string(answer).unwrap()))
+            .collect::<Vec<_>>()
+            .join(",\n");
+
+        format!(
+            "{{\n  \"answersCount\": {},\n  \"hasNextCursor\": {},\n  \"answers\": [\n{}\n  ],\n  \"additionalSkills\": {},\n  \"additionalSlots\": {}\n}}\n",
+            self.answers_count,
+            self.has_next_cursor,
+            answers,
+            serde_json::to_string(&self.additional_skills).unwrap(),
+            serde_json::to_string(&self.additional_slots).unwrap(),
+        )
+    }
+}
+
+impl GoldenAnswer {
+    fn new(full_equip: &ResultFullEquipments) -> Self {
+        let mut deco_combs = full_equip
+            .deco_combs
+            .iter()
+            .map(GoldenDecoComb::new)
+            .collect::<Vec<_>>();
+
+        deco_combs.sort();
+
+        Self {
+            armors: full_equip
+                .armors
+                .iter()
+                .map(|(part, armor)| (part.clone(), armor.id.clone()))
+                .collect(),
+            talisman_skills: full_equip.talisman.skills.clone().into_iter().collect(),
+            talisman_slots: full_equip.talisman.slots.clone(),
+            deco_combs,
+            common_leftover_skills: full_equip
+                .common_leftover_skills
+                .clone()
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl GoldenDecoComb {
+    fn new(deco_comb: &ResultDecorationCombination) -> Self {
+        Self {
+            skill_decos: deco_comb.skill_decos.clone().into_iter().collect(),
+            slots_sum: deco_comb.slots_sum.clone(),
+            leftover_slots_sum: deco_comb.leftover_slots_sum.clone(),
+            leftover_skills: deco_comb.leftover_skills.clone().into_iter().collect(),
+        }
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> T {
+    serde_json::from_reader(BufReader::new(File::open(path).unwrap())).unwrap()
+}
+
+// Same inputs as the benches
+pub(super) async fn load_case(
+    manifest_dir: &Path,
+    case_dir: &Path,
+) -> (DataManager, CalculateRequest) {
+    let mut dm = DataManager::from_sources(
+        &manifest_dir.join("../src-tauri/data/armor.json"),
+        &manifest_dir.join("../src-tauri/data/skill.json"),
+        &manifest_dir.join("../src-tauri/data/deco.json"),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let anomalies = parse_anomaly(
+        &case_dir.join("anomaly.txt"),
+        dm.get_armors(),
+        dm.get_armor_name_dict(),
+        dm.get_skill_name_dict(),
+    )
+    .unwrap();
+
+    dm.set_file_anomalies(anomalies.items);
+
+    let talismans =
+        parse_talisman(&case_dir.join("talisman.txt"), dm.get_skill_name_dict()).unwrap();
+
+    dm.set_file_talismans(talismans.items);
+
+    let weapon_slots: Vec<SkillSlotCount> = read_json(&case_dir.join("weapon_slots.json"));
+    let selected_skills: HashMap<String, SkillSlotCount> =
+        read_json(&case_dir.join("selected_skills.json"));
+    let selected_skills: IntMap<usize, SkillSlotCount> = selected_skills
+        .iter()
+        .map(|(skill_id, level)| (dm.get_skill_uid(skill_id), *level))
+        .collect();
+    let free_slots: Vec<SkillSlotCount> = read_json(&case_dir.join("free_slots.json"));
+
+    let request = CalculateRequest::new(
+        weapon_slots,
+        selected_skills,
+        free_slots,
+        SexType::Female,
+        false,
+    );
+
+    (dm, request)
+}
+
+async fn calculate_case(manifest_dir: &Path, case_dir: &Path) -> GoldenResult {
+    let (dm, request) = load_case(manifest_dir, case_dir).await;
+
+    let cm = load_cm(&dm, &request);
+    let result = calculate(&dm, &cm, &request).unwrap();
+
+    let (_, additional_result) = Calculator::calculate_additional_skills(
+        &request,
+        &dm,
+        &cm,
+        &CancelToken::default(),
+        &EmptyReporter {},
+    )
+    .unwrap();
+
+    GoldenResult::new(&result, additional_result.skills, additional_result.slots)
+}
+
+fn get_case_dirs(bench_data_dir: &Path) -> Vec<PathBuf> {
+    let mut case_dirs = std::fs::read_dir(bench_data_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+
+    case_dirs.sort();
+
+    case_dirs
+}
+
+fn describe_difference(expected: &GoldenResult, actual: &GoldenResult) -> String {
+    let missing = expected
+        .answers
+        .iter()
+        .filter(|answer| !actual.answers.contains(answer))
+        .count();
+    let extra = actual
+        .answers
+        .iter()
+        .filter(|answer| !expected.answers.contains(answer))
+        .count();
+
+    format!(
+        "answers {} -> {} ({} missing, {} new), next cursor {} -> {}, additional skills changed: {}, additional slots {:?} -> {:?}",
+        expected.answers_count,
+        actual.answers_count,
+        missing,
+        extra,
+        expected.has_next_cursor,
+        actual.has_next_cursor,
+        expected.additional_skills != actual.additional_skills,
+        expected.additional_slots,
+        actual.additional_slots,
+    )
+}
+
+#[tokio::test]
+async fn bench_cases_match_golden() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let is_bless = std::env::var_os(BLESS_ENV).is_some();
+
+    let mut failures = Vec::new();
+
+    for case_dir in get_case_dirs(&manifest_dir.join("../bench-data")) {
+        let actual = calculate_case(manifest_dir, &case_dir).await;
+        let golden_path = case_dir.join(GOLDEN_FILE_NAME);
+
+        if is_bless {
+            std::fs::write(&golden_path, actual.to_golden_string()).unwrap();
+            continue;
+        }
+
+        if !golden_path.exists() {
+            failures.push(format!("{}: no golden file", case_dir.display()));
+            continue;
+        }
+
+        let expected: GoldenResult = read_json(&golden_path);
+
+        if expected != actual {
+            failures.push(format!(
+                "{}: {}",
+                case_dir.display(),
+                describe_difference(&expected, &actual)
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Results differ from golden files, rerun with {}=1 if the change is intended:\n{}",
+        BLESS_ENV,
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,384 @@
+use std::path::Path;
+
+use crate::calc::{
+    calc_reporter::EmptyReporter,
+    calc_request::CalculateRequest,
+    calc_result::{CalculateResult, ResultFullEquipments},
+    calc_sort::{SortKey, SortKind},
+    cancel_token::CancelToken,
+    ilp::{IlpError, IlpModel, IlpSolution, RowKind},
+    solver::{EnumerationSolver, IlpSolver, Solver},
+};
+use crate::data::{armor::ArmorPart, data_manager::DataManager};
+
+use super::{
+    common::{get_request, load_cm, load_dm},
+    differential::{random_case, GameData},
+    golden::load_case,
+    oracle::BruteForceOracle,
+};
+
+const EPS: f64 = 1e-6;
+const CASES_COUNT: u64 = 150;
+const LESSER_CASES_COUNT: u64 = 30;
+
+const DEFENSE_DESC: SortKey = SortKey {
+    kind: SortKind::Defense,
+    is_descending: true,
+};
+
+fn assert_solution(solution: &IlpSolution, objective: f64, values: &[f64]) {
+    assert!(
+        (solution.objective - objective).abs() < EPS,
+        "objective {} != {}",
+        solution.objective,
+        objective
+    );
+
+    for (var, (actual, expected)) in solution.values.iter().zip(values.iter()).enumerate() {
+        assert!(
+            (actual - expected).abs() < EPS,
+            "var {}: {} != {}",
+            var,
+            actual,
+            expected
+        );
+    }
+}
+
+// min -x - y, x + 2y <= 4, 3x + y <= 6
+fn two_var_model(is_integer: bool) -> IlpModel {
+    let mut model = IlpModel::new();
+    let x = model.add_var(-1.0, None, is_integer);
+    let y = model.add_var(-1.0, None, is_integer);
+
+    model.add_row(vec![(x, 1.0), (y, 2.0)], RowKind::Le, 4.0);
+    model.add_row(vec![(x, 3.0), (y, 1.0)], RowKind::Le, 6.0);
+
+    model
+}
+
+fn solve_model(model: &IlpModel) -> Option<IlpSolution> {
+    model.solve(&CancelToken::default()).unwrap()
+}
+
+#[test]
+fn lp_optimum_at_vertex() {
+    let solution = solve_model(&two_var_model(false)).unwrap();
+
+    assert_solution(&solution, -2.8, &[1.6, 1.2]);
+}
+
+#[test]
+fn ilp_optimum_is_integral() {
+    let solution = solve_model(&two_var_model(true)).unwrap();
+
+    assert!((solution.objective + 2.0).abs() < EPS);
+    assert!(solution
+        .values
+        .iter()
+        .all(|value| (value - value.round()).abs() < EPS));
+    assert!(solution.get_int(0) + 2 * solution.get_int(1) <= 4);
+    assert!(3 * solution.get_int(0) + solution.get_int(1) <= 6);
+}
+
+#[test]
+fn infeasible_rows() {
+    let mut model = IlpModel::new();
+    let x = model.add_var(1.0, None, false);
+
+    model.add_row(vec![(x, 1.0)], RowKind::Le, 1.0);
+    model.add_row(vec![(x, 1.0)], RowKind::Ge, 2.0);
+
+    assert!(solve_model(&model).is_none());
+}
+
+#[test]
+fn unbounded_objective() {
+    let mut model = IlpModel::new();
+    let x = model.add_var(-1.0, None, false);
+    let y = model.add_var(0.0, None, false);
+
+    model.add_row(vec![(x, 1.0), (y, -1.0)], RowKind::Ge, 1.0);
+
+    assert!(solve_model(&model).is_none());
+}
+
+// Beale's example, where Dantzig's rule cycles on degenerate pivots without an anti cycling rule
+#[test]
+fn degenerate_pivots() {
+    let mut model = IlpModel::new();
+    let x4 = model.add_var(-0.75, None, false);
+    let x5 = model.add_var(20.0, None, false);
+    let x6 = model.add_var(-0.5, None, false);
+    let x7 = model.add_var(6.0, None, false);
+
+    model.add_row(
+        vec![(x4, 0.25), (x5, -8.0), (x6, -1.0), (x7, 9.0)],
+        RowKind::Le,
+        0.0,
+    );
+    model.add_row(
+        vec![(x4, 0.5), (x5, -12.0), (x6, -0.5), (x7, 3.0)],
+        RowKind::Le,
+        0.0,
+    );
+    model.add_row(vec![(x6, 1.0)], RowKind::Le, 1.0);
+
+    let solution = solve_model(&model).unwrap();
+
+    assert_solution(&solution, -1.25, &[1.0, 0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn equality_rows() {
+    let mut model = IlpModel::new();
+    let x = model.add_var(1.0, None, false);
+    let y = model.add_var(2.0, None, false);
+
+    model.add_row(vec![(x, 1.0), (y, 1.0)], RowKind::Eq, 3.0);
+    model.add_row(vec![(x, 1.0), (y, -1.0)], RowKind::Eq, 1.0);
+
+    // Redundant row leaves an artificial variable in the basis after phase one
+    model.add_row(vec![(x, 2.0), (y, 2.0)], RowKind::Eq, 6.0);
+
+    let solution = solve_model(&model).unwrap();
+
+    assert_solution(&solution, 4.0, &[2.0, 1.0]);
+}
+
+#[test]
+fn equality_without_integer_solution() {
+    let solve = |is_integer: bool| {
+        let mut model = IlpModel::new();
+        let x = model.add_var(0.0, None, is_integer);
+
+        model.add_row(vec![(x, 2.0)], RowKind::Eq, 3.0);
+
+        solve_model(&model)
+    };
+
+    assert_solution(&solve(false).unwrap(), 0.0, &[1.5]);
+    assert!(solve(true).is_none());
+}
+
+#[test]
+fn upper_bounds() {
+    let solve = |is_integer: bool| {
+        let mut model = IlpModel::new();
+        model.add_var(-1.0, Some(2.5), is_integer);
+
+        solve_model(&model).unwrap()
+    };
+
+    assert_solution(&solve(false), -2.5, &[2.5]);
+    assert_solution(&solve(true), -2.0, &[2.0]);
+}
+
+#[test]
+fn cancelled_solve() {
+    let cancel_token = CancelToken::default();
+    cancel_token.cancel();
+
+    assert!(two_var_model(true).solve(&cancel_token).unwrap().is_none());
+}
+
+// The optimum needs two pivots from the slack basis
+#[test]
+fn pivot_limit_is_an_error() {
+    let mut model = two_var_model(false);
+    model.set_max_pivots(1);
+
+    assert_eq!(
+        model.solve(&CancelToken::default()).unwrap_err(),
+        IlpError::PivotLimit(1)
+    );
+
+    model.set_max_pivots(2);
+
+    assert_solution(&solve_model(&model).unwrap(), -2.8, &[1.6, 1.2]);
+}
+
+fn get_defense(full_equip: &ResultFullEquipments) -> i32 {
+    full_equip
+        .armors
+        .values()
+        .map(|armor| armor.stat.defense as i32)
+        .sum()
+}
+
+fn get_best_defense(result: &CalculateResult) -> Option<i32> {
+    result.full_equipments.iter().map(get_defense).max()
+}
+
+fn solve(
+    solver: &dyn Solver,
+    dm: &DataManager,
+    request: &CalculateRequest,
+) -> (String, CalculateResult) {
+    solver
+        .solve(
+            request,
+            dm,
+            &load_cm(dm, request),
+            &CancelToken::default(),
+            &EmptyReporter {},
+        )
+        .unwrap()
+}
+
+// Every ILP answer must be feasible, feasible sets must be covered like the enumeration ones,
+// and the best defense must be the one of the enumeration with lesser equipments included.
+// ILP runs without them, with them it finds every feasible set one by one, which takes minutes
+#[test]
+fn ilp_solver_matches_oracle_and_enumeration() {
+    let data = GameData::load();
+
+    let mut failures = Vec::new();
+
+    for seed in 0..CASES_COUNT {
+        let mut case = random_case(seed, &data);
+        case.request.sort_keys = vec![DEFENSE_DESC];
+
+        let dm = &case.dm;
+
+        let mut ilp_request = case.request.clone();
+        ilp_request.include_lte_equips = false;
+
+        let (_, ilp_result) = solve(&IlpSolver {}, dm, &ilp_request);
+        let (_, enum_result) = solve(&EnumerationSolver {}, dm, &case.request);
+
+        let oracle = BruteForceOracle::new(
+            dm,
+            &case.decos,
+            &case.banned_decos,
+            &case.deco_inventory,
+            &case.request,
+        );
+
+        let expected = oracle.find_feasible(&case.req_skills, &case.request);
+        let actual = BruteForceOracle::get_result_keys(&ilp_result);
+
+        let extra = actual.difference(&expected).collect::<Vec<_>>();
+        let uncovered = oracle.find_uncovered(&expected, &actual, &case.req_skills);
+
+        let ilp_best = ilp_result.full_equipments.first().map(get_defense);
+        let enum_best = get_best_defense(&enum_result);
+
+        if !extra.is_empty()
+            || !uncovered.is_empty()
+            || ilp_best != enum_best
+            || ilp_best != get_best_defense(&ilp_result)
+        {
+            failures.push(format!(
+                "Seed {}: skills {:?}, uncovered: {:?}, extra: {:?}, best defense ILP {:?} enumeration {:?}",
+                seed, case.req_skills, uncovered, extra, ilp_best, enum_best,
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Failed cases:\n{}",
+        failures.join("\n")
+    );
+}
+
+// Lesser equipments are only skipped without include_lte_equips, which random cases set.
+// Helm, torso and arm are pinned so every feasible set is found in time
+#[test]
+fn ilp_solver_keeps_lesser_equips() {
+    let data = GameData::load();
+
+    let mut added_count = 0;
+
+    for seed in 0..LESSER_CASES_COUNT {
+        let mut case = random_case(seed, &data);
+        let dm = &case.dm;
+
+        for part in [ArmorPart::Helm, ArmorPart::Torso, ArmorPart::Arm] {
+            let armor_id = dm
+                .get_armors()
+                .values()
+                .filter(|armor| armor.part == part)
+                .map(|armor| armor.id().clone())
+                .min()
+                .unwrap();
+
+            case.request.pinned_equips.insert(part, armor_id);
+        }
+
+        let mut request = case.request.clone();
+        request.include_lte_equips = false;
+
+        let (_, lesser_result) = solve(&IlpSolver {}, dm, &case.request);
+        let (_, result) = solve(&IlpSolver {}, dm, &request);
+
+        let lesser_keys = BruteForceOracle::get_result_keys(&lesser_result);
+        let keys = BruteForceOracle::get_result_keys(&result);
+
+        assert!(
+            keys.is_subset(&lesser_keys),
+            "Seed {}: missing with lesser equipments {:?}",
+            seed,
+            keys.difference(&lesser_keys).collect::<Vec<_>>()
+        );
+
+        added_count += lesser_keys.len() - keys.len();
+    }
+
+    assert!(0 < added_count);
+}
+
+// Pages never go over the limit, with or without the Pareto filter
+#[tokio::test]
+async fn ilp_solver_keeps_answer_limit() {
+    let dm = load_dm().await;
+
+    for (answer_limit, pareto_only) in [(1, false), (3, false), (1, true), (3, true)] {
+        let mut request = get_request(&dm);
+        request.answer_limit = answer_limit;
+        request.pareto_only = pareto_only;
+
+        let (_, result) = solve(&IlpSolver {}, &dm, &request);
+
+        assert!(
+            result.full_equipments.len() <= answer_limit,
+            "{} answers over the limit {}, pareto only {}",
+            result.full_equipments.len(),
+            answer_limit,
+            pareto_only
+        );
+    }
+}
+
+// Enumeration without lesser equipments skips armors only worse in skills and slots,
+// which may have more defense (590 instead of 597 on case3), so only the one with them is compared.
+// Other cases take minutes with lesser equipments
+const BENCH_CASES: [&str; 2] = ["case3", "case4"];
+
+#[tokio::test]
+async fn ilp_best_defense_matches_enumeration_on_bench_cases() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    for case_name in BENCH_CASES {
+        let case_dir = manifest_dir.join("../bench-data").join(case_name);
+        let (dm, mut request) = load_case(manifest_dir, &case_dir).await;
+
+        request.sort_keys = vec![DEFENSE_DESC];
+        request.answer_limit = 1;
+
+        let (_, ilp_result) = solve(&IlpSolver {}, &dm, &request);
+
+        request.include_lte_equips = true;
+
+        let (_, enum_result) = solve(&EnumerationSolver {}, &dm, &request);
+
+        assert_eq!(
+            get_best_defense(&ilp_result),
+            get_best_defense(&enum_result),
+            "{}",
+            case_dir.display()
+        );
+    }
+}
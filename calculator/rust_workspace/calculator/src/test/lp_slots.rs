@@ -0,0 +1,224 @@
+use crate::calc::{
+    calc_vector::CalcVector,
+    constant::MAX_SLOT_LEVEL,
+    deco_combination::DecorationCombination,
+    types::{PointsVec, SkillSlotCount, SlotsVec},
+};
+
+use super::rng::TestRng;
+
+const CASES_COUNT: u64 = 2000;
+const MAX_SIZES_LEN: i64 = 8;
+
+fn random_sizes(rng: &mut TestRng) -> Vec<SkillSlotCount> {
+    (0..rng.range(0, MAX_SIZES_LEN))
+        .map(|_| rng.range(1, MAX_SLOT_LEVEL as i64) as SkillSlotCount)
+        .collect()
+}
+
+// Count of each size, index 0 is size 1
+fn to_counts(sizes: &[SkillSlotCount]) -> SlotsVec {
+    let mut counts = SlotsVec::default();
+
+    for &size in sizes {
+        counts[size as usize - 1] += 1;
+    }
+
+    counts
+}
+
+fn to_lp(sizes: &[SkillSlotCount]) -> SlotsVec {
+    CalcVector::convert_to_lp_slots(&to_counts(sizes))
+}
+
+// Tries every slot for every decoration, no ordering tricks
+fn can_assign(deco_sizes: &[SkillSlotCount], slot_sizes: &[SkillSlotCount]) -> bool {
+    fn assign(
+        deco_sizes: &[SkillSlotCount],
+        slot_sizes: &[SkillSlotCount],
+        used: &mut [bool],
+    ) -> bool {
+        let (&deco_size, left_decos) = match deco_sizes.split_first() {
+            Some(split) => split,
+            None => return true,
+        };
+
+        for index in 0..slot_sizes.len() {
+            if used[index] || slot_sizes[index] < deco_size {
+                continue;
+            }
+
+            used[index] = true;
+            let is_possible = assign(left_decos, slot_sizes, used);
+            used[index] = false;
+
+            if is_possible {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    assign(deco_sizes, slot_sizes, &mut vec![false; slot_sizes.len()])
+}
+
+fn is_non_increasing(slots_lp: &SlotsVec) -> bool {
+    (1..MAX_SLOT_LEVEL).all(|index| slots_lp[index] <= slots_lp[index - 1])
+}
+
+#[test]
+fn lp_conversion_round_trip() {
+    let mut rng = TestRng::new(1);
+
+    for _ in 0..CASES_COUNT {
+        let sizes = random_sizes(&mut rng);
+        let counts = to_counts(&sizes);
+
+        let slots_lp = CalcVector::convert_to_lp_slots(&counts);
+
+        for index in 0..MAX_SLOT_LEVEL {
+            let bigger_count = sizes.iter().filter(|&&size| index < size as usize).count();
+
+            assert_eq!(slots_lp[index] as usize, bigger_count, "{:?}", sizes);
+        }
+
+        assert!(is_non_increasing(&slots_lp));
+        assert_eq!(CalcVector::convert_from_lp_slots(&slots_lp), counts);
+
+        let mut slots_lp_mut = counts;
+        CalcVector::convert_to_lp_slots_mut(&mut slots_lp_mut);
+        assert_eq!(slots_lp_mut, slots_lp);
+
+        CalcVector::convert_from_lp_slots_mut(&mut slots_lp_mut);
+        assert_eq!(slots_lp_mut, counts);
+
+        let points = PointsVec::from_iterator(counts.iter().map(|&count| count as i32));
+        let points_lp = CalcVector::convert_to_lp_points(&points);
+
+        assert_eq!(
+            points_lp,
+            PointsVec::from_iterator(slots_lp.iter().map(|&count| count as i32))
+        );
+    }
+}
+
+#[test]
+fn smaller_deco_fits_larger_slot() {
+    for slot_size in 1..=MAX_SLOT_LEVEL as SkillSlotCount {
+        for deco_size in 1..=MAX_SLOT_LEVEL as SkillSlotCount {
+            assert_eq!(
+                DecorationCombination::is_possible_static_lp(
+                    &to_lp(&[slot_size]),
+                    &to_lp(&[deco_size])
+                ),
+                deco_size <= slot_size,
+                "slot {}, deco {}",
+                slot_size,
+                deco_size
+            );
+        }
+    }
+
+    let mut rng = TestRng::new(2);
+
+    for _ in 0..CASES_COUNT {
+        let slot_sizes = random_sizes(&mut rng);
+        let mut deco_sizes = random_sizes(&mut rng);
+
+        if !DecorationCombination::is_possible_static_lp(&to_lp(&slot_sizes), &to_lp(&deco_sizes)) {
+            continue;
+        }
+
+        // Shrinking any decoration keeps it possible
+        for index in 0..deco_sizes.len() {
+            let original_size = deco_sizes[index];
+
+            for smaller_size in 1..original_size {
+                deco_sizes[index] = smaller_size;
+
+                assert!(
+                    DecorationCombination::is_possible_static_lp(
+                        &to_lp(&slot_sizes),
+                        &to_lp(&deco_sizes)
+                    ),
+                    "slots {:?}, decos {:?}",
+                    slot_sizes,
+                    deco_sizes
+                );
+            }
+
+            deco_sizes[index] = original_size;
+        }
+    }
+}
+
+#[test]
+fn lp_check_matches_assignment() {
+    let mut rng = TestRng::new(3);
+
+    for _ in 0..CASES_COUNT {
+        let slot_sizes = random_sizes(&mut rng);
+        let deco_sizes = random_sizes(&mut rng);
+
+        let expected = can_assign(&deco_sizes, &slot_sizes);
+
+        assert_eq!(
+            DecorationCombination::is_possible_static_lp(&to_lp(&slot_sizes), &to_lp(&deco_sizes)),
+            expected,
+            "slots {:?}, decos {:?}",
+            slot_sizes,
+            deco_sizes
+        );
+
+        let mut slots_lp = to_lp(&slot_sizes);
+
+        assert_eq!(
+            DecorationCombination::is_possible_static_lp_equip_mut(
+                &mut slots_lp,
+                &[&to_lp(&deco_sizes)]
+            ),
+            expected,
+            "slots {:?}, decos {:?}",
+            slot_sizes,
+            deco_sizes
+        );
+    }
+}
+
+#[test]
+fn promoted_leftover_slots_keep_feasibility() {
+    let mut rng = TestRng::new(4);
+
+    for _ in 0..CASES_COUNT {
+        let slot_sizes = random_sizes(&mut rng);
+        let deco_sizes = random_sizes(&mut rng);
+
+        let mut left_slots_lp = to_lp(&slot_sizes) - to_lp(&deco_sizes);
+
+        if !left_slots_lp.iter().all(|&count| 0 <= count) {
+            continue;
+        }
+
+        let subtracted_lp = left_slots_lp;
+        CalcVector::promote_subtracted_lp_slots(&mut left_slots_lp);
+
+        assert!(is_non_increasing(&left_slots_lp), "{:?}", left_slots_lp);
+        assert!(left_slots_lp
+            .iter()
+            .zip(subtracted_lp.iter())
+            .all(|(promoted, subtracted)| promoted <= subtracted));
+
+        let more_deco_sizes = random_sizes(&mut rng);
+        let all_deco_sizes = [deco_sizes.clone(), more_deco_sizes.clone()].concat();
+
+        assert_eq!(
+            DecorationCombination::is_possible_static_lp(&left_slots_lp, &to_lp(&more_deco_sizes)),
+            can_assign(&all_deco_sizes, &slot_sizes),
+            "slots {:?}, decos {:?} then {:?}",
+            slot_sizes,
+            deco_sizes,
+            more_deco_sizes
+        );
+    }
+}
@@ -1,3 +1,27 @@
+mod anomaly_ranking;
+mod calc_reporter;
+mod calc_state;
+mod common;
+mod deco_inventory;
+mod deco_table;
+mod differential;
+mod file_stamp;
+mod golden;
+mod ilp;
+mod lp_slots;
+mod oracle;
+mod pagination;
+mod pareto;
+mod parser;
+mod pinned;
+mod profile;
+mod rng;
+mod skills;
+mod sort;
+mod stat;
+mod talisman;
+mod validation;
+
 #[cfg(test)]
 #[tokio::test]
 async fn it_works() {
@@ -6,7 +30,9 @@ async fn it_works() {
 
     use crate::{
         calc::{
-            calc_data_manager::CalcDataManager, calculator::Calculator, constant::MAX_SLOT_LEVEL,
+            calc_data_manager::CalcDataManager, calc_reporter::EmptyReporter,
+            calc_request::CalculateRequest, calculator::Calculator, cancel_token::CancelToken,
+            constant::MAX_SLOT_LEVEL,
         },
         data::data_manager::DataManager,
     };
@@ -17,10 +43,15 @@ async fn it_works() {
         &manifest_dir.join("../src-tauri/data/armor.json"),
         &manifest_dir.join("../src-tauri/data/skill.json"),
         &manifest_dir.join("../src-tauri/data/deco.json"),
+        None,
     )
-    .await;
+    .await
+    .unwrap();
 
-    let cm = CalcDataManager::new(&dm);
+    let mut cm = CalcDataManager::new(&dm);
+    cm.load_base_armors(&dm);
+    cm.load_anomalies(&dm);
+    cm.load_talismans(&dm);
 
     info!("Armors length: {}", dm.get_armors().len());
 
@@ -50,15 +81,35 @@ async fn it_works() {
 
     selected_skills.insert(dm.get_skill_uid("spiribirds_call"), 5);
 
-    let (_log, _results) = Calculator::calculate(
+    let request = CalculateRequest::new(
         weapon_slots,
         selected_skills,
         free_slots,
         crate::data::armor::SexType::Female,
         false,
+    );
+
+    cm.refresh_infos(
         &dm,
-        &cm,
+        &Calculator::convert_to_skills_container(&request.selected_skills),
     );
+
+    let (_log, results) = Calculator::calculate(
+        &request,
+        &dm,
+        &cm,
+        &CancelToken::default(),
+        &EmptyReporter {},
+    )
+    .unwrap();
+
+    for full_equip in results.full_equipments.iter() {
+        assert!(!full_equip.deco_combs.is_empty());
+
+        for deco_comb in full_equip.deco_combs.iter() {
+            assert!(deco_comb.leftover_skills.values().all(|&level| 0 <= level));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -75,6 +126,7 @@ fn deco_comb_compare1() {
 
 #[cfg(test)]
 #[test]
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
 fn armor_stat_compare() {
     use crate::data::armor::ArmorStat;
 
@@ -136,8 +188,10 @@ async fn le_equips_compare1() {
         &manifest_dir.join("../src-tauri/data/armor.json"),
         &manifest_dir.join("../src-tauri/data/skill.json"),
         &manifest_dir.join("../src-tauri/data/deco.json"),
+        None,
     )
-    .await;
+    .await
+    .unwrap();
 
     let mut slot_armors = Vec::new();
     let mut equip_uid = CalcEquipmentUid::default();
@@ -158,7 +212,7 @@ async fn le_equips_compare1() {
 #[cfg(test)]
 #[tokio::test]
 async fn skill_count_test() {
-    use crate::calc::constant::SKILLS_COUNT;
+    use crate::calc::constant::MAX_SKILLS_COUNT;
     use crate::data::data_manager::DataManager;
 
     let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf();
@@ -167,10 +221,39 @@ async fn skill_count_test() {
         &manifest_dir.join("../src-tauri/data/armor.json"),
         &manifest_dir.join("../src-tauri/data/skill.json"),
         &manifest_dir.join("../src-tauri/data/deco.json"),
+        None,
     )
-    .await;
+    .await
+    .unwrap();
 
-    assert_eq!(SKILLS_COUNT, dm.get_skills().len());
+    assert!(dm.get_skills().len() <= MAX_SKILLS_COUNT);
+}
+
+#[cfg(test)]
+#[test]
+fn skill_count_over_max_test() {
+    use std::collections::HashMap;
+
+    use crate::calc::constant::MAX_SKILLS_COUNT;
+    use crate::data::data_manager::DataManager;
+    use crate::data::skill::Skill;
+
+    let skills = (0..MAX_SKILLS_COUNT + 1)
+        .map(|index| {
+            let id = format!("skill_{}", index);
+
+            (
+                id.clone(),
+                Skill {
+                    id,
+                    max_level: 1,
+                    names: HashMap::new(),
+                },
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    assert!(DataManager::new(HashMap::new(), skills, HashMap::new(), None).is_err());
 }
 
 #[cfg(test)]
@@ -186,8 +269,10 @@ async fn skill_equality_test() {
         &manifest_dir.join("../src-tauri/data/armor.json"),
         &manifest_dir.join("../src-tauri/data/skill.json"),
         &manifest_dir.join("../src-tauri/data/deco.json"),
+        None,
     )
-    .await;
+    .await
+    .unwrap();
 
     let mut skills1 = SkillsContainer::new();
     skills1.set(dm.get_skill_uid("spread_up"), 3);
@@ -208,3 +293,46 @@ async fn skill_equality_test() {
     assert!(map.contains_key(&skills2.get_list()));
     assert_ne!(map.get(&skills2.get_list()), None);
 }
+
+#[cfg(test)]
+#[tokio::test]
+async fn manual_anomalies_test() {
+    use std::collections::HashMap;
+
+    use crate::data::armor::{AnomalyArmor, ArmorStat, Talisman};
+    use crate::data::data_manager::DataManager;
+
+    let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf();
+
+    let mut dm = DataManager::from_sources(
+        &manifest_dir.join("../src-tauri/data/armor.json"),
+        &manifest_dir.join("../src-tauri/data/skill.json"),
+        &manifest_dir.join("../src-tauri/data/deco.json"),
+        None,
+    )
+    .await
+    .unwrap();
+
+    let original = dm.get_armors()["kamura_head_scarf"].clone();
+    let anomaly = AnomalyArmor::new(
+        &original,
+        ArmorStat::new_empty(),
+        vec![1, 0, 0],
+        HashMap::new(),
+    );
+
+    // Manual ids are counted apart from the file ones, so two manuals never share an id
+    dm.set_file_anomalies(vec![anomaly.clone()]);
+    dm.add_manual_anomaly(anomaly.clone());
+    dm.add_manual_anomaly(anomaly);
+
+    assert_eq!(dm.get_manual_anomaly_armors().len(), 2);
+    assert_eq!(dm.get_anomaly_armors().len(), 3);
+
+    dm.add_manual_talisman(&Talisman::new(String::new(), Vec::new(), vec![1, 0, 0]));
+    dm.clear_manual_anomalies();
+
+    assert!(dm.get_manual_anomaly_armors().is_empty());
+    assert_eq!(dm.get_anomaly_armors().len(), 1);
+    assert_eq!(dm.get_manual_talismans().len(), 1);
+}
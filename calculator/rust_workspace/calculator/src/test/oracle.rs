@@ -0,0 +1,364 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    calc::{
+        calc_equipment::CalcEquipment,
+        calc_request::CalculateRequest,
+        calc_result::{CalculateResult, ResultFullEquipments},
+        constant::MAX_SLOT_LEVEL,
+        types::SkillSlotCount,
+    },
+    data::{
+        armor::{ArmorPart, BaseArmor, SexType, Talisman},
+        data_manager::DataManager,
+        deco::Decoration,
+    },
+};
+
+// Armor ids of each part and the talisman key, in part order
+pub type AnswerKey = Vec<String>;
+
+pub struct OracleEquip {
+    pub key: String,
+    pub skills: HashMap<String, SkillSlotCount>,
+    pub slot_sizes: Vec<SkillSlotCount>,
+}
+
+pub struct OracleDeco {
+    pub skill_id: String,
+    pub skill_level: SkillSlotCount,
+    pub slot_size: SkillSlotCount,
+    pub max_count: Option<SkillSlotCount>,
+}
+
+// Slow reference solver trying every armor set and every decoration count, for small data only
+pub struct BruteForceOracle {
+    parts: Vec<Vec<OracleEquip>>,
+    decos: Vec<OracleDeco>,
+}
+
+impl BruteForceOracle {
+    pub fn new(
+        dm: &DataManager,
+        decos: &[Decoration],
+        banned_decos: &HashSet<String>,
+        deco_inventory: &HashMap<String, SkillSlotCount>,
+        request: &CalculateRequest,
+    ) -> Self {
+        let mut parts = Vec::new();
+
+        for part in ArmorPart::get_all_armor() {
+            let mut armors = dm
+                .get_parts(part.as_usize())
+                .into_iter()
+                .filter(|armor| {
+                    armor.sex_type == SexType::All || armor.sex_type == request.sex_type
+                })
+                .map(|armor| Self::from_armor(armor))
+                .collect::<Vec<_>>();
+
+            armors.push(Self::from_armor(&BaseArmor::create_empty(part)));
+
+            parts.push(armors);
+        }
+
+        let mut talismans = dm
+            .get_talismans()
+            .iter()
+            .map(|tali| Self::from_talisman(tali))
+            .collect::<Vec<_>>();
+
+        talismans.push(Self::from_talisman(dm.empty_talisman()));
+
+        parts.push(talismans);
+
+        for (part, equip_id) in request.pinned_equips.iter() {
+            parts[part.as_usize()].retain(|equip| &equip.key == equip_id);
+        }
+
+        for part_equips in parts.iter_mut() {
+            part_equips.retain(|equip| !request.excluded_equips.contains(&equip.key));
+        }
+
+        let decos = decos
+            .iter()
+            .filter(|deco| !banned_decos.contains(&deco.id))
+            .map(|deco| OracleDeco {
+                skill_id: deco.skill_id.clone(),
+                skill_level: deco.skill_level,
+                slot_size: deco.slot_size,
+                max_count: deco_inventory.get(&deco.id).copied(),
+            })
+            .collect();
+
+        Self { parts, decos }
+    }
+
+    fn from_armor(armor: &BaseArmor) -> OracleEquip {
+        OracleEquip {
+            key: armor.id().clone(),
+            skills: armor
+                .skills
+                .iter()
+                .map(|(id, skill)| (id.clone(), skill.level))
+                .collect(),
+            slot_sizes: armor.slots.clone(),
+        }
+    }
+
+    fn from_talisman(tali: &Talisman) -> OracleEquip {
+        let skills = tali
+            .skills
+            .iter()
+            .map(|skill| (skill.id.clone(), skill.level))
+            .collect::<HashMap<_, _>>();
+
+        OracleEquip {
+            key: Self::get_talisman_key(
+                &skills,
+                &CalcEquipment::convert_from_base_slots(&tali.slot_sizes)
+                    .data
+                    .0[0],
+            ),
+            skills,
+            slot_sizes: tali.slot_sizes.clone(),
+        }
+    }
+
+    // Results don't carry talisman ids, so talismans are told apart by their skills and slots
+    fn get_talisman_key(
+        skills: &HashMap<String, SkillSlotCount>,
+        slot_counts: &[SkillSlotCount],
+    ) -> String {
+        let skills = skills
+            .iter()
+            .filter(|(_, &level)| level != 0)
+            .collect::<BTreeSet<_>>();
+
+        format!("talisman {:?} {:?}", skills, slot_counts)
+    }
+
+    pub fn get_result_keys(result: &CalculateResult) -> BTreeSet<AnswerKey> {
+        result
+            .full_equipments
+            .iter()
+            .map(Self::get_answer_key)
+            .collect()
+    }
+
+    pub fn get_answer_key(full_equip: &ResultFullEquipments) -> AnswerKey {
+        let mut key = ArmorPart::get_all_armor()
+            .iter()
+            .map(|part| full_equip.armors[part.as_str()].id.clone())
+            .collect::<Vec<_>>();
+
+        key.push(Self::get_talisman_key(
+            &full_equip.talisman.skills,
+            &full_equip.talisman.slots,
+        ));
+
+        key
+    }
+
+    // Feasible sets not matched by any answer at least as good on every part, which the calculator may leave out
+    pub fn find_uncovered<'a>(
+        &self,
+        expected: &'a BTreeSet<AnswerKey>,
+        actual: &BTreeSet<AnswerKey>,
+        req_skills: &HashMap<String, SkillSlotCount>,
+    ) -> Vec<&'a AnswerKey> {
+        let actual_equips = actual
+            .iter()
+            .filter_map(|key| self.get_equips(key))
+            .collect::<Vec<_>>();
+
+        expected
+            .iter()
+            .filter(|key| {
+                let equips = match self.get_equips(key) {
+                    Some(equips) => equips,
+                    None => return true,
+                };
+
+                !actual_equips.iter().any(|answer_equips| {
+                    equips
+                        .iter()
+                        .zip(answer_equips.iter())
+                        .all(|(equip, answer_equip)| {
+                            Self::is_le_equip(equip, answer_equip, req_skills)
+                        })
+                })
+            })
+            .collect()
+    }
+
+    fn get_equips(&self, key: &AnswerKey) -> Option<Vec<&OracleEquip>> {
+        key.iter()
+            .zip(self.parts.iter())
+            .map(|(equip_key, part_equips)| {
+                part_equips.iter().find(|equip| &equip.key == equip_key)
+            })
+            .collect()
+    }
+
+    fn is_le_equip(
+        equip: &OracleEquip,
+        other: &OracleEquip,
+        req_skills: &HashMap<String, SkillSlotCount>,
+    ) -> bool {
+        let is_le_skills = req_skills.keys().all(|skill_id| {
+            equip.skills.get(skill_id).copied().unwrap_or_default()
+                <= other.skills.get(skill_id).copied().unwrap_or_default()
+        });
+
+        is_le_skills
+            && (1..=MAX_SLOT_LEVEL as SkillSlotCount).all(|size| {
+                let count = |slot_sizes: &[SkillSlotCount]| {
+                    slot_sizes.iter().filter(|&&slot| size <= slot).count()
+                };
+
+                count(&equip.slot_sizes) <= count(&other.slot_sizes)
+            })
+    }
+
+    pub fn find_feasible(
+        &self,
+        req_skills: &HashMap<String, SkillSlotCount>,
+        request: &CalculateRequest,
+    ) -> BTreeSet<AnswerKey> {
+        let mut answers = BTreeSet::new();
+        let mut indices = vec![0; self.parts.len()];
+
+        if self.parts.iter().any(|part_equips| part_equips.is_empty()) {
+            return answers;
+        }
+
+        loop {
+            let equips = indices
+                .iter()
+                .enumerate()
+                .map(|(part, &index)| &self.parts[part][index])
+                .collect::<Vec<_>>();
+
+            if self.is_feasible(&equips, req_skills, request) {
+                answers.insert(equips.iter().map(|equip| equip.key.clone()).collect());
+            }
+
+            let mut part = 0;
+
+            loop {
+                if part == indices.len() {
+                    return answers;
+                }
+
+                indices[part] += 1;
+
+                if indices[part] < self.parts[part].len() {
+                    break;
+                }
+
+                indices[part] = 0;
+                part += 1;
+            }
+        }
+    }
+
+    pub fn is_feasible(
+        &self,
+        equips: &[&OracleEquip],
+        req_skills: &HashMap<String, SkillSlotCount>,
+        request: &CalculateRequest,
+    ) -> bool {
+        let mut missing_skills = HashMap::new();
+
+        for (skill_id, &level) in req_skills.iter() {
+            let equip_level = equips
+                .iter()
+                .map(|equip| equip.skills.get(skill_id).copied().unwrap_or_default())
+                .sum::<SkillSlotCount>();
+
+            if equip_level < level {
+                missing_skills.insert(skill_id.clone(), level - equip_level);
+            }
+        }
+
+        let mut slot_sizes = request
+            .weapon_slots
+            .iter()
+            .chain(equips.iter().flat_map(|equip| equip.slot_sizes.iter()))
+            .copied()
+            .filter(|&size| 0 < size)
+            .collect::<Vec<_>>();
+
+        slot_sizes.sort_unstable_by(|size1, size2| size2.cmp(size1));
+
+        let mut used_sizes = Vec::new();
+
+        for (index, &count) in request.free_slots.iter().enumerate().take(MAX_SLOT_LEVEL) {
+            for _ in 0..count {
+                used_sizes.push(index as SkillSlotCount + 1);
+            }
+        }
+
+        let decos = self
+            .decos
+            .iter()
+            .filter(|deco| missing_skills.contains_key(&deco.skill_id))
+            .collect::<Vec<_>>();
+
+        Self::is_possible_decos(&decos, &mut missing_skills, &slot_sizes, &mut used_sizes)
+    }
+
+    // Tries every count of each decoration in turn
+    fn is_possible_decos(
+        decos: &[&OracleDeco],
+        missing_skills: &mut HashMap<String, SkillSlotCount>,
+        slot_sizes: &[SkillSlotCount],
+        used_sizes: &mut Vec<SkillSlotCount>,
+    ) -> bool {
+        if !Self::fits_in_slots(used_sizes, slot_sizes) {
+            return false;
+        }
+
+        let (deco, left_decos) = match decos.split_first() {
+            Some(split) => split,
+            None => return missing_skills.values().all(|&level| level <= 0),
+        };
+
+        let missing_level = missing_skills[&deco.skill_id].max(0);
+        let mut max_count = (missing_level + deco.skill_level - 1) / deco.skill_level;
+
+        if let Some(owned_count) = deco.max_count {
+            max_count = max_count.min(owned_count);
+        }
+
+        for count in 0..=max_count {
+            *missing_skills.get_mut(&deco.skill_id).unwrap() -= count * deco.skill_level;
+            used_sizes.extend((0..count).map(|_| deco.slot_size));
+
+            let is_possible =
+                Self::is_possible_decos(left_decos, missing_skills, slot_sizes, used_sizes);
+
+            *missing_skills.get_mut(&deco.skill_id).unwrap() += count * deco.skill_level;
+            used_sizes.truncate(used_sizes.len() - count as usize);
+
+            if is_possible {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Biggest decoration goes to the biggest slot, which is optimal for slots sorted by size
+    fn fits_in_slots(used_sizes: &[SkillSlotCount], slot_sizes: &[SkillSlotCount]) -> bool {
+        let mut used_sizes = used_sizes.to_vec();
+        used_sizes.sort_unstable_by(|size1, size2| size2.cmp(size1));
+
+        used_sizes.len() <= slot_sizes.len()
+            && used_sizes
+                .iter()
+                .zip(slot_sizes.iter())
+                .all(|(used_size, slot_size)| used_size <= slot_size)
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    calc::{
+        calc_data_manager::CalcDataManager,
+        calc_reporter::EmptyReporter,
+        calc_request::{CalcCursor, CalculateRequest},
+        calc_result::{AdditionalSkillsResult, CalculateResult},
+        calculator::Calculator,
+        cancel_token::CancelToken,
+        constant::{MAX_ANSWER_LENGTH, MAX_SLOT_LEVEL},
+        types::SkillSlotCount,
+    },
+    data::{
+        armor::{AnomalyArmor, ArmorSkill, ArmorStat},
+        data_manager::DataManager,
+    },
+    utils::stable_hash::get_stable_hash,
+};
+
+use super::common::{self, calculate, load_cm, load_dm};
+
+// Common search has 82 answers, several pages of this size and under one full page
+const PAGE_SIZE: usize = 7;
+
+type AnswerKey = BTreeMap<String, String>;
+
+fn get_request(dm: &DataManager, answer_limit: usize) -> CalculateRequest {
+    let mut request = common::get_request(dm);
+    request.answer_limit = answer_limit;
+
+    request
+}
+
+fn get_answer_keys(result: &CalculateResult) -> Vec<AnswerKey> {
+    result
+        .full_equipments
+        .iter()
+        .map(|full_equip| {
+            full_equip
+                .armors
+                .iter()
+                .map(|(part, armor)| (part.clone(), armor.id.clone()))
+                .collect()
+        })
+        .collect()
+}
+
+// Cursors go through JSON in the app and the CLI
+fn round_trip(cursor: &CalcCursor) -> CalcCursor {
+    serde_json::from_str(&serde_json::to_string(cursor).unwrap()).unwrap()
+}
+
+fn get_all_pages(
+    dm: &DataManager,
+    cm: &CalcDataManager,
+    mut request: CalculateRequest,
+) -> Vec<CalculateResult> {
+    let mut pages = Vec::new();
+
+    loop {
+        let result = calculate(dm, cm, &request).unwrap();
+        let next_cursor = result.next_cursor.as_ref().map(round_trip);
+
+        pages.push(result);
+
+        match next_cursor {
+            Some(cursor) => request.cursor = Some(cursor),
+            None => break,
+        }
+    }
+
+    pages
+}
+
+#[tokio::test]
+async fn pages_cover_every_answer_once() {
+    let dm = load_dm().await;
+    let cm = load_cm(&dm, &get_request(&dm, PAGE_SIZE));
+
+    let full_result = calculate(&dm, &cm, &get_request(&dm, MAX_ANSWER_LENGTH)).unwrap();
+    assert!(full_result.next_cursor.is_none());
+
+    let full_keys = get_answer_keys(&full_result)
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert!(2 * PAGE_SIZE < full_keys.len());
+
+    let pages = get_all_pages(&dm, &cm, get_request(&dm, PAGE_SIZE));
+    let page_keys = pages.iter().flat_map(get_answer_keys).collect::<Vec<_>>();
+
+    assert!(pages
+        .iter()
+        .all(|page| page.full_equipments.len() <= PAGE_SIZE));
+    assert_eq!(
+        page_keys.len(),
+        page_keys.iter().collect::<HashSet<_>>().len()
+    );
+    assert_eq!(page_keys.into_iter().collect::<HashSet<_>>(), full_keys);
+}
+
+#[tokio::test]
+async fn cursor_resumes_with_other_managers() {
+    let dm = load_dm().await;
+    let cm = load_cm(&dm, &get_request(&dm, PAGE_SIZE));
+
+    let first_page = calculate(&dm, &cm, &get_request(&dm, PAGE_SIZE)).unwrap();
+
+    let mut request = get_request(&dm, PAGE_SIZE);
+    request.cursor = first_page.next_cursor.as_ref().map(round_trip);
+    assert!(request.cursor.is_some());
+
+    let second_page = calculate(&dm, &cm, &request).unwrap();
+
+    // Loaded again like in another run, hash maps iterate in another order
+    let other_dm = load_dm().await;
+    let other_cm = load_cm(&other_dm, &request);
+
+    let other_second_page = calculate(&other_dm, &other_cm, &request).unwrap();
+
+    // Answers of a page are the same, ties in the page sort may come in another order
+    let mut expected = get_answer_keys(&second_page);
+    let mut actual = get_answer_keys(&other_second_page);
+    expected.sort();
+    actual.sort();
+
+    assert_eq!(actual, expected);
+}
+
+// Fingerprints are kept by the app between calculations, the hash must not change between builds
+#[test]
+fn stable_hash_is_fixed() {
+    assert_eq!(
+        get_stable_hash(&(vec![3, 0, 0], "kamura_garb", true)),
+        0x446b7a2487f44238
+    );
+}
+
+#[tokio::test]
+async fn mismatched_cursor_is_error() {
+    let mut dm = load_dm().await;
+    let mut cm = load_cm(&dm, &get_request(&dm, PAGE_SIZE));
+
+    let cursor = calculate(&dm, &cm, &get_request(&dm, PAGE_SIZE))
+        .unwrap()
+        .next_cursor
+        .unwrap();
+
+    let resume = |dm: &DataManager, cm: &CalcDataManager, cursor: CalcCursor| {
+        let mut request = get_request(dm, PAGE_SIZE);
+        request.cursor = Some(cursor);
+
+        calculate(dm, cm, &request)
+    };
+
+    assert!(resume(&dm, &cm, cursor.clone()).is_ok());
+
+    // Other request
+    let mut request = get_request(&dm, PAGE_SIZE);
+    request
+        .selected_skills
+        .insert(dm.get_skill_uid("attack_boost"), 4);
+    request.cursor = Some(cursor.clone());
+    assert!(calculate(&dm, &cm, &request).is_err());
+
+    // Broken positions with the right fingerprint
+    let mut broken = serde_json::to_value(&cursor).unwrap();
+    broken["candidateIndex"] = serde_json::json!(usize::MAX);
+    assert!(resume(&dm, &cm, serde_json::from_value(broken).unwrap()).is_err());
+
+    let mut broken = serde_json::to_value(&cursor).unwrap();
+    broken["partsState"]["probeIds"] = serde_json::json!([[0, usize::MAX]]);
+    assert!(resume(&dm, &cm, serde_json::from_value(broken).unwrap()).is_err());
+
+    let mut broken = serde_json::to_value(&cursor).unwrap();
+    broken["leState"]["probeIds"] = serde_json::json!([[0, usize::MAX]]);
+    assert!(resume(&dm, &cm, serde_json::from_value(broken).unwrap()).is_err());
+
+    // Other equipments loaded
+    let anomaly = AnomalyArmor::new(
+        &dm.get_armors()["kamura_head_scarf"],
+        ArmorStat::new_empty(),
+        vec![1, 0, 0],
+        HashMap::from([("attack_boost".to_string(), ArmorSkill { level: 1 })]),
+    );
+    dm.set_file_anomalies(vec![anomaly]);
+    cm.load_anomalies(&dm);
+    cm.refresh_infos(
+        &dm,
+        &Calculator::convert_to_skills_container(&get_request(&dm, PAGE_SIZE).selected_skills),
+    );
+
+    assert!(resume(&dm, &cm, cursor).is_err());
+}
+
+fn calculate_additional_skills(
+    dm: &DataManager,
+    cm: &CalcDataManager,
+    request: &CalculateRequest,
+) -> AdditionalSkillsResult {
+    Calculator::calculate_additional_skills(
+        request,
+        dm,
+        cm,
+        &CancelToken::default(),
+        &EmptyReporter {},
+    )
+    .unwrap()
+    .1
+}
+
+#[tokio::test]
+async fn additional_skills_follow_pages() {
+    let dm = load_dm().await;
+    let cm = load_cm(&dm, &get_request(&dm, PAGE_SIZE));
+
+    let full = calculate_additional_skills(&dm, &cm, &get_request(&dm, MAX_ANSWER_LENGTH));
+    assert!(full.next_cursor.is_none());
+
+    let mut request = get_request(&dm, PAGE_SIZE);
+    let mut merged_skills = HashMap::<String, (SkillSlotCount, SkillSlotCount)>::new();
+    let mut merged_slots = vec![0; MAX_SLOT_LEVEL];
+    let mut equipments_count = 0;
+    let mut pages_count = 0;
+
+    loop {
+        let page = calculate_additional_skills(&dm, &cm, &request);
+
+        // Partial skills come with the cursor instead of nothing
+        if page.next_cursor.is_some() {
+            assert_eq!(page.equipments_count, PAGE_SIZE);
+            assert!(!page.skills.is_empty());
+        }
+
+        for (skill_id, (min_level, max_level)) in page.skills {
+            let merged = merged_skills
+                .entry(skill_id)
+                .or_insert((min_level, max_level));
+            merged.1 = merged.1.max(max_level);
+        }
+
+        for (merged, count) in merged_slots.iter_mut().zip(page.slots) {
+            *merged = (*merged).max(count);
+        }
+
+        equipments_count += page.equipments_count;
+        pages_count += 1;
+
+        match page.next_cursor {
+            Some(cursor) => request.cursor = Some(cursor),
+            None => break,
+        }
+    }
+
+    assert!(2 < pages_count);
+    assert_eq!(equipments_count, full.equipments_count);
+    assert_eq!(merged_skills, full.skills);
+    assert_eq!(merged_slots, full.slots);
+}
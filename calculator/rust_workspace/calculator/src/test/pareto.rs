@@ -0,0 +1,249 @@
+use crate::{
+    calc::{
+        calc_data_manager::CalcDataManager,
+        calc_equipment::CalcEquipment,
+        calc_request::{CalcCursor, CalculateRequest},
+        calc_result::{CalculateResult, ResultFullEquipments},
+        calc_vector::CalcVector,
+        constant::MAX_SLOT_LEVEL,
+        skills::SkillsContainer,
+        types::SlotsVec,
+    },
+    data::{armor::ArmorStat, data_manager::DataManager},
+};
+
+use super::{
+    common::{self, load_cm},
+    differential::{random_case, GameData},
+    oracle::{AnswerKey, BruteForceOracle},
+};
+
+const CASES_COUNT: u64 = 100;
+
+// Values ParetoFilter compares, taken back from a result
+struct Summary {
+    key: AnswerKey,
+    skills: SkillsContainer,
+    total_slots_lp: SlotsVec,
+    leftover_slots_lp: SlotsVec,
+    stats: ArmorStat,
+}
+
+// Same order as the leftover slots sort, sum first then counts from the biggest size
+fn get_leftover_key(slots: &SlotsVec) -> Vec<i32> {
+    let mut key = vec![slots.iter().map(|&count| count as i32).sum()];
+    key.extend((0..MAX_SLOT_LEVEL).rev().map(|index| slots[index] as i32));
+
+    key
+}
+
+fn get_summary(dm: &DataManager, full_equip: &ResultFullEquipments) -> Summary {
+    let mut skills = SkillsContainer::new();
+    let mut stats = ArmorStat::new_empty();
+
+    let all_skills = full_equip
+        .armors
+        .values()
+        .flat_map(|armor| armor.skills.iter())
+        .chain(full_equip.talisman.skills.iter());
+
+    for (skill_id, level) in all_skills {
+        let uid = dm.get_skill_uid(skill_id);
+        skills.set(uid, skills.get(uid) + level);
+    }
+
+    for armor in full_equip.armors.values() {
+        stats.defense += armor.stat.defense;
+        stats.fire_res += armor.stat.fire_res;
+        stats.water_res += armor.stat.water_res;
+        stats.ice_res += armor.stat.ice_res;
+        stats.elec_res += armor.stat.elec_res;
+        stats.dragon_res += armor.stat.dragon_res;
+    }
+
+    let leftover_slots = full_equip
+        .deco_combs
+        .iter()
+        .map(|deco_comb| SlotsVec::from_column_slice(&deco_comb.leftover_slots_sum))
+        .max_by_key(get_leftover_key)
+        .unwrap_or_default();
+
+    Summary {
+        key: BruteForceOracle::get_answer_key(full_equip),
+        skills,
+        total_slots_lp: CalcVector::convert_to_lp_slots(&SlotsVec::from_column_slice(
+            &full_equip.total_raw_slots,
+        )),
+        leftover_slots_lp: CalcVector::convert_to_lp_slots(&leftover_slots),
+        stats,
+    }
+}
+
+fn is_le(summary: &Summary, other: &Summary) -> bool {
+    CalcEquipment::is_le_values(
+        (&summary.total_slots_lp, &other.total_slots_lp),
+        (&summary.skills, &other.skills),
+        Some((summary.stats.clone(), other.stats.clone())),
+        None,
+    ) && CalcEquipment::is_le_values(
+        (&summary.leftover_slots_lp, &other.leftover_slots_lp),
+        (&summary.skills, &other.skills),
+        None,
+        None,
+    )
+}
+
+fn is_strictly_le(summary: &Summary, other: &Summary) -> bool {
+    is_le(summary, other) && !is_le(other, summary)
+}
+
+fn calculate(
+    dm: &DataManager,
+    cm: &CalcDataManager,
+    request: &CalculateRequest,
+    pareto_only: bool,
+) -> CalculateResult {
+    let mut request = request.clone();
+    request.pareto_only = pareto_only;
+
+    let result = common::calculate(dm, cm, &request).unwrap();
+
+    assert!(result.next_cursor.is_none());
+
+    result
+}
+
+// Pareto answers are a part of all answers and none is dominated by another answer,
+// every dropped answer is dominated by a kept one
+#[test]
+fn pareto_answers_are_not_dominated() {
+    let data = GameData::load();
+
+    let mut failures = Vec::new();
+    let mut filtered_count = 0;
+
+    for seed in 0..CASES_COUNT {
+        let case = random_case(seed, &data);
+        let dm = &case.dm;
+
+        let cm = load_cm(dm, &case.request);
+
+        let all_summaries = calculate(dm, &cm, &case.request, false)
+            .full_equipments
+            .iter()
+            .map(|full_equip| get_summary(dm, full_equip))
+            .collect::<Vec<_>>();
+
+        let pareto_summaries = calculate(dm, &cm, &case.request, true)
+            .full_equipments
+            .iter()
+            .map(|full_equip| get_summary(dm, full_equip))
+            .collect::<Vec<_>>();
+
+        let unknown = pareto_summaries
+            .iter()
+            .filter(|pareto| all_summaries.iter().all(|other| other.key != pareto.key))
+            .map(|pareto| &pareto.key)
+            .collect::<Vec<_>>();
+
+        let dominated = pareto_summaries
+            .iter()
+            .filter(|pareto| {
+                all_summaries
+                    .iter()
+                    .any(|other| is_strictly_le(pareto, other))
+            })
+            .map(|pareto| &pareto.key)
+            .collect::<Vec<_>>();
+
+        let uncovered = all_summaries
+            .iter()
+            .filter(|other| {
+                !pareto_summaries
+                    .iter()
+                    .any(|pareto| pareto.key == other.key || is_le(other, pareto))
+            })
+            .map(|other| &other.key)
+            .collect::<Vec<_>>();
+
+        filtered_count += all_summaries.len() - pareto_summaries.len();
+
+        if !unknown.is_empty() || !dominated.is_empty() || !uncovered.is_empty() {
+            failures.push(format!(
+                "Seed {}: skills {:?}, unknown: {:?}, dominated: {:?}, uncovered: {:?}",
+                seed, case.req_skills, unknown, dominated, uncovered
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Failed cases:\n{}",
+        failures.join("\n")
+    );
+
+    // Cases must have dominated answers for the check to mean something
+    assert!(0 < filtered_count);
+}
+
+// Earlier pages can not be compared with, a Pareto page is never resumed
+#[test]
+fn pareto_search_refuses_cursor() {
+    let data = GameData::load();
+    let case = random_case(0, &data);
+    let dm = &case.dm;
+
+    let mut request = case.request.clone();
+    request.pareto_only = true;
+    request.cursor = Some(CalcCursor::default());
+
+    let cm = load_cm(dm, &request);
+
+    assert!(common::calculate(dm, &cm, &request).is_err());
+}
+
+// A limited Pareto page is the start of the whole front, not the first answers found
+#[test]
+fn limited_pareto_page_is_front_start() {
+    let data = GameData::load();
+
+    let mut checked_count = 0;
+
+    for seed in 0..CASES_COUNT {
+        let case = random_case(seed, &data);
+        let dm = &case.dm;
+
+        let cm = load_cm(dm, &case.request);
+
+        let front_keys = calculate(dm, &cm, &case.request, true)
+            .full_equipments
+            .iter()
+            .map(BruteForceOracle::get_answer_key)
+            .collect::<Vec<_>>();
+
+        if front_keys.len() < 2 {
+            continue;
+        }
+
+        let mut request = case.request.clone();
+        request.answer_limit = front_keys.len() / 2;
+
+        let page_keys = calculate(dm, &cm, &request, true)
+            .full_equipments
+            .iter()
+            .map(BruteForceOracle::get_answer_key)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            page_keys,
+            front_keys[..request.answer_limit],
+            "Seed {}: skills {:?}",
+            seed,
+            case.req_skills
+        );
+
+        checked_count += 1;
+    }
+
+    assert!(0 < checked_count);
+}
@@ -0,0 +1,206 @@
+use std::path::PathBuf;
+
+use crate::data::parser::{parse_anomaly, parse_talisman, ParseError, ParseErrorKind};
+
+use super::common::load_dm;
+
+const ARMOR_NAME: &str = "카이저X크라운";
+const SKILL_NAME: &str = "간파";
+
+fn write_temp_file(name: &str, lines: &[&str]) -> PathBuf {
+    let filename = std::env::temp_dir().join(format!(
+        "mhr_calculator_{}_{}.txt",
+        name,
+        std::process::id()
+    ));
+
+    std::fs::write(&filename, lines.join("\n")).unwrap();
+
+    filename
+}
+
+fn get_positions(errors: &[ParseError]) -> Vec<(u64, usize)> {
+    errors
+        .iter()
+        .map(|error| (error.line, error.column))
+        .collect()
+}
+
+fn get_error(errors: &[ParseError], line: u64, column: usize) -> &ParseError {
+    errors
+        .iter()
+        .find(|error| error.line == line && error.column == column)
+        .unwrap()
+}
+
+fn get_suggestions(error: &ParseError) -> &Vec<String> {
+    match &error.kind {
+        ParseErrorKind::UnknownArmorName { suggestions }
+        | ParseErrorKind::UnknownSkillName { suggestions } => suggestions,
+        kind => panic!("No suggestions in {:?}", kind),
+    }
+}
+
+#[tokio::test]
+async fn anomaly_errors_point_at_values() {
+    let dm = load_dm().await;
+
+    let filename = write_temp_file(
+        "parser_anomaly",
+        &[
+            "카이저X크라운,5,-1,0,0,0,-4,2,0,0,간파,1",
+            "카이저X크라운,abc,0,0,0,0,0,0,0,0,,,간파,x",
+            "카이저X크라욷,1,0,0,0,0,0,0,0,0,,",
+            "카이저X크라운,1,0,0,0,0,0,0,0,0,간피,1",
+            "카이저X크라운,1",
+        ],
+    );
+
+    let result = parse_anomaly(
+        &filename,
+        dm.get_armors(),
+        dm.get_armor_name_dict(),
+        dm.get_skill_name_dict(),
+    )
+    .unwrap();
+
+    std::fs::remove_file(&filename).unwrap();
+
+    // Only the first line is valid, every broken value of the others is reported
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(
+        get_positions(&result.errors),
+        [(2, 2), (2, 14), (3, 1), (4, 11)]
+            .into_iter()
+            .chain((3..=10).map(|column| (5, column)))
+            .collect::<Vec<_>>()
+    );
+
+    let error = get_error(&result.errors, 2, 2);
+    assert_eq!(error.kind, ParseErrorKind::InvalidNumber);
+    assert_eq!(error.value, "abc");
+
+    let error = get_error(&result.errors, 2, 14);
+    assert_eq!(error.kind, ParseErrorKind::InvalidNumber);
+    assert_eq!(error.value, "x");
+
+    let error = get_error(&result.errors, 3, 1);
+    assert!(matches!(
+        error.kind,
+        ParseErrorKind::UnknownArmorName { .. }
+    ));
+    assert!(get_suggestions(error).iter().any(|name| name == ARMOR_NAME));
+    assert!(error.to_string().starts_with("line 3, column 1: "));
+    assert!(error.to_string().contains(ARMOR_NAME));
+
+    let error = get_error(&result.errors, 4, 11);
+    assert!(matches!(
+        error.kind,
+        ParseErrorKind::UnknownSkillName { .. }
+    ));
+    assert!(get_suggestions(error).iter().any(|name| name == SKILL_NAME));
+
+    assert!(result
+        .errors
+        .iter()
+        .filter(|error| error.line == 5)
+        .all(|error| error.kind == ParseErrorKind::MissingValue));
+}
+
+#[tokio::test]
+async fn talisman_errors_follow_file_lines() {
+    let dm = load_dm().await;
+
+    // Quoted names may span lines, errors after them still have the line in the file
+    let filename = write_temp_file(
+        "parser_talisman",
+        &[
+            "\"간",
+            "파\",1,,0,1,0,0",
+            "간파,x,,0,1,0,0",
+            "간피,1,,0,1,0,0",
+            "간파,3,,0,3,0,0",
+            "간파,3,,0,3,0",
+        ],
+    );
+
+    let result = parse_talisman(&filename, dm.get_skill_name_dict()).unwrap();
+
+    std::fs::remove_file(&filename).unwrap();
+
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(
+        get_positions(&result.errors),
+        [(1, 1), (3, 2), (4, 1), (6, 7)]
+    );
+
+    let error = get_error(&result.errors, 1, 1);
+    assert_eq!(error.value, "간\n파");
+
+    let error = get_error(&result.errors, 4, 1);
+    assert!(get_suggestions(error).iter().any(|name| name == SKILL_NAME));
+    assert!(error.to_string().ends_with(&format!(
+        "(did you mean {}?)",
+        get_suggestions(error).join(", ")
+    )));
+}
+
+#[tokio::test]
+async fn slot_sizes_out_of_range_are_errors() {
+    let dm = load_dm().await;
+
+    let talisman_filename = write_temp_file(
+        "parser_talisman_slots",
+        &["간파,3,,0,7,0,0", "간파,3,,0,4,-1,0", "간파,3,,0,4,1,0"],
+    );
+
+    let talismans = parse_talisman(&talisman_filename, dm.get_skill_name_dict()).unwrap();
+
+    std::fs::remove_file(&talisman_filename).unwrap();
+
+    assert_eq!(talismans.items.len(), 1);
+    assert_eq!(get_positions(&talismans.errors), [(1, 5), (2, 6)]);
+
+    let error = get_error(&talismans.errors, 1, 5);
+    assert_eq!(error.kind, ParseErrorKind::OutOfRange { min: 0, max: 4 });
+    assert_eq!(error.value, "7");
+    assert_eq!(
+        error.to_string(),
+        "line 1, column 5: number \"7\" out of range 0 to 4"
+    );
+
+    let anomaly_filename = write_temp_file(
+        "parser_anomaly_slots",
+        &["카이저X크라운,5,0,0,0,0,0,2,0,5,간파,1"],
+    );
+
+    let anomalies = parse_anomaly(
+        &anomaly_filename,
+        dm.get_armors(),
+        dm.get_armor_name_dict(),
+        dm.get_skill_name_dict(),
+    )
+    .unwrap();
+
+    std::fs::remove_file(&anomaly_filename).unwrap();
+
+    assert!(anomalies.items.is_empty());
+    assert_eq!(get_positions(&anomalies.errors), [(1, 10)]);
+}
+
+#[test]
+fn unknown_name_without_close_names() {
+    let error = ParseError {
+        line: 7,
+        column: 3,
+        value: "zzzzzzzz".to_string(),
+        kind: ParseErrorKind::UnknownSkillName {
+            suggestions: Vec::new(),
+        },
+    };
+
+    assert_eq!(
+        error.to_string(),
+        "line 7, column 3: unknown skill name \"zzzzzzzz\""
+    );
+}
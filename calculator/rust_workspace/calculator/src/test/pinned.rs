@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    calc::calc_request::CalculateRequest,
+    data::{
+        armor::{ArmorPart, BaseArmor},
+        data_manager::DataManager,
+    },
+};
+
+use super::{
+    common::{self, load_cm},
+    differential::{random_case, GameData},
+    oracle::{AnswerKey, BruteForceOracle},
+    rng::TestRng,
+};
+
+const CASES_COUNT: u64 = 100;
+
+fn calculate(dm: &DataManager, request: &CalculateRequest) -> BTreeSet<AnswerKey> {
+    let result = common::calculate(dm, &load_cm(dm, request), request).unwrap();
+
+    assert!(result.next_cursor.is_none());
+
+    BruteForceOracle::get_result_keys(&result)
+}
+
+fn filter_answers(
+    answers: &BTreeSet<AnswerKey>,
+    is_kept: impl Fn(&AnswerKey) -> bool,
+) -> BTreeSet<AnswerKey> {
+    answers.iter().filter(|key| is_kept(key)).cloned().collect()
+}
+
+// Answers with a pinned or excluded armor must be exactly the answers without them
+// which have the pinned one and don't have the excluded one
+#[test]
+fn pinned_and_excluded_armors_filter_answers() {
+    let data = GameData::load();
+
+    let mut failures = Vec::new();
+    let mut filtered_count = 0;
+
+    for seed in 0..CASES_COUNT {
+        let case = random_case(seed, &data);
+        let dm = &case.dm;
+
+        let all_answers = calculate(dm, &case.request);
+
+        let used_armors = all_answers
+            .iter()
+            .flat_map(|key| {
+                key.iter()
+                    .take(ArmorPart::get_all_armor().len())
+                    .enumerate()
+            })
+            .filter(|(_, id)| !BaseArmor::is_empty_armor(id))
+            .map(|(part, id)| (part, id.clone()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        if used_armors.len() < 2 {
+            continue;
+        }
+
+        let mut rng = TestRng::new(seed);
+
+        let (pinned_part, pinned_id) = rng.pick(&used_armors).clone();
+
+        // Pinning an excluded armor is an error
+        let other_armors = used_armors
+            .iter()
+            .filter(|(_, id)| *id != pinned_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        let (_, excluded_id) = rng.pick(&other_armors).clone();
+
+        let has_pinned = |key: &AnswerKey| key[pinned_part] == pinned_id;
+        let has_excluded = |key: &AnswerKey| key.contains(&excluded_id);
+
+        let mut pinned_request = case.request.clone();
+        pinned_request
+            .pinned_equips
+            .insert(ArmorPart::from_usize(pinned_part), pinned_id.clone());
+
+        let mut excluded_request = case.request.clone();
+        excluded_request.excluded_equips.insert(excluded_id.clone());
+
+        let mut both_request = pinned_request.clone();
+        both_request.excluded_equips.insert(excluded_id.clone());
+
+        let checks = [
+            (&pinned_request, filter_answers(&all_answers, has_pinned)),
+            (
+                &excluded_request,
+                filter_answers(&all_answers, |key| !has_excluded(key)),
+            ),
+            (
+                &both_request,
+                filter_answers(&all_answers, |key| has_pinned(key) && !has_excluded(key)),
+            ),
+        ];
+
+        for (request, expected) in checks {
+            let actual = calculate(dm, request);
+
+            filtered_count += all_answers.len() - expected.len();
+
+            if actual != expected {
+                failures.push(format!(
+                    "Seed {}: skills {:?}, pinned {} ({}), excluded {}\nmissing: {:?}\nextra: {:?}",
+                    seed,
+                    case.req_skills,
+                    pinned_id,
+                    ArmorPart::from_usize(pinned_part).as_str(),
+                    excluded_id,
+                    expected.difference(&actual).collect::<Vec<_>>(),
+                    actual.difference(&expected).collect::<Vec<_>>(),
+                ));
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Failed cases:\n{}",
+        failures.join("\n")
+    );
+
+    assert!(0 < filtered_count);
+}
+
+#[test]
+fn unknown_pinned_armor_is_error() {
+    let data = GameData::load();
+    let case = random_case(0, &data);
+    let dm = &case.dm;
+
+    let mut request = case.request.clone();
+    request
+        .pinned_equips
+        .insert(ArmorPart::Helm, "unknown_helm".to_string());
+
+    match common::calculate(dm, &load_cm(dm, &request), &request) {
+        Ok(_) => panic!("Unknown pinned armor must be an error"),
+        Err(error) => assert!(error.to_string().contains("unknown_helm")),
+    }
+}
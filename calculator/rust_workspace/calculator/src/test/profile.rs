@@ -0,0 +1,334 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{
+    calc::constant::MAX_ANSWER_LENGTH,
+    data::{
+        armor::{AnomalyArmor, ArmorStat, Talisman},
+        data_manager::DataManager,
+        profile::{Profile, ProfileStore, DEFAULT_PROFILE_NAME, PROFILE_VERSION},
+    },
+};
+
+use super::common::load_dm;
+
+fn create_temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "mhr_calculator_profile_{}_{}",
+        name,
+        std::process::id()
+    ));
+
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+fn get_profile(banned_deco: &str) -> Profile {
+    let mut profile = Profile::default();
+    profile.banned_decos.insert(banned_deco.to_string());
+    profile.anomaly_filename = format!("{}.txt", banned_deco);
+
+    profile
+}
+
+fn assert_same_profile(actual: &Profile, expected: &Profile) {
+    assert_eq!(actual.banned_decos, expected.banned_decos);
+    assert_eq!(actual.anomaly_filename, expected.anomaly_filename);
+}
+
+#[test]
+fn create_profiles() {
+    let dir = create_temp_dir("create");
+    let store = ProfileStore::new(&dir);
+
+    assert!(store.get_names().is_empty());
+
+    store.create("b").unwrap();
+    store.create("a").unwrap();
+
+    assert_eq!(store.get_names(), ["a", "b"]);
+    assert!(store.exists("a"));
+    assert!(store.create("a").is_err());
+
+    for name in ["", " a", ".a", "a/b", "a:b"] {
+        assert!(store.create(name).is_err(), "{:?}", name);
+    }
+
+    assert_eq!(store.get_names(), ["a", "b"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn active_name_is_remembered() {
+    let dir = create_temp_dir("active");
+    let store = ProfileStore::new(&dir);
+
+    assert_eq!(store.get_active_name(), DEFAULT_PROFILE_NAME);
+
+    store.create("a").unwrap();
+    store.create("b").unwrap();
+
+    // First profile when none is remembered
+    assert_eq!(store.get_active_name(), "a");
+
+    store.set_active_name("b").unwrap();
+
+    assert_eq!(ProfileStore::new(&dir).get_active_name(), "b");
+
+    // Remembered profile removed by hand
+    fs::remove_file(dir.join("profiles").join("b.json")).unwrap();
+
+    assert_eq!(store.get_active_name(), "a");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rename_profiles() {
+    let dir = create_temp_dir("rename");
+    let store = ProfileStore::new(&dir);
+
+    let profile = get_profile("a");
+
+    store.save("a", &profile).unwrap();
+    store.create("b").unwrap();
+    store.set_active_name("a").unwrap();
+
+    // Active one stays active with its new name
+    store.rename("a", "c").unwrap();
+
+    assert_eq!(store.get_names(), ["b", "c"]);
+    assert_eq!(store.get_active_name(), "c");
+    assert_same_profile(&store.load("c").unwrap(), &profile);
+
+    store.rename("b", "d").unwrap();
+
+    assert_eq!(store.get_names(), ["c", "d"]);
+    assert_eq!(store.get_active_name(), "c");
+
+    assert!(store.rename("c", "d").is_err());
+    assert!(store.rename("missing", "e").is_err());
+    assert!(store.rename("c", "e/f").is_err());
+    assert_eq!(store.get_names(), ["c", "d"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn duplicate_profiles() {
+    let dir = create_temp_dir("duplicate");
+    let store = ProfileStore::new(&dir);
+
+    let profile = get_profile("a");
+
+    store.save("a", &profile).unwrap();
+    store.set_active_name("a").unwrap();
+
+    store.duplicate("a", "b").unwrap();
+
+    assert_eq!(store.get_names(), ["a", "b"]);
+    assert_eq!(store.get_active_name(), "a");
+    assert_same_profile(&store.load("b").unwrap(), &profile);
+
+    // Copies are independent
+    store.save("b", &get_profile("b")).unwrap();
+
+    assert_same_profile(&store.load("a").unwrap(), &profile);
+
+    assert!(store.duplicate("a", "b").is_err());
+    assert!(store.duplicate("missing", "c").is_err());
+    assert_eq!(store.get_names(), ["a", "b"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn delete_profiles() {
+    let dir = create_temp_dir("delete");
+    let store = ProfileStore::new(&dir);
+
+    for name in ["a", "b", "c"] {
+        store.create(name).unwrap();
+    }
+
+    store.set_active_name("b").unwrap();
+
+    store.delete("c").unwrap();
+
+    assert_eq!(store.get_names(), ["a", "b"]);
+    assert_eq!(store.get_active_name(), "b");
+
+    // Active one falls back to the first left
+    store.delete("b").unwrap();
+
+    assert_eq!(store.get_names(), ["a"]);
+    assert_eq!(store.get_active_name(), "a");
+
+    assert!(store.delete("missing").is_err());
+
+    store.delete("a").unwrap();
+
+    assert!(store.get_names().is_empty());
+    assert_eq!(store.get_active_name(), DEFAULT_PROFILE_NAME);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn single_profile_becomes_default() {
+    let dir = create_temp_dir("migrate");
+
+    let profile = get_profile("a");
+    profile.save(&dir.join("profile.json")).unwrap();
+
+    let store = ProfileStore::new(&dir);
+
+    assert!(!dir.join("profile.json").exists());
+    assert_eq!(store.get_names(), [DEFAULT_PROFILE_NAME]);
+    assert_eq!(store.get_active_name(), DEFAULT_PROFILE_NAME);
+    assert_same_profile(&store.load(DEFAULT_PROFILE_NAME).unwrap(), &profile);
+
+    // Only moved while there are no named profiles
+    get_profile("b").save(&dir.join("profile.json")).unwrap();
+
+    let store = ProfileStore::new(&dir);
+
+    assert!(dir.join("profile.json").exists());
+    assert_same_profile(&store.load(DEFAULT_PROFILE_NAME).unwrap(), &profile);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unversioned_profile_is_migrated() {
+    let dir = create_temp_dir("unversioned");
+    let filename = dir.join("profile.json");
+
+    fs::write(
+        &filename,
+        r#"{
+            "bannedDecos": { "attack_jewel": true, "expert_jewel": false },
+            "anomalyFilename": "anomalies.txt",
+            "lastSearch": {
+                "sexType": "female",
+                "weaponSlots": [1, 0, 0],
+                "selectedSkills": { "attack_boost": 3 },
+                "freeSlots": [0, 0, 0, 0]
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let profile = Profile::load(&filename).unwrap();
+
+    assert_eq!(profile.version, PROFILE_VERSION);
+    assert_eq!(profile.anomaly_filename, "anomalies.txt");
+    assert_eq!(
+        profile.banned_decos.into_iter().collect::<Vec<_>>(),
+        ["attack_jewel"]
+    );
+
+    let last_search = profile.last_search.unwrap();
+
+    assert_eq!(last_search.selected_skills["attack_boost"], 3);
+    assert_eq!(last_search.answer_limit, MAX_ANSWER_LENGTH);
+    assert!(last_search.pinned_equips.is_empty());
+    assert!(!last_search.pareto_only);
+
+    // Broken last search only loses the search form
+    fs::write(&filename, r#"{ "lastSearch": { "sexType": 1 } }"#).unwrap();
+
+    assert!(Profile::load(&filename).unwrap().last_search.is_none());
+
+    // Newer versions are not guessed at
+    fs::write(&filename, r#"{ "version": 99 }"#).unwrap();
+
+    assert!(Profile::load(&filename).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+// Pinned and excluded ids of the last search must still point at the same manual equipments
+#[tokio::test]
+async fn manual_ids_are_kept() {
+    let mut dm = load_dm().await;
+
+    for original_id in ["kamura_head_scarf", "leather_headgear", "hunters_helm"] {
+        let original = dm.get_armors()[original_id].clone();
+
+        dm.add_manual_anomaly(AnomalyArmor::new(
+            &original,
+            ArmorStat::new_empty(),
+            vec![1, 0, 0],
+            HashMap::new(),
+        ));
+    }
+
+    for slot_size in 1..=3 {
+        dm.add_manual_talisman(&Talisman::new(
+            String::new(),
+            Vec::new(),
+            vec![slot_size, 0, 0],
+        ));
+    }
+
+    let removed_anomaly_id = dm
+        .get_manual_anomaly_armors()
+        .keys()
+        .find(|id| id.ends_with("kamura_head_scarf"))
+        .unwrap()
+        .clone();
+    let removed_talisman_id = dm.get_manual_talismans()[0].id().clone();
+
+    dm.remove_manual_anomaly(&removed_anomaly_id);
+    dm.remove_manual_talisman(&removed_talisman_id);
+
+    let mut profile = Profile::default();
+    profile.update_manuals(&dm);
+
+    let get_ids = |dm: &DataManager| {
+        let mut anomaly_ids = dm
+            .get_manual_anomaly_armors()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        anomaly_ids.sort();
+
+        let talisman_ids = dm
+            .get_manual_talismans()
+            .iter()
+            .map(|tali| tali.id().clone())
+            .collect::<Vec<_>>();
+
+        (anomaly_ids, talisman_ids)
+    };
+
+    let saved_ids = get_ids(&dm);
+
+    dm.clear_manual_anomalies();
+    dm.clear_manual_talismans();
+
+    profile.apply_manuals(&mut dm);
+
+    assert_eq!(get_ids(&dm), saved_ids);
+
+    // New ones never take the id of a kept one
+    let original = dm.get_armors()["kamura_head_scarf"].clone();
+    let anomaly = dm.add_manual_anomaly(AnomalyArmor::new(
+        &original,
+        ArmorStat::new_empty(),
+        vec![1, 0, 0],
+        HashMap::new(),
+    ));
+    let talisman = dm.add_manual_talisman(&Talisman::new(String::new(), Vec::new(), vec![1, 0, 0]));
+
+    assert!(!saved_ids.0.contains(anomaly.affected.id()));
+    assert!(!saved_ids.1.contains(talisman.id()));
+    assert_eq!(dm.get_manual_anomaly_armors().len(), 3);
+    assert_eq!(dm.get_manual_talismans().len(), 3);
+}
@@ -0,0 +1,115 @@
+use crate::calc::{constant::MAX_SKILLS_COUNT, skills::SkillsContainer, types::SkillSlotCount};
+
+use super::rng::TestRng;
+
+const CASES_COUNT: u64 = 500;
+// Few enough that levels stay in range
+const STEPS_COUNT: usize = 10;
+const MAX_SKILLS_LEN: i64 = 6;
+
+// Equipment-like container, a few skills with small levels
+fn random_levels(rng: &mut TestRng) -> Vec<SkillSlotCount> {
+    let mut levels = vec![0; MAX_SKILLS_COUNT];
+
+    for _ in 0..rng.range(0, MAX_SKILLS_LEN) {
+        let uid = rng.range(0, MAX_SKILLS_COUNT as i64 - 1) as usize;
+
+        levels[uid] = rng.range(-3, 7) as SkillSlotCount;
+    }
+
+    levels
+}
+
+// Same operations on plain levels, as the dense container did them
+fn assert_same(container: &SkillsContainer, levels: &[SkillSlotCount]) {
+    let expected = levels
+        .iter()
+        .enumerate()
+        .filter(|(_, &level)| 0 < level)
+        .map(|(uid, &level)| (uid, level))
+        .collect::<Vec<_>>();
+
+    assert_eq!(container.get_list(), expected);
+    assert_eq!(container.len(), expected.len());
+    assert_eq!(container.is_empty(), expected.is_empty());
+    assert_eq!(
+        container.get_indices(),
+        expected.iter().map(|(uid, _)| *uid).collect::<Vec<_>>()
+    );
+    assert_eq!(container, &SkillsContainer::from(levels.to_vec()));
+
+    for (uid, &level) in levels.iter().enumerate() {
+        assert_eq!(container.get(uid), level.max(0));
+        assert_eq!(container.contains(uid), 0 < level);
+    }
+}
+
+#[test]
+fn masked_container_matches_dense_levels() {
+    let mut rng = TestRng::new(5);
+
+    for _ in 0..CASES_COUNT {
+        let mut levels = random_levels(&mut rng);
+        let mut container = SkillsContainer::from(levels.clone());
+
+        for _ in 0..STEPS_COUNT {
+            let other_levels = random_levels(&mut rng);
+            let mut other = SkillsContainer::from(other_levels.clone());
+
+            let is_le = levels.iter().zip(&other_levels).all(|(a, b)| a <= b);
+            assert_eq!(container.is_le(&other), is_le);
+            assert_eq!(container.get_diff(&other).is_empty(), is_le);
+
+            match rng.range(0, 5) {
+                0 => {
+                    container.add(&other);
+                    levels
+                        .iter_mut()
+                        .zip(&other_levels)
+                        .for_each(|(a, b)| *a += b);
+                }
+                1 => {
+                    container.sub(&other);
+                    levels
+                        .iter_mut()
+                        .zip(&other_levels)
+                        .for_each(|(a, b)| *a -= b);
+                }
+                2 => {
+                    container.sub_mut(&mut other);
+                    levels
+                        .iter_mut()
+                        .zip(&other_levels)
+                        .for_each(|(a, b)| *a -= b);
+
+                    let negated = levels.iter().map(|level| -level).collect::<Vec<_>>();
+                    assert_same(&other, &negated);
+                }
+                3 => {
+                    let uid = rng.range(0, MAX_SKILLS_COUNT as i64 - 1) as usize;
+                    let level = rng.range(-3, 7) as SkillSlotCount;
+
+                    if rng.chance(50) {
+                        container.set(uid, level);
+                        levels[uid] = level;
+                    } else {
+                        container.add_level(uid, level);
+                        levels[uid] += level;
+                    }
+                }
+                4 => {
+                    container.clear_zeros();
+                    levels.iter_mut().for_each(|level| *level = (*level).max(0));
+                }
+                _ => {
+                    for (uid, level) in container.iter_mut() {
+                        *level -= 1;
+                        levels[uid] -= 1;
+                    }
+                }
+            }
+
+            assert_same(&container, &levels);
+        }
+    }
+}
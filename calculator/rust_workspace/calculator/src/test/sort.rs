@@ -0,0 +1,170 @@
+use crate::{
+    calc::{
+        calc_request::{CalcCursor, CalculateRequest},
+        calc_result::{CalculateResult, ResultFullEquipments},
+        calc_sort::{SortKey, SortKind},
+        constant::MAX_ANSWER_LENGTH,
+        types::SkillSlotCount,
+    },
+    data::{armor::ArmorStat, data_manager::DataManager},
+};
+
+use super::common::{self, load_cm, load_dm};
+
+const ANSWER_LIMIT: usize = 10;
+
+fn get_request(dm: &DataManager, sort_keys: &[SortKey], answer_limit: usize) -> CalculateRequest {
+    let mut request = common::get_request(dm);
+    request.sort_keys = sort_keys.to_vec();
+    request.answer_limit = answer_limit;
+
+    request
+}
+
+// Every answer (82) of the common search fits in one result
+fn calculate(dm: &DataManager, request: &CalculateRequest) -> CalculateResult {
+    let result = common::calculate(dm, &load_cm(dm, request), request).unwrap();
+
+    // Ranked search keeps the best answers instead of stopping at the limit
+    assert!(result.next_cursor.is_none());
+
+    result
+}
+
+fn sum_stat(full_equip: &ResultFullEquipments, stat_value: fn(&ArmorStat) -> i16) -> i32 {
+    full_equip
+        .armors
+        .values()
+        .map(|armor| stat_value(&armor.stat) as i32)
+        .sum()
+}
+
+fn get_leftover_slots_key(slots: &[SkillSlotCount]) -> Vec<i32> {
+    let mut key = vec![slots.iter().map(|&count| count as i32).sum()];
+    key.extend(slots.iter().rev().map(|&count| count as i32));
+
+    key
+}
+
+// Rank of AnswerSorter, taken back from a result
+fn get_rank(full_equip: &ResultFullEquipments, sort_keys: &[SortKey]) -> Vec<i32> {
+    let mut rank = Vec::new();
+
+    for sort_key in sort_keys {
+        let values = match sort_key.kind {
+            SortKind::Defense => vec![sum_stat(full_equip, |stat| stat.defense)],
+            SortKind::FireRes => vec![sum_stat(full_equip, |stat| stat.fire_res)],
+            SortKind::WaterRes => vec![sum_stat(full_equip, |stat| stat.water_res)],
+            SortKind::IceRes => vec![sum_stat(full_equip, |stat| stat.ice_res)],
+            SortKind::ElecRes => vec![sum_stat(full_equip, |stat| stat.elec_res)],
+            SortKind::DragonRes => vec![sum_stat(full_equip, |stat| stat.dragon_res)],
+            SortKind::LeftoverSlots => full_equip
+                .deco_combs
+                .iter()
+                .map(|deco_comb| get_leftover_slots_key(&deco_comb.leftover_slots_sum))
+                .max()
+                .unwrap(),
+            SortKind::AnomalyCount => vec![full_equip
+                .armors
+                .values()
+                .filter(|armor| armor.is_anomaly)
+                .count() as i32],
+            SortKind::CommonLeftoverSkills => {
+                vec![full_equip.common_leftover_skills.len() as i32]
+            }
+        };
+
+        rank.extend(values.into_iter().map(|value| {
+            if sort_key.is_descending {
+                -value
+            } else {
+                value
+            }
+        }));
+    }
+
+    rank
+}
+
+fn get_ranks(result: &CalculateResult, sort_keys: &[SortKey]) -> Vec<Vec<i32>> {
+    result
+        .full_equipments
+        .iter()
+        .map(|full_equip| get_rank(full_equip, sort_keys))
+        .collect()
+}
+
+fn sort_key(kind: SortKind, is_descending: bool) -> SortKey {
+    SortKey {
+        kind,
+        is_descending,
+    }
+}
+
+fn get_sort_keys_list() -> Vec<Vec<SortKey>> {
+    vec![
+        vec![sort_key(SortKind::Defense, true)],
+        vec![sort_key(SortKind::Defense, false)],
+        vec![
+            sort_key(SortKind::FireRes, false),
+            sort_key(SortKind::Defense, true),
+        ],
+        vec![
+            sort_key(SortKind::LeftoverSlots, true),
+            sort_key(SortKind::DragonRes, true),
+        ],
+    ]
+}
+
+#[tokio::test]
+async fn answers_follow_sort_keys() {
+    let dm = load_dm().await;
+
+    for sort_keys in get_sort_keys_list() {
+        let result = calculate(&dm, &get_request(&dm, &sort_keys, MAX_ANSWER_LENGTH));
+        let ranks = get_ranks(&result, &sort_keys);
+
+        assert!(ANSWER_LIMIT < ranks.len());
+        assert_ne!(ranks.first(), ranks.last());
+        assert!(
+            ranks.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{:?}: {:?}",
+            sort_keys,
+            ranks
+        );
+    }
+}
+
+// Worst answers are replaced while searching, so a limited result is the head of the full one.
+// Ties at the limit may keep any of the tied answers, so ranks are compared instead of answers
+#[tokio::test]
+async fn limited_answers_are_the_best_ones() {
+    let dm = load_dm().await;
+
+    for sort_keys in get_sort_keys_list() {
+        let full_result = calculate(&dm, &get_request(&dm, &sort_keys, MAX_ANSWER_LENGTH));
+        let limited_result = calculate(&dm, &get_request(&dm, &sort_keys, ANSWER_LIMIT));
+
+        let mut expected = get_ranks(&full_result, &sort_keys);
+        expected.truncate(ANSWER_LIMIT);
+
+        assert_eq!(
+            get_ranks(&limited_result, &sort_keys),
+            expected,
+            "{:?}",
+            sort_keys
+        );
+    }
+}
+
+#[tokio::test]
+async fn ranked_search_refuses_cursor() {
+    let dm = load_dm().await;
+
+    let mut request = get_request(&dm, &get_sort_keys_list()[0], ANSWER_LIMIT);
+    request.cursor = Some(CalcCursor::default());
+
+    let cm = load_cm(&dm, &request);
+
+    assert!(common::calculate(&dm, &cm, &request).is_err());
+}
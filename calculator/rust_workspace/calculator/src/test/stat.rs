@@ -0,0 +1,144 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    calc::{
+        calc_request::CalculateRequest,
+        calc_result::{CalculateResult, ResultFullEquipments},
+        calc_stat::{MinStats, StatBounds, StatValues},
+        constant::STAT_COUNT,
+    },
+    data::data_manager::DataManager,
+};
+
+use super::{
+    common::{self, load_cm},
+    differential::{random_case, GameData},
+    oracle::{AnswerKey, BruteForceOracle},
+};
+
+const CASES_COUNT: u64 = 100;
+
+fn calculate(dm: &DataManager, request: &CalculateRequest) -> CalculateResult {
+    let result = common::calculate(dm, &load_cm(dm, request), request).unwrap();
+
+    assert!(result.next_cursor.is_none());
+
+    result
+}
+
+fn get_stat_values(full_equip: &ResultFullEquipments) -> StatValues {
+    let mut sum_stats = [0; STAT_COUNT];
+
+    for armor in full_equip.armors.values() {
+        for (sum, value) in sum_stats
+            .iter_mut()
+            .zip(StatBounds::get_stat_values(&armor.stat))
+        {
+            *sum += value;
+        }
+    }
+
+    sum_stats
+}
+
+fn set_min_stat(min_stats: &mut MinStats, stat_index: usize, value: i32) {
+    let min_stat = match stat_index {
+        0 => &mut min_stats.defense,
+        1 => &mut min_stats.fire_res,
+        2 => &mut min_stats.water_res,
+        3 => &mut min_stats.ice_res,
+        4 => &mut min_stats.elec_res,
+        _ => &mut min_stats.dragon_res,
+    };
+
+    *min_stat = Some(value as i16);
+}
+
+fn is_enough(stat_values: &StatValues, min_stats: &MinStats) -> bool {
+    min_stats
+        .to_values()
+        .iter()
+        .zip(stat_values)
+        .all(|(min_stat, value)| match min_stat {
+            Some(min_stat) => min_stat <= value,
+            None => true,
+        })
+}
+
+// Answers with minimum stats must be exactly the answers without them that have enough stats,
+// best case bounds of empty, slot only and grouped equipments must not prune any of them
+#[test]
+fn stat_bounds_keep_every_valid_answer() {
+    let data = GameData::load();
+
+    let mut failures = Vec::new();
+    let mut kept_count = 0;
+    let mut pruned_count = 0;
+
+    for seed in 0..CASES_COUNT {
+        let case = random_case(seed, &data);
+        let dm = &case.dm;
+
+        let all_answers = calculate(dm, &case.request)
+            .full_equipments
+            .iter()
+            .map(|full_equip| {
+                (
+                    BruteForceOracle::get_answer_key(full_equip),
+                    get_stat_values(full_equip),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if all_answers.is_empty() {
+            continue;
+        }
+
+        // Median defense and one median resistance, so some answers are left out
+        let mut min_stats = MinStats::default();
+
+        for stat_index in [0, 1 + seed as usize % (STAT_COUNT - 1)] {
+            let mut values = all_answers
+                .iter()
+                .map(|(_, stat_values)| stat_values[stat_index])
+                .collect::<Vec<_>>();
+            values.sort();
+
+            set_min_stat(&mut min_stats, stat_index, values[values.len() / 2]);
+        }
+
+        let expected = all_answers
+            .iter()
+            .filter(|(_, stat_values)| is_enough(stat_values, &min_stats))
+            .map(|(key, _)| key.clone())
+            .collect::<BTreeSet<AnswerKey>>();
+
+        let mut request = case.request.clone();
+        request.min_stats = min_stats.clone();
+
+        let actual = BruteForceOracle::get_result_keys(&calculate(dm, &request));
+
+        kept_count += expected.len();
+        pruned_count += all_answers.len() - expected.len();
+
+        if actual != expected {
+            failures.push(format!(
+                "Seed {}: skills {:?}, min stats {:?}\npruned: {:?}\nextra: {:?}",
+                seed,
+                case.req_skills,
+                min_stats,
+                expected.difference(&actual).collect::<Vec<_>>(),
+                actual.difference(&expected).collect::<Vec<_>>(),
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Failed cases:\n{}",
+        failures.join("\n")
+    );
+
+    assert!(0 < kept_count);
+    assert!(0 < pruned_count);
+}
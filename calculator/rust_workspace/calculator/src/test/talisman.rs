@@ -0,0 +1,184 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    calc::{calc_equipment::CalcEquipment, calc_talisman::TalismanAnalyzer, types::SkillSlotCount},
+    data::{
+        armor::{SkillIdLevel, Talisman},
+        data_manager::DataManager,
+    },
+};
+
+use super::common::load_dm;
+
+// Critical eye only has size 2 and 4 decorations
+const CRITICAL_EYE_DECOS: [&str; 2] = ["expert_jewel_2", "expert_jewel_4"];
+
+fn talisman(
+    id: &str,
+    skills: &[(&str, SkillSlotCount)],
+    slot_sizes: &[SkillSlotCount],
+) -> Talisman {
+    let skills = skills
+        .iter()
+        .map(|(id, level)| SkillIdLevel {
+            id: id.to_string(),
+            level: *level,
+        })
+        .collect();
+
+    Talisman::new(id.to_string(), skills, slot_sizes.to_vec())
+}
+
+fn is_le_with_decos(dm: &DataManager, tali1: &Talisman, tali2: &Talisman) -> bool {
+    let equip1 = CalcEquipment::new_talisman(0, Arc::new(tali1.clone()), dm);
+    let equip2 = CalcEquipment::new_talisman(1, Arc::new(tali2.clone()), dm);
+
+    equip1.is_le_with_decos(&equip2, dm)
+}
+
+// (melted, kept) ids
+fn get_melt_list(dm: &mut DataManager, talismans: Vec<Talisman>) -> HashSet<(String, String)> {
+    dm.set_file_talismans(talismans);
+
+    TalismanAnalyzer::get_dominated_talismans(dm)
+        .into_iter()
+        .map(|dominated| {
+            (
+                dominated.talisman.id().clone(),
+                dominated.dominated_by.id().clone(),
+            )
+        })
+        .collect()
+}
+
+fn melt_pair(melted: &str, kept: &str) -> (String, String) {
+    (melted.to_string(), kept.to_string())
+}
+
+#[tokio::test]
+async fn plain_dominance() {
+    let mut dm = load_dm().await;
+
+    let weak = talisman("weak", &[("attack_boost", 1)], &[1, 0, 0]);
+    let strong = talisman("strong", &[("attack_boost", 2)], &[2, 0, 0]);
+
+    assert!(is_le_with_decos(&dm, &weak, &strong));
+    assert!(!is_le_with_decos(&dm, &strong, &weak));
+
+    let melt_list = get_melt_list(&mut dm, vec![weak, strong]);
+
+    assert_eq!(melt_list, HashSet::from([melt_pair("weak", "strong")]));
+}
+
+#[tokio::test]
+async fn dominance_through_decos_in_spare_slots() {
+    let mut dm = load_dm().await;
+
+    let skilled = talisman(
+        "skilled",
+        &[("attack_boost", 1), ("critical_eye", 1)],
+        &[0, 0, 0],
+    );
+    let slotted = talisman("slotted", &[("attack_boost", 1)], &[2, 0, 0]);
+    let small_slot = talisman("small_slot", &[("attack_boost", 1)], &[1, 0, 0]);
+
+    assert!(is_le_with_decos(&dm, &skilled, &slotted));
+    assert!(!is_le_with_decos(&dm, &slotted, &skilled));
+
+    // Size 1 slot can't hold a critical eye decoration
+    assert!(!is_le_with_decos(&dm, &skilled, &small_slot));
+
+    let melt_list = get_melt_list(&mut dm, vec![skilled, slotted, small_slot]);
+
+    assert_eq!(
+        melt_list,
+        HashSet::from([
+            melt_pair("skilled", "slotted"),
+            melt_pair("small_slot", "slotted"),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn skill_without_deco() {
+    let mut dm = load_dm().await;
+
+    assert!(!dm.has_decoration(dm.get_skill_uid("stormsoul")));
+
+    let stormsoul = talisman("stormsoul", &[("stormsoul", 1)], &[0, 0, 0]);
+    let slotted = talisman("slotted", &[], &[4, 4, 4]);
+
+    assert!(!is_le_with_decos(&dm, &stormsoul, &slotted));
+    assert!(!is_le_with_decos(&dm, &slotted, &stormsoul));
+
+    let melt_list = get_melt_list(&mut dm, vec![stormsoul, slotted]);
+
+    assert!(melt_list.is_empty());
+}
+
+#[tokio::test]
+async fn identical_talismans_are_not_listed() {
+    let mut dm = load_dm().await;
+
+    let tali1 = talisman("tali1", &[("attack_boost", 2)], &[2, 1, 0]);
+    let tali2 = talisman("tali2", &[("attack_boost", 2)], &[2, 1, 0]);
+
+    assert!(is_le_with_decos(&dm, &tali1, &tali2));
+    assert!(is_le_with_decos(&dm, &tali2, &tali1));
+
+    // Same talisman through decorations, neither is listed
+    let skilled = talisman("skilled", &[("critical_eye", 1)], &[2, 0, 0]);
+    let slotted = talisman("slotted", &[], &[2, 2, 0]);
+
+    let melt_list = get_melt_list(&mut dm, vec![tali1, tali2, skilled, slotted]);
+
+    assert!(!melt_list
+        .iter()
+        .any(|(melted, _)| melted.starts_with("tali")));
+    assert!(melt_list.contains(&melt_pair("skilled", "slotted")));
+}
+
+#[tokio::test]
+async fn unavailable_decos_are_not_used() {
+    let mut dm = load_dm().await;
+
+    let skilled = talisman("skilled", &[("critical_eye", 1)], &[0, 0, 0]);
+    let slotted = talisman("slotted", &[], &[4, 0, 0]);
+
+    assert!(is_le_with_decos(&dm, &skilled, &slotted));
+
+    let banned_decos = CRITICAL_EYE_DECOS
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<HashSet<_>>();
+    dm.set_banned_decos(&banned_decos);
+
+    assert!(!is_le_with_decos(&dm, &skilled, &slotted));
+    assert!(get_melt_list(&mut dm, vec![skilled.clone(), slotted.clone()]).is_empty());
+
+    dm.set_banned_decos(&HashSet::new());
+
+    let deco_inventory = CRITICAL_EYE_DECOS
+        .iter()
+        .map(|id| (id.to_string(), 0))
+        .collect::<HashMap<_, _>>();
+    dm.set_deco_inventory(&deco_inventory);
+
+    assert!(!is_le_with_decos(&dm, &skilled, &slotted));
+    assert!(get_melt_list(&mut dm, vec![skilled.clone(), slotted.clone()]).is_empty());
+
+    // One owned decoration is enough again
+    dm.set_deco_inventory(&HashMap::from([
+        (CRITICAL_EYE_DECOS[0].to_string(), 0),
+        (CRITICAL_EYE_DECOS[1].to_string(), 1),
+    ]));
+
+    assert!(is_le_with_decos(&dm, &skilled, &slotted));
+    assert_eq!(
+        get_melt_list(&mut dm, vec![skilled, slotted]),
+        HashSet::from([melt_pair("skilled", "slotted")])
+    );
+}
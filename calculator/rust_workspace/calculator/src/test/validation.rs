@@ -0,0 +1,215 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    calc::constant::MAX_SKILLS_COUNT,
+    data::{
+        armor::{ArmorSkill, BaseArmor},
+        data_manager::DataManager,
+        deco::Decoration,
+        skill::Skill,
+        validation::{DataFile, DataIssueKind, DataReport},
+    },
+    utils::parse::try_parse_data,
+};
+
+struct GameData {
+    armors: HashMap<String, BaseArmor>,
+    skills: HashMap<String, Skill>,
+    decos: HashMap<String, Decoration>,
+}
+
+impl GameData {
+    fn load() -> Self {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../src-tauri/data");
+
+        let armors = try_parse_data::<BaseArmor, _>(data_dir.join("armor.json"))
+            .unwrap()
+            .into_iter()
+            .map(|armor| (armor.id().clone(), armor))
+            .collect();
+        let skills = try_parse_data::<Skill, _>(data_dir.join("skill.json"))
+            .unwrap()
+            .into_iter()
+            .map(|skill| (skill.id.clone(), skill))
+            .collect();
+        let decos = try_parse_data::<Decoration, _>(data_dir.join("deco.json"))
+            .unwrap()
+            .into_iter()
+            .map(|deco| (deco.id.clone(), deco))
+            .collect();
+
+        Self {
+            armors,
+            skills,
+            decos,
+        }
+    }
+
+    fn check(&self) -> DataReport {
+        DataReport::check(&self.armors, &self.skills, &self.decos)
+    }
+
+    fn get_issues(&self, file: DataFile, id: &str) -> Vec<(DataIssueKind, bool)> {
+        self.check()
+            .issues
+            .into_iter()
+            .filter(|issue| issue.file == file && issue.id == id)
+            .map(|issue| (issue.kind, issue.is_error))
+            .collect()
+    }
+}
+
+#[test]
+fn shipped_data_has_no_errors() {
+    let data = GameData::load();
+
+    let report = data.check();
+
+    assert!(!report.has_errors(), "{}", report);
+}
+
+#[test]
+fn broken_data_is_reported() {
+    let mut data = GameData::load();
+
+    let armor = data.armors.get_mut("kamura_head_scarf").unwrap();
+    armor
+        .skills
+        .insert("no_such_skill".to_string(), ArmorSkill { level: 1 });
+    armor.skills.get_mut("divine_blessing").unwrap().level = 9;
+    armor.slots = vec![5, 0, 0];
+
+    assert_eq!(
+        data.get_issues(DataFile::Armor, "kamura_head_scarf"),
+        vec![
+            (
+                DataIssueKind::InvalidSkillLevel {
+                    skill_id: "divine_blessing".to_string(),
+                    level: 9,
+                    max_level: data.skills["divine_blessing"].max_level,
+                },
+                false
+            ),
+            (
+                DataIssueKind::UnknownSkill {
+                    skill_id: "no_such_skill".to_string()
+                },
+                true
+            ),
+            (DataIssueKind::InvalidSlotSize { slot_size: 5 }, true),
+        ]
+    );
+
+    let deco = data.decos.get_mut("attack_jewel_2").unwrap();
+    deco.skill_id = "no_such_skill".to_string();
+    deco.slot_size = 0;
+
+    assert_eq!(
+        data.get_issues(DataFile::Deco, "attack_jewel_2"),
+        vec![
+            (
+                DataIssueKind::DecoWithoutSkill {
+                    skill_id: "no_such_skill".to_string()
+                },
+                true
+            ),
+            (DataIssueKind::InvalidSlotSize { slot_size: 0 }, true),
+        ]
+    );
+
+    // Same name in two languages of one skill is fine, in two skills it collides
+    let skill = data.skills.get_mut("attack_boost").unwrap();
+    let skill_name = skill.names.values().next().unwrap().clone();
+    skill
+        .names
+        .values_mut()
+        .for_each(|name| *name = skill_name.clone());
+
+    assert!(data.get_issues(DataFile::Skill, "attack_boost").is_empty());
+
+    data.skills
+        .get_mut("weakness_exploit")
+        .unwrap()
+        .names
+        .values_mut()
+        .for_each(|name| *name = skill_name.clone());
+
+    assert_eq!(
+        data.get_issues(DataFile::Skill, "weakness_exploit"),
+        vec![(
+            DataIssueKind::DuplicateName {
+                name: skill_name,
+                other_id: "attack_boost".to_string(),
+            },
+            false
+        )]
+    );
+
+    assert!(DataManager::new(data.armors, data.skills, data.decos, None).is_err());
+}
+
+// Skill levels are kept in fixed size vectors, more skills can't be loaded
+#[test]
+fn too_many_skills_are_reported() {
+    let mut data = GameData::load();
+
+    let shipped_count = data.skills.len();
+
+    for index in shipped_count..=MAX_SKILLS_COUNT {
+        let id = format!("extra_skill_{}", index);
+
+        data.skills.insert(
+            id.clone(),
+            Skill {
+                id,
+                max_level: 1,
+                names: HashMap::new(),
+            },
+        );
+    }
+
+    assert_eq!(
+        data.get_issues(DataFile::Skill, ""),
+        vec![(
+            DataIssueKind::TooManySkills {
+                count: MAX_SKILLS_COUNT + 1,
+                max_count: MAX_SKILLS_COUNT,
+            },
+            true
+        )]
+    );
+
+    let error = DataManager::new(data.armors, data.skills, data.decos, None)
+        .err()
+        .unwrap();
+
+    assert!(error.to_string().contains(&format!(
+        "error: skill.json: {} skills, only up to {} are supported",
+        MAX_SKILLS_COUNT + 1,
+        MAX_SKILLS_COUNT
+    )));
+}
+
+#[test]
+fn unreadable_files_are_reported() {
+    let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../src-tauri/data");
+
+    let report = DataReport::check_sources(
+        &data_dir.join("armor.json"),
+        &data_dir.join("no_such_skill.json"),
+        &data_dir.join("armor.json"),
+    );
+
+    let files = report
+        .issues
+        .iter()
+        .map(|issue| {
+            (
+                issue.file,
+                matches!(issue.kind, DataIssueKind::InvalidFile { .. }),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(files, vec![(DataFile::Skill, true), (DataFile::Deco, true)]);
+}
@@ -1 +1,3 @@
 pub mod parse;
+pub mod stable_hash;
+pub mod suggest;
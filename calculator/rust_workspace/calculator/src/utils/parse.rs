@@ -3,7 +3,7 @@ use std::{fs::File, io::BufReader, path::Path};
 use anyhow::Context;
 use serde::de;
 
-pub fn parse_data<T, P: AsRef<Path>>(filename: P) -> Vec<T>
+pub fn try_parse_data<T, P: AsRef<Path>>(filename: P) -> anyhow::Result<Vec<T>>
 where
     T: de::DeserializeOwned,
 {
@@ -14,5 +14,4 @@ where
 
             serde_json::from_reader(reader).context("Failed to parse")
         })
-        .unwrap_or_default()
 }
@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// FNV-1a over the json of the value. Unlike std hashers and Hash impls,
+// the result stays the same between toolchains, so it can be saved
+pub fn get_stable_hash<T: Serialize>(value: &T) -> u64 {
+    let bytes = serde_json::to_vec(value).unwrap();
+
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
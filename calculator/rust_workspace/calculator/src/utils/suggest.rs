@@ -0,0 +1,53 @@
+const MAX_SUGGESTION_COUNT: usize = 3;
+
+fn get_edit_distance(left: &[char], right: &[char]) -> usize {
+    let mut prev_row = (0..=right.len()).collect::<Vec<_>>();
+    let mut row = vec![0; right.len() + 1];
+
+    for (i, left_char) in left.iter().enumerate() {
+        row[0] = i + 1;
+
+        for (j, right_char) in right.iter().enumerate() {
+            let replace_cost = if left_char == right_char { 0 } else { 1 };
+
+            row[j + 1] = (prev_row[j] + replace_cost)
+                .min(prev_row[j + 1] + 1)
+                .min(row[j] + 1);
+        }
+
+        std::mem::swap(&mut prev_row, &mut row);
+    }
+
+    prev_row[right.len()]
+}
+
+// Closest names first, names too far from the value are not suggested
+pub fn get_closest_matches<'a>(
+    value: &str,
+    candidates: impl Iterator<Item = &'a String>,
+) -> Vec<String> {
+    let value = value.to_lowercase().chars().collect::<Vec<_>>();
+    let max_distance = (value.len() / 3).max(1);
+
+    let mut matches = candidates
+        .filter_map(|candidate| {
+            let candidate_chars = candidate.to_lowercase().chars().collect::<Vec<_>>();
+            let distance = get_edit_distance(&value, &candidate_chars);
+
+            if distance <= max_distance {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    matches.sort();
+    matches.dedup_by(|(_, left), (_, right)| left == right);
+
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTION_COUNT)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
@@ -0,0 +1,435 @@
+mod table;
+#[cfg(test)]
+mod test;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use clap::{Parser, ValueEnum};
+use log::{info, warn};
+use mhr_calculator::{
+    calc::{
+        calc_data_manager::CalcDataManager,
+        calc_reporter::EmptyReporter,
+        calc_request::{CalcCursor, CalculateRequest},
+        calc_result::CalculateResult,
+        calc_sort::SortKey,
+        calc_stat::MinStats,
+        calculator::Calculator,
+        cancel_token::CancelToken,
+        constant::MAX_SLOT_LEVEL,
+        solver::SolverKind,
+        types::SkillSlotCount,
+    },
+    data::{
+        armor::{ArmorPart, SexType},
+        data_manager::DataManager,
+        parser::{parse_anomaly, parse_talisman},
+        skill::Skill,
+        validation::DataReport,
+        Language,
+    },
+};
+use nohash_hasher::IntMap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser)]
+#[command(about = "Calculates armor combinations without the app window")]
+struct Args {
+    /// JSON request file, options given on the command line override its values
+    #[arg(short, long)]
+    request: Option<PathBuf>,
+
+    /// Directory containing armor.json, skill.json and deco.json
+    #[arg(long, default_value = "./data")]
+    data_dir: PathBuf,
+
+    /// Directory to keep decoration combination tables in, later runs load them instead of building
+    #[arg(long)]
+    deco_table_dir: Option<PathBuf>,
+
+    /// Checks the game data files and prints the problems found instead of calculating
+    #[arg(long)]
+    check_data: bool,
+
+    /// Anomaly armors file exported by the mod
+    #[arg(long)]
+    anomaly: Option<PathBuf>,
+
+    /// Talismans file exported by the mod
+    #[arg(long)]
+    talisman: Option<PathBuf>,
+
+    /// Required skill as <id or name>=<level>, may be repeated
+    #[arg(short, long = "skill", value_parser = parse_skill_arg)]
+    skills: Vec<(String, SkillSlotCount)>,
+
+    /// Weapon slot sizes, one per slot and 0 for no slot, e.g. 2,1,0
+    #[arg(long, value_delimiter = ',')]
+    weapon_slots: Option<Vec<SkillSlotCount>>,
+
+    /// Required free slot counts by slot size, e.g. 0,1,0,0
+    #[arg(long, value_delimiter = ',')]
+    free_slots: Option<Vec<SkillSlotCount>>,
+
+    #[arg(long, value_enum)]
+    sex_type: Option<CliSexType>,
+
+    #[arg(long)]
+    include_lte_equips: bool,
+
+    /// Drops answers worse than another one in skills, slots and stats
+    #[arg(long)]
+    pareto_only: bool,
+
+    #[arg(long)]
+    answer_limit: Option<usize>,
+
+    /// Search engine, ilp finds the best answers first when sorted by a stat
+    #[arg(long, value_enum, default_value_t = CliSolver::Enumeration)]
+    solver: CliSolver,
+
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+
+    /// Language of armor and skill names in the table output
+    #[arg(long, default_value = "en")]
+    lang: String,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CliSexType {
+    Male,
+    Female,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CliSolver {
+    Enumeration,
+    Ilp,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Table,
+}
+
+// Same fields as the app sends to cmd_calculate_skillset
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RequestFile {
+    anomaly_filename: Option<PathBuf>,
+    talisman_filename: Option<PathBuf>,
+    sex_type: Option<SexType>,
+    weapon_slots: Option<Vec<SkillSlotCount>>,
+    selected_skills: HashMap<String, SkillSlotCount>,
+    free_slots: Option<Vec<SkillSlotCount>>,
+    include_lte_equips: bool,
+    min_stats: MinStats,
+    pinned_equips: HashMap<ArmorPart, String>,
+    excluded_equips: HashSet<String>,
+    answer_limit: Option<usize>,
+    cursor: Option<CalcCursor>,
+    sort_keys: Vec<SortKey>,
+    pareto_only: bool,
+
+    // Owned count of each decoration id, decorations not listed are unlimited
+    deco_inventory: HashMap<String, SkillSlotCount>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CalculateOutput {
+    log: String,
+    result: CalculateResult,
+}
+
+fn parse_skill_arg(arg: &str) -> Result<(String, SkillSlotCount), String> {
+    let (skill, level) = arg
+        .rsplit_once('=')
+        .ok_or_else(|| format!("Skill must be <id or name>=<level>: {}", arg))?;
+
+    let level = level
+        .parse()
+        .map_err(|_| format!("Invalid skill level: {}", level))?;
+
+    // Max level depends on the skill, it's checked once the data is loaded
+    if level < 1 {
+        return Err(format!("Skill level must be at least 1: {}", level));
+    }
+
+    Ok((skill.to_string(), level))
+}
+
+fn read_request_file(filename: &Path) -> anyhow::Result<RequestFile> {
+    let file = File::open(filename)
+        .with_context(|| format!("Failed to open request file: {}", filename.display()))?;
+
+    let mut request: RequestFile = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to parse request file: {}", filename.display()))?;
+
+    // Paths in the request file are relative to the file itself
+    let base_dir = filename.parent().unwrap_or_else(|| Path::new(""));
+
+    request.anomaly_filename = request.anomaly_filename.map(|path| base_dir.join(path));
+    request.talisman_filename = request.talisman_filename.map(|path| base_dir.join(path));
+
+    Ok(request)
+}
+
+fn get_skill_id(dm: &DataManager, skill: &str) -> anyhow::Result<String> {
+    if dm.get_skills().iter().any(|info| info.id == skill) {
+        return Ok(skill.to_string());
+    }
+
+    match dm.get_skill_name_dict().get(skill) {
+        Some(skill_id) => Ok(skill_id.clone()),
+        None => bail!("Unknown skill: {}", skill),
+    }
+}
+
+fn check_file_exists(filename: &Path) -> anyhow::Result<()> {
+    if !filename.is_file() {
+        bail!("File not found: {}", filename.display());
+    }
+
+    Ok(())
+}
+
+fn check_slots(
+    weapon_slots: &[SkillSlotCount],
+    free_slots: &[SkillSlotCount],
+) -> anyhow::Result<()> {
+    if let Some(size) = weapon_slots
+        .iter()
+        .find(|size| !(0..=MAX_SLOT_LEVEL as SkillSlotCount).contains(size))
+    {
+        bail!(
+            "Weapon slot size must be between 0 and {}: {}",
+            MAX_SLOT_LEVEL,
+            size
+        );
+    }
+
+    if free_slots.len() != MAX_SLOT_LEVEL {
+        bail!(
+            "Free slots must have {} counts, one per slot size: {}",
+            MAX_SLOT_LEVEL,
+            free_slots.len()
+        );
+    }
+
+    if let Some(count) = free_slots.iter().find(|count| **count < 0) {
+        bail!("Free slot count can't be negative: {}", count);
+    }
+
+    Ok(())
+}
+
+fn check_skill_level(skill: &Skill, level: SkillSlotCount) -> anyhow::Result<()> {
+    if !(1..=skill.max_level).contains(&level) {
+        bail!(
+            "Skill level of {} must be between 1 and {}: {}",
+            skill.id,
+            skill.max_level,
+            level
+        );
+    }
+
+    Ok(())
+}
+
+fn check_data(data_dir: &Path, format: OutputFormat) -> anyhow::Result<()> {
+    let report = DataReport::check_sources(
+        &data_dir.join("armor.json"),
+        &data_dir.join("skill.json"),
+        &data_dir.join("deco.json"),
+    );
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Table if report.issues.is_empty() => println!("No problems found"),
+        OutputFormat::Table => print!("{}", report),
+    }
+
+    if report.has_errors() {
+        bail!("Game data has {} errors", report.get_errors_count());
+    }
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+
+    run(Args::parse()).await
+}
+
+async fn run(args: Args) -> anyhow::Result<()> {
+    // Missing data files are reported with the other problems
+    if args.check_data {
+        return check_data(&args.data_dir, args.format);
+    }
+
+    let mut request_file = match &args.request {
+        Some(filename) => read_request_file(filename)?,
+        None => RequestFile::default(),
+    };
+
+    let weapon_slots = args
+        .weapon_slots
+        .or(request_file.weapon_slots)
+        .unwrap_or_else(|| vec![0; 3]);
+    let free_slots = args
+        .free_slots
+        .or(request_file.free_slots)
+        .unwrap_or_else(|| vec![0; MAX_SLOT_LEVEL]);
+
+    check_slots(&weapon_slots, &free_slots)?;
+
+    let lang: Language = serde_json::from_value(serde_json::Value::String(args.lang.clone()))
+        .with_context(|| format!("Unknown language: {}", args.lang))?;
+
+    let armors_filename = args.data_dir.join("armor.json");
+    let skills_filename = args.data_dir.join("skill.json");
+    let decos_filename = args.data_dir.join("deco.json");
+
+    for filename in [&armors_filename, &skills_filename, &decos_filename] {
+        check_file_exists(filename)?;
+    }
+
+    let mut dm = DataManager::from_sources(
+        &armors_filename,
+        &skills_filename,
+        &decos_filename,
+        args.deco_table_dir.as_deref(),
+    )
+    .await?;
+
+    // Next run loads this table instead of building it again
+    dm.save_deco_table();
+
+    if let Some(anomaly_filename) = args.anomaly.or(request_file.anomaly_filename) {
+        let anomalies = parse_anomaly(
+            &anomaly_filename,
+            dm.get_armors(),
+            dm.get_armor_name_dict(),
+            dm.get_skill_name_dict(),
+        )
+        .with_context(|| format!("Failed to read {}", anomaly_filename.display()))?;
+
+        for error in anomalies.errors.iter() {
+            warn!("{}: {}", anomaly_filename.display(), error);
+        }
+
+        info!("Loaded {} anomaly armors", anomalies.items.len());
+
+        dm.set_file_anomalies(anomalies.items);
+    }
+
+    if let Some(talisman_filename) = args.talisman.or(request_file.talisman_filename) {
+        let talismans = parse_talisman(&talisman_filename, dm.get_skill_name_dict())
+            .with_context(|| format!("Failed to read {}", talisman_filename.display()))?;
+
+        for error in talismans.errors.iter() {
+            warn!("{}: {}", talisman_filename.display(), error);
+        }
+
+        info!("Loaded {} talismans", talismans.items.len());
+
+        dm.set_file_talismans(talismans.items);
+    }
+
+    if !request_file.deco_inventory.is_empty() {
+        dm.set_deco_inventory(&request_file.deco_inventory);
+    }
+
+    request_file.selected_skills.extend(args.skills);
+
+    if request_file.selected_skills.is_empty() {
+        bail!("No skills are given");
+    }
+
+    let mut selected_skills = IntMap::default();
+
+    for (skill, level) in request_file.selected_skills.iter() {
+        let skill_id = get_skill_id(&dm, skill)?;
+        let uid = dm.get_skill_uid(&skill_id);
+
+        check_skill_level(&dm.get_skills()[uid], *level)?;
+
+        selected_skills.insert(uid, *level);
+    }
+
+    let sex_type = match args.sex_type {
+        Some(CliSexType::Male) => SexType::Male,
+        Some(CliSexType::Female) => SexType::Female,
+        None => match request_file.sex_type {
+            Some(sex_type) => sex_type,
+            None => bail!("Sex type is not given"),
+        },
+    };
+
+    let mut cm = CalcDataManager::new(&dm);
+    cm.load_base_armors(&dm);
+    cm.load_anomalies(&dm);
+    cm.load_talismans(&dm);
+    cm.refresh_infos(
+        &dm,
+        &Calculator::convert_to_skills_container(&selected_skills),
+    );
+
+    let mut request = CalculateRequest::new(
+        weapon_slots,
+        selected_skills,
+        free_slots,
+        sex_type,
+        args.include_lte_equips || request_file.include_lte_equips,
+    );
+
+    request.min_stats = request_file.min_stats;
+    request.pinned_equips = request_file.pinned_equips;
+    request.excluded_equips = request_file.excluded_equips;
+    request.cursor = request_file.cursor;
+    request.sort_keys = request_file.sort_keys;
+    request.pareto_only = args.pareto_only || request_file.pareto_only;
+
+    if let Some(answer_limit) = args.answer_limit.or(request_file.answer_limit) {
+        request.answer_limit = answer_limit;
+    }
+
+    let solver = match args.solver {
+        CliSolver::Enumeration => SolverKind::Enumeration,
+        CliSolver::Ilp => SolverKind::Ilp,
+    };
+
+    let (log, result) = solver.get_solver().solve(
+        &request,
+        &dm,
+        &cm,
+        &CancelToken::default(),
+        &EmptyReporter {},
+    )?;
+
+    info!("{}", log);
+
+    dm.save_deco_table();
+
+    match args.format {
+        OutputFormat::Json => {
+            let output = CalculateOutput { log, result };
+
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Table => print!("{}", table::format_result(&dm, &result, lang)),
+    }
+
+    Ok(())
+}
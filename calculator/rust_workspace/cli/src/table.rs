@@ -0,0 +1,136 @@
+use std::fmt::Write;
+
+use mhr_calculator::{
+    calc::calc_result::{CalculateResult, ResultFullEquipments},
+    data::{armor::ArmorPart, data_manager::DataManager, Language},
+};
+
+const HEADERS: [&str; 9] = [
+    "No", "Helm", "Torso", "Arm", "Waist", "Feet", "Talisman", "Defense", "Leftover",
+];
+
+pub fn format_result(dm: &DataManager, result: &CalculateResult, lang: Language) -> String {
+    let rows = result
+        .full_equipments
+        .iter()
+        .enumerate()
+        .map(|(index, full_equip)| get_row(dm, index + 1, full_equip, lang))
+        .collect::<Vec<_>>();
+
+    let mut widths = HEADERS.map(|header| header.chars().count());
+
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut ret = String::new();
+
+    write_row(&mut ret, &HEADERS.map(|header| header.to_string()), &widths);
+
+    for row in rows.iter() {
+        write_row(&mut ret, row, &widths);
+    }
+
+    writeln!(
+        ret,
+        "\n{} results in {:.2}s",
+        result.full_equipments.len(),
+        result.calc_time
+    )
+    .unwrap();
+
+    if result.next_cursor.is_some() {
+        writeln!(
+            ret,
+            "More results are available, use json output to get the cursor"
+        )
+        .unwrap();
+    }
+
+    ret
+}
+
+fn write_row(ret: &mut String, cells: &[String], widths: &[usize]) {
+    let line = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    writeln!(ret, "{}", line.trim_end()).unwrap();
+}
+
+// Anomaly armors are marked with an asterisk
+fn get_row(
+    dm: &DataManager,
+    number: usize,
+    full_equip: &ResultFullEquipments,
+    lang: Language,
+) -> Vec<String> {
+    let mut row = vec![number.to_string()];
+    let mut defense = 0;
+
+    for part in ArmorPart::get_all_armor() {
+        match full_equip.armors.get(part.as_str()) {
+            Some(armor) => {
+                let base_armor = dm.get_armor(&armor.base_id);
+                let name = base_armor
+                    .names
+                    .get(&lang)
+                    .unwrap_or(&armor.base_id)
+                    .clone();
+
+                defense += armor.stat.defense as i32;
+
+                row.push(if armor.is_anomaly {
+                    format!("{}*", name)
+                } else {
+                    name
+                });
+            }
+            None => row.push("-".to_string()),
+        }
+    }
+
+    let mut tali_skills = full_equip
+        .talisman
+        .skills
+        .iter()
+        .map(|(skill_id, level)| {
+            let skill = dm.get_skill(dm.get_skill_uid(skill_id));
+            let name = skill.names.get(&lang).unwrap_or(&skill.id);
+
+            format!("{} {}", name, level)
+        })
+        .collect::<Vec<_>>();
+
+    tali_skills.sort();
+
+    let tali_slots = format!("[{}]", join_slots(&full_equip.talisman.slots));
+
+    row.push(if tali_skills.is_empty() {
+        tali_slots
+    } else {
+        format!("{} {}", tali_skills.join(", "), tali_slots)
+    });
+
+    row.push(defense.to_string());
+
+    row.push(match full_equip.deco_combs.first() {
+        Some(deco_comb) => join_slots(&deco_comb.leftover_slots_sum),
+        None => "-".to_string(),
+    });
+
+    row
+}
+
+fn join_slots(slots: &[i8]) -> String {
+    slots
+        .iter()
+        .map(|count| count.to_string())
+        .collect::<Vec<_>>()
+        .join("-")
+}
@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use mhr_calculator::data::skill::Skill;
+
+use super::{
+    check_data, check_skill_level, check_slots, parse_skill_arg, run, Args, CliSexType, CliSolver,
+    OutputFormat,
+};
+
+const DATA_FILENAMES: [&str; 3] = ["armor.json", "skill.json", "deco.json"];
+
+fn create_temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "mhr_calculator_cli_{}_{}",
+        name,
+        std::process::id()
+    ));
+
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+fn copy_bundled_data(dir: &Path, filenames: &[&str]) {
+    let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../src-tauri/data");
+
+    for filename in filenames {
+        fs::copy(data_dir.join(filename), dir.join(filename)).unwrap();
+    }
+}
+
+#[test]
+fn default_args() {
+    let args = Args::try_parse_from(["mhr-calculator-cli"]).unwrap();
+
+    assert_eq!(args.data_dir, PathBuf::from("./data"));
+    assert!(args.request.is_none());
+    assert!(args.skills.is_empty());
+    assert!(!args.check_data);
+    assert!(matches!(args.solver, CliSolver::Enumeration));
+    assert!(args.format == OutputFormat::Json);
+    assert_eq!(args.lang, "en");
+}
+
+#[test]
+fn search_args() {
+    let args = Args::try_parse_from([
+        "mhr-calculator-cli",
+        "-s",
+        "attack_boost=7",
+        "--skill",
+        "간파=3",
+        "--weapon-slots",
+        "1,0,0",
+        "--free-slots",
+        "0,1,0,0",
+        "--sex-type",
+        "female",
+        "--answer-limit",
+        "20",
+        "--solver",
+        "ilp",
+        "-f",
+        "table",
+    ])
+    .unwrap();
+
+    assert_eq!(
+        args.skills,
+        [("attack_boost".to_string(), 7), ("간파".to_string(), 3)]
+    );
+    assert_eq!(args.weapon_slots, Some(vec![1, 0, 0]));
+    assert_eq!(args.free_slots, Some(vec![0, 1, 0, 0]));
+    assert!(matches!(args.sex_type, Some(CliSexType::Female)));
+    assert_eq!(args.answer_limit, Some(20));
+    assert!(matches!(args.solver, CliSolver::Ilp));
+    assert!(args.format == OutputFormat::Table);
+}
+
+#[test]
+fn invalid_args() {
+    for skill in [
+        "attack_boost",
+        "attack_boost=x",
+        "attack_boost=300",
+        "attack_boost=0",
+        "attack_boost=-1",
+    ] {
+        assert!(
+            Args::try_parse_from(["mhr-calculator-cli", "--skill", skill]).is_err(),
+            "{}",
+            skill
+        );
+    }
+
+    assert!(Args::try_parse_from(["mhr-calculator-cli", "--weapon-slots", "1,a,0"]).is_err());
+    assert!(Args::try_parse_from(["mhr-calculator-cli", "--sex-type", "other"]).is_err());
+
+    // Level is after the last '=', names may have one
+    assert_eq!(parse_skill_arg("a=b=2"), Ok(("a=b".to_string(), 2)));
+}
+
+#[test]
+fn check_data_accepts_bundled_data() {
+    let dir = create_temp_dir("check_data_bundled");
+    copy_bundled_data(&dir, &DATA_FILENAMES);
+
+    let result = check_data(&dir, OutputFormat::Json);
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    result.unwrap();
+}
+
+// Missing and broken files are reported by the check, not stopped before it
+#[tokio::test]
+async fn check_data_reports_missing_and_broken_files() {
+    let dir = create_temp_dir("check_data_broken");
+    copy_bundled_data(&dir, &["skill.json"]);
+    fs::write(dir.join("deco.json"), "[{").unwrap();
+
+    let args = Args::try_parse_from([
+        "mhr-calculator-cli",
+        "--check-data",
+        "--data-dir",
+        dir.to_str().unwrap(),
+    ])
+    .unwrap();
+
+    let result = run(args).await;
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(result.unwrap_err().to_string(), "Game data has 2 errors");
+}
+
+#[test]
+fn invalid_slots_are_errors() {
+    check_slots(&[4, 1, 0], &[0, 1, 0, 0]).unwrap();
+
+    assert!(check_slots(&[5, 0, 0], &[0; 4]).is_err());
+    assert!(check_slots(&[-1, 0, 0], &[0; 4]).is_err());
+    assert!(check_slots(&[0; 3], &[0, 1]).is_err());
+    assert!(check_slots(&[0; 3], &[0, -1, 0, 0]).is_err());
+}
+
+// Slots are checked before the data is loaded, so no data directory is needed
+#[tokio::test]
+async fn invalid_slot_args_are_errors() {
+    for (option, slots, error) in [
+        ("--free-slots", "0,1", "Free slots must have 4 counts"),
+        (
+            "--weapon-slots",
+            "5",
+            "Weapon slot size must be between 0 and 4",
+        ),
+    ] {
+        let args = Args::try_parse_from([
+            "mhr-calculator-cli",
+            "--skill",
+            "attack_boost=1",
+            "--sex-type",
+            "female",
+            option,
+            slots,
+        ])
+        .unwrap();
+
+        let result = run(args).await;
+
+        assert!(
+            result.unwrap_err().to_string().starts_with(error),
+            "{} {}",
+            option,
+            slots
+        );
+    }
+}
+
+#[test]
+fn invalid_skill_levels_are_errors() {
+    let skill = Skill {
+        id: "attack_boost".to_string(),
+        max_level: 7,
+        names: HashMap::new(),
+    };
+
+    check_skill_level(&skill, 1).unwrap();
+    check_skill_level(&skill, 7).unwrap();
+
+    assert!(check_skill_level(&skill, 0).is_err());
+    assert!(check_skill_level(&skill, 8).is_err());
+}
+
+// Request files skip the argument parser, their levels are checked with the data
+#[tokio::test]
+async fn skill_level_over_max_is_error() {
+    let dir = create_temp_dir("skill_level");
+    copy_bundled_data(&dir, &DATA_FILENAMES);
+
+    let request_filename = dir.join("request.json");
+    fs::write(
+        &request_filename,
+        r#"{ "selectedSkills": { "attack_boost": 8 } }"#,
+    )
+    .unwrap();
+
+    let args = Args::try_parse_from([
+        "mhr-calculator-cli",
+        "--data-dir",
+        dir.to_str().unwrap(),
+        "--request",
+        request_filename.to_str().unwrap(),
+        "--sex-type",
+        "female",
+    ])
+    .unwrap();
+
+    let result = run(args).await;
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Skill level of attack_boost must be between 1 and 7: 8"
+    );
+}
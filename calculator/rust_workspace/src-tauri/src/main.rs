@@ -4,26 +4,489 @@
 )]
 
 use data::armor::SexType;
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use mhr_calculator::{
     calc::{
-        calc_data_manager::CalcDataManager, calc_result::CalculateResult, calculator::Calculator,
+        calc_anomaly_ranking::{AnomalyRanker, AnomalyRanking},
+        calc_data_manager::CalcDataManager,
+        calc_reporter::{CalcProgress, CalcReporter},
+        calc_request::{CalcCursor, CalculateRequest},
+        calc_result::{AdditionalSkillsResult, CalculateResult, ResultFullEquipments},
+        calc_sort::SortKey,
+        calc_stat::MinStats,
+        calc_state::CalcState,
+        calc_talisman::{DominatedTalisman, TalismanAnalyzer},
+        calculator::Calculator,
         types::SkillSlotCount,
     },
     data::{
         armor::{AnomalyArmor, ArmorPart, ArmorStat, BaseArmor, SkillIdLevel, Talisman},
         data_manager::DataManager,
-        parser::{parse_anomaly, parse_talisman},
+        file_stamp::FileStamp,
+        parser::{parse_anomaly, parse_talisman, ParseError},
+        profile::{Profile, ProfileStore, SearchParams},
         skill::Skill,
+        validation::DataReport,
     },
     *,
 };
 use nohash_hasher::IntMap;
-use serde::Serialize;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
-    sync::RwLock,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
 };
+use tauri::{AppHandle, Manager, Window};
+
+const PROGRESS_EVENT: &str = "calc_progress";
+const ANSWER_EVENT: &str = "calc_answer";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+const FILE_CHANGED_EVENT: &str = "mod_file_changed";
+const FILE_EVENT_DELAY: Duration = Duration::from_millis(300);
+const DECO_TABLES_DIRNAME: &str = "deco_tables";
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct FileParseReport {
+    file_error: Option<String>,
+    errors: Vec<ParseError>,
+}
+
+// Last parse of each mod file, problems are shown in the anomaly and talisman tabs
+// and the stamp of the parsed file lets unchanged files skip parsing
+#[derive(Default)]
+struct ParseReportState {
+    anomaly: Mutex<FileParseReport>,
+    talisman: Mutex<FileParseReport>,
+    anomaly_stamp: Mutex<Option<FileStamp>>,
+    talisman_stamp: Mutex<Option<FileStamp>>,
+    profile_files: Mutex<HashMap<String, ProfileFiles>>,
+}
+
+// Mod files parsed for a profile, switching back to it reuses them while the files are unchanged
+#[derive(Clone)]
+struct ProfileFiles {
+    anomalies: Vec<AnomalyArmor>,
+    talismans: Vec<Talisman>,
+    anomaly_report: FileParseReport,
+    talisman_report: FileParseReport,
+    anomaly_stamp: FileStamp,
+    talisman_stamp: FileStamp,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FileChangedPayload {
+    anomaly: bool,
+    talisman: bool,
+}
+
+struct ActiveProfile {
+    name: String,
+    profile: Profile,
+}
+
+// User data owned by the backend, the active profile is saved on every change
+struct ProfileState {
+    store: ProfileStore,
+    active: Mutex<ActiveProfile>,
+    file_watch_sender: Mutex<Sender<()>>,
+}
+
+impl ProfileState {
+    fn update<F: FnOnce(&mut Profile)>(&self, f: F) {
+        let mut active = self.active.lock().unwrap();
+
+        f(&mut active.profile);
+
+        if let Err(e) = self.store.save(&active.name, &active.profile) {
+            warn!("Profile save failed: {:?}", e);
+        }
+
+        drop(active);
+
+        self.wake_file_watch();
+    }
+
+    // Mod files may have changed, the watcher follows the folders of the new ones
+    fn wake_file_watch(&self) {
+        let _ = self.file_watch_sender.lock().unwrap().send(());
+    }
+
+    fn get_list(&self) -> ProfileList {
+        ProfileList {
+            names: self.store.get_names(),
+            active: self.active.lock().unwrap().name.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileList {
+    names: Vec<String>,
+    active: String,
+}
+
+// Mod files of the new profile that failed to load are left empty and listed here
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SwitchedProfile {
+    profiles: ProfileList,
+    file_errors: Vec<String>,
+}
+
+fn parse_file_anomalies(
+    filename: &str,
+    dm: &DataManager,
+    report_state: &ParseReportState,
+) -> Vec<AnomalyArmor> {
+    let mut report = FileParseReport::default();
+
+    *report_state.anomaly_stamp.lock().unwrap() = Some(FileStamp::read(filename.as_ref()));
+
+    let anomalies = if filename.is_empty() {
+        Vec::new()
+    } else {
+        match parse_anomaly(
+            filename.as_ref(),
+            dm.get_armors(),
+            dm.get_armor_name_dict(),
+            dm.get_skill_name_dict(),
+        ) {
+            Ok(result) => {
+                report.errors = result.errors;
+                result.items
+            }
+            Err(e) => {
+                report.file_error = Some(e.to_string());
+                Vec::new()
+            }
+        }
+    };
+
+    for error in report.errors.iter() {
+        warn!("Anomaly file {}", error);
+    }
+
+    *report_state.anomaly.lock().unwrap() = report;
+
+    anomalies
+}
+
+fn parse_file_talismans(
+    filename: &str,
+    dm: &DataManager,
+    report_state: &ParseReportState,
+) -> Vec<Talisman> {
+    let mut report = FileParseReport::default();
+
+    *report_state.talisman_stamp.lock().unwrap() = Some(FileStamp::read(filename.as_ref()));
+
+    let talismans = if filename.is_empty() {
+        Vec::new()
+    } else {
+        match parse_talisman(filename.as_ref(), dm.get_skill_name_dict()) {
+            Ok(result) => {
+                report.errors = result.errors;
+                result.items
+            }
+            Err(e) => {
+                report.file_error = Some(e.to_string());
+                Vec::new()
+            }
+        }
+    };
+
+    for error in report.errors.iter() {
+        warn!("Talisman file {}", error);
+    }
+
+    *report_state.talisman.lock().unwrap() = report;
+
+    talismans
+}
+
+fn is_file_unchanged(filename: &str, stamp: &Mutex<Option<FileStamp>>) -> bool {
+    match stamp.lock().unwrap().as_mut() {
+        Some(stamp) => stamp.is_unchanged(filename.as_ref()),
+        None => false,
+    }
+}
+
+fn reload_file_anomalies(
+    filename: &str,
+    dm: &mut DataManager,
+    cm: &mut CalcDataManager,
+    report_state: &ParseReportState,
+) -> bool {
+    if is_file_unchanged(filename, &report_state.anomaly_stamp) {
+        return false;
+    }
+
+    let anomalies = parse_file_anomalies(filename, dm, report_state);
+
+    dm.set_file_anomalies(anomalies);
+    cm.load_anomalies(dm);
+
+    true
+}
+
+fn reload_file_talismans(
+    filename: &str,
+    dm: &mut DataManager,
+    cm: &mut CalcDataManager,
+    report_state: &ParseReportState,
+) -> bool {
+    if is_file_unchanged(filename, &report_state.talisman_stamp) {
+        return false;
+    }
+
+    let talismans = parse_file_talismans(filename, dm, report_state);
+
+    dm.set_file_talismans(talismans);
+    cm.load_talismans(dm);
+
+    true
+}
+
+// Watches the folders of the mod files of the active profile, since exports may replace the files,
+// and reloads the ones changed by a new export. Cursors of the old equipments are refused after it
+fn watch_files(handle: AppHandle, wake_receiver: Receiver<()>, wake_sender: Sender<()>) {
+    let watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+        // Reading the files on reload must not wake the watcher again
+        Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+            let _ = wake_sender.send(());
+        }
+        Ok(_) => {}
+        Err(e) => debug!("File watch error: {}", e),
+    });
+
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            warn!(
+                "File watcher failed to start, mod files reload on calculations only: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    let mut watched_dirs = HashSet::<PathBuf>::new();
+
+    while wake_receiver.recv().is_ok() {
+        // Exports write in several steps, their events are handled at once
+        thread::sleep(FILE_EVENT_DELAY);
+        while wake_receiver.try_recv().is_ok() {}
+
+        let (anomaly_filename, talisman_filename) = {
+            let profile_state = handle.state::<ProfileState>();
+            let active = profile_state.active.lock().unwrap();
+
+            (
+                active.profile.anomaly_filename.clone(),
+                active.profile.talisman_filename.clone(),
+            )
+        };
+
+        let dirs = [&anomaly_filename, &talisman_filename]
+            .iter()
+            .filter_map(|filename| Path::new(filename).parent())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(|dir| dir.to_path_buf())
+            .collect::<HashSet<_>>();
+
+        for dir in watched_dirs.difference(&dirs) {
+            if let Err(e) = watcher.unwatch(dir) {
+                debug!("File unwatch failed: {}", e);
+            }
+        }
+
+        watched_dirs.retain(|dir| dirs.contains(dir));
+
+        for dir in dirs {
+            if watched_dirs.contains(&dir) {
+                continue;
+            }
+
+            match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    watched_dirs.insert(dir);
+                }
+                Err(e) => warn!("File watch failed: {}, {}", dir.display(), e),
+            }
+        }
+
+        let report_state = handle.state::<ParseReportState>();
+
+        // Checked without the data locks, which a running calculation holds for long
+        if is_file_unchanged(&anomaly_filename, &report_state.anomaly_stamp)
+            && is_file_unchanged(&talisman_filename, &report_state.talisman_stamp)
+        {
+            continue;
+        }
+
+        let dm = handle.state::<RwLock<DataManager>>();
+        let cm = handle.state::<RwLock<CalcDataManager>>();
+
+        let mut dm = dm.write().unwrap();
+        let mut cm = cm.write().unwrap();
+
+        let payload = FileChangedPayload {
+            anomaly: reload_file_anomalies(&anomaly_filename, &mut dm, &mut cm, &report_state),
+            talisman: reload_file_talismans(&talisman_filename, &mut dm, &mut cm, &report_state),
+        };
+
+        info!(
+            "Mod files changed, anomaly: {}, talisman: {}",
+            payload.anomaly, payload.talisman
+        );
+
+        if let Err(e) = handle.emit_all(FILE_CHANGED_EVENT, payload) {
+            debug!("File changed emit failed: {}", e);
+        }
+    }
+}
+
+// Parses the mod files of the profile, unless the ones parsed for it before are unchanged
+fn load_profile_files(
+    name: &str,
+    profile: &Profile,
+    dm: &DataManager,
+    report_state: &ParseReportState,
+) -> ProfileFiles {
+    let mut profile_files = report_state.profile_files.lock().unwrap();
+
+    if let Some(files) = profile_files.get_mut(name) {
+        if files
+            .anomaly_stamp
+            .is_unchanged(profile.anomaly_filename.as_ref())
+            && files
+                .talisman_stamp
+                .is_unchanged(profile.talisman_filename.as_ref())
+        {
+            *report_state.anomaly.lock().unwrap() = files.anomaly_report.clone();
+            *report_state.talisman.lock().unwrap() = files.talisman_report.clone();
+            *report_state.anomaly_stamp.lock().unwrap() = Some(files.anomaly_stamp.clone());
+            *report_state.talisman_stamp.lock().unwrap() = Some(files.talisman_stamp.clone());
+
+            return files.clone();
+        }
+    }
+
+    let anomaly_stamp = FileStamp::read(profile.anomaly_filename.as_ref());
+    let talisman_stamp = FileStamp::read(profile.talisman_filename.as_ref());
+
+    let files = ProfileFiles {
+        anomalies: parse_file_anomalies(&profile.anomaly_filename, dm, report_state),
+        talismans: parse_file_talismans(&profile.talisman_filename, dm, report_state),
+        anomaly_report: report_state.anomaly.lock().unwrap().clone(),
+        talisman_report: report_state.talisman.lock().unwrap().clone(),
+        anomaly_stamp,
+        talisman_stamp,
+    };
+
+    profile_files.insert(name.to_string(), files.clone());
+
+    files
+}
+
+// Replaces the anomalies, talismans and decoration settings with the ones of the profile.
+// Mod files that fail to load leave no equipments of their own and their errors are returned
+fn apply_profile(
+    name: &str,
+    profile: &Profile,
+    dm: &mut DataManager,
+    cm: &mut CalcDataManager,
+    report_state: &ParseReportState,
+) -> Vec<String> {
+    profile.apply_manuals(dm);
+
+    let files = load_profile_files(name, profile, dm, report_state);
+
+    dm.set_file_anomalies(files.anomalies);
+    dm.set_file_talismans(files.talismans);
+
+    cm.load_anomalies(dm);
+    cm.load_talismans(dm);
+
+    cm.set_banned_decos(profile.banned_decos.clone());
+    cm.set_deco_inventory(profile.deco_inventory.clone());
+
+    sync_deco_settings(dm, cm);
+
+    let mut file_errors = Vec::new();
+
+    if let Some(e) = files.anomaly_report.file_error {
+        file_errors.push(format!("Anomaly file {}: {}", profile.anomaly_filename, e));
+    }
+
+    if let Some(e) = files.talisman_report.file_error {
+        file_errors.push(format!(
+            "Talisman file {}: {}",
+            profile.talisman_filename, e
+        ));
+    }
+
+    file_errors
+}
+
+// Decoration combinations of dm follow the ban list and inventory kept in cm
+fn sync_deco_settings(dm: &mut DataManager, cm: &CalcDataManager) {
+    dm.set_deco_settings(cm.get_banned_decos(), cm.get_deco_inventory());
+}
+
+struct WindowReporter {
+    window: Window,
+    stream_answers: bool,
+    last_emit: Mutex<Option<Instant>>,
+}
+
+impl WindowReporter {
+    fn new(window: Window, stream_answers: bool) -> Self {
+        Self {
+            window,
+            stream_answers,
+            last_emit: Mutex::new(None),
+        }
+    }
+}
+
+impl CalcReporter for WindowReporter {
+    fn on_progress(&self, progress: &CalcProgress) {
+        let mut last_emit = self.last_emit.lock().unwrap();
+
+        if let Some(last_emit) = *last_emit {
+            if !progress.is_done && last_emit.elapsed() < PROGRESS_INTERVAL {
+                return;
+            }
+        }
+
+        *last_emit = Some(Instant::now());
+
+        if let Err(e) = self.window.emit(PROGRESS_EVENT, progress) {
+            debug!("Progress emit failed: {}", e);
+        }
+    }
+
+    fn is_streaming_answers(&self) -> bool {
+        self.stream_answers
+    }
+
+    fn on_answer(&self, answer: &ResultFullEquipments) {
+        if let Err(e) = self.window.emit(ANSWER_EVENT, answer) {
+            debug!("Answer emit failed: {}", e);
+        }
+    }
+}
 
 #[tauri::command]
 fn cmd_get_file_anomalies(dm: tauri::State<RwLock<DataManager>>) -> Vec<AnomalyArmor> {
@@ -46,12 +509,18 @@ fn cmd_get_file_anomalies(dm: tauri::State<RwLock<DataManager>>) -> Vec<AnomalyA
 fn cmd_clear_file_anomalies(
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    report_state: tauri::State<ParseReportState>,
+    profile_state: tauri::State<ProfileState>,
 ) {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
 
     dm.clear_file_anomalies();
+    *report_state.anomaly.lock().unwrap() = FileParseReport::default();
+    *report_state.anomaly_stamp.lock().unwrap() = Some(FileStamp::read(Path::new("")));
     cm.load_anomalies(&dm);
+
+    profile_state.update(|profile| profile.anomaly_filename.clear());
 }
 
 #[tauri::command]
@@ -59,16 +528,13 @@ fn cmd_parse_anomaly(
     filename: &str,
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    report_state: tauri::State<ParseReportState>,
+    profile_state: tauri::State<ProfileState>,
 ) -> Vec<AnomalyArmor> {
     let anomalies = {
         let dm = dm.read().unwrap();
 
-        parse_anomaly(
-            filename.as_ref(),
-            dm.get_armors(),
-            dm.get_armor_name_dict(),
-            dm.get_skill_name_dict(),
-        )
+        parse_file_anomalies(filename, &dm, &report_state)
     };
 
     let mut dm = dm.write().unwrap();
@@ -77,9 +543,16 @@ fn cmd_parse_anomaly(
     dm.set_file_anomalies(anomalies.clone());
     cm.load_anomalies(&dm);
 
+    profile_state.update(|profile| profile.anomaly_filename = filename.to_string());
+
     anomalies
 }
 
+#[tauri::command]
+fn cmd_get_anomaly_parse_report(report_state: tauri::State<ParseReportState>) -> FileParseReport {
+    report_state.anomaly.lock().unwrap().clone()
+}
+
 #[tauri::command]
 fn cmd_add_manual_anomaly(
     original_id: &str,
@@ -88,6 +561,7 @@ fn cmd_add_manual_anomaly(
     stat_diff: ArmorStat,
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
 ) -> Option<AnomalyArmor> {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
@@ -106,6 +580,8 @@ fn cmd_add_manual_anomaly(
     let inserted = dm.add_manual_anomaly(anomaly);
     cm.load_anomalies(&dm);
 
+    profile_state.update(|profile| profile.update_manuals(&dm));
+
     Some((*inserted).clone())
 }
 
@@ -113,6 +589,7 @@ fn cmd_add_manual_anomaly(
 fn cmd_clear_manual_anomalies(
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
 ) -> bool {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
@@ -120,6 +597,8 @@ fn cmd_clear_manual_anomalies(
     dm.clear_manual_anomalies();
     cm.load_anomalies(&dm);
 
+    profile_state.update(|profile| profile.update_manuals(&dm));
+
     true
 }
 
@@ -128,6 +607,7 @@ fn cmd_set_manual_anomalies(
     anomalies: HashMap<ArmorPart, Vec<AnomalyArmor>>,
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
 ) -> bool {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
@@ -142,6 +622,8 @@ fn cmd_set_manual_anomalies(
 
     cm.load_anomalies(&dm);
 
+    profile_state.update(|profile| profile.update_manuals(&dm));
+
     true
 }
 
@@ -166,10 +648,12 @@ fn cmd_parse_talisman(
     filename: &str,
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    report_state: tauri::State<ParseReportState>,
+    profile_state: tauri::State<ProfileState>,
 ) -> Vec<Talisman> {
     let talismans = {
         let dm = dm.read().unwrap();
-        parse_talisman(filename.as_ref(), dm.get_skill_name_dict())
+        parse_file_talismans(filename, &dm, &report_state)
     };
 
     let mut dm = dm.write().unwrap();
@@ -178,19 +662,32 @@ fn cmd_parse_talisman(
     dm.set_file_talismans(talismans.clone());
     cm.load_talismans(&dm);
 
+    profile_state.update(|profile| profile.talisman_filename = filename.to_string());
+
     talismans
 }
 
+#[tauri::command]
+fn cmd_get_talisman_parse_report(report_state: tauri::State<ParseReportState>) -> FileParseReport {
+    report_state.talisman.lock().unwrap().clone()
+}
+
 #[tauri::command]
 fn cmd_clear_file_talismans(
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    report_state: tauri::State<ParseReportState>,
+    profile_state: tauri::State<ProfileState>,
 ) {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
 
     dm.clear_file_talismans();
+    *report_state.talisman.lock().unwrap() = FileParseReport::default();
+    *report_state.talisman_stamp.lock().unwrap() = Some(FileStamp::read(Path::new("")));
     cm.load_talismans(&dm);
+
+    profile_state.update(|profile| profile.talisman_filename.clear());
 }
 
 #[tauri::command]
@@ -198,6 +695,7 @@ fn cmd_add_manual_talisman(
     talisman: Talisman,
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
 ) -> Talisman {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
@@ -205,6 +703,8 @@ fn cmd_add_manual_talisman(
     let inserted = dm.add_manual_talisman(&talisman);
     cm.load_talismans(&dm);
 
+    profile_state.update(|profile| profile.update_manuals(&dm));
+
     (*inserted).clone()
 }
 
@@ -213,6 +713,7 @@ fn cmd_delete_manual_talisman(
     id: String,
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
 ) -> bool {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
@@ -220,6 +721,8 @@ fn cmd_delete_manual_talisman(
     let removed = dm.remove_manual_talisman(&id);
     cm.load_talismans(&dm);
 
+    profile_state.update(|profile| profile.update_manuals(&dm));
+
     removed
 }
 
@@ -228,6 +731,7 @@ fn cmd_set_manual_talismans(
     talismans: Vec<Talisman>,
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
 ) -> bool {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
@@ -245,6 +749,8 @@ fn cmd_set_manual_talismans(
 
     cm.load_talismans(&dm);
 
+    profile_state.update(|profile| profile.update_manuals(&dm));
+
     true
 }
 
@@ -252,6 +758,7 @@ fn cmd_set_manual_talismans(
 fn cmd_clear_manual_talismans(
     dm: tauri::State<RwLock<DataManager>>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
 ) -> bool {
     let mut dm = dm.write().unwrap();
     let mut cm = cm.write().unwrap();
@@ -259,9 +766,18 @@ fn cmd_clear_manual_talismans(
     dm.clear_manual_talismans();
     cm.load_talismans(&dm);
 
+    profile_state.update(|profile| profile.update_manuals(&dm));
+
     true
 }
 
+#[tauri::command]
+fn cmd_get_dominated_talismans(dm: tauri::State<RwLock<DataManager>>) -> Vec<DominatedTalisman> {
+    let dm = dm.read().unwrap();
+
+    TalismanAnalyzer::get_dominated_talismans(&dm)
+}
+
 #[tauri::command]
 fn cmd_get_skill_names(dm: tauri::State<RwLock<DataManager>>) -> HashMap<String, Skill> {
     let dm = &dm.read().unwrap();
@@ -284,23 +800,176 @@ fn cmd_get_armor_names(dm: tauri::State<RwLock<DataManager>>) -> HashMap<String,
         .collect();
 }
 
+// Problems found in the game data at startup, with errors the app runs only this command
+#[tauri::command]
+fn cmd_get_data_report(data_report: tauri::State<DataReport>) -> DataReport {
+    data_report.inner().clone()
+}
+
 #[tauri::command]
 fn cmd_set_banned_decos(
     deco_ids: HashMap<String, bool>,
     cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
 ) -> bool {
     let mut cm = cm.write().unwrap();
 
-    cm.set_banned_decos(
-        deco_ids
-            .iter()
-            .map(|(deco_id, _)| deco_id.clone())
-            .collect::<HashSet<_>>(),
-    );
+    let banned_decos = deco_ids
+        .iter()
+        .map(|(deco_id, _)| deco_id.clone())
+        .collect::<HashSet<_>>();
+
+    cm.set_banned_decos(banned_decos.clone());
+
+    profile_state.update(|profile| profile.banned_decos = banned_decos);
 
     true
 }
 
+#[tauri::command]
+fn cmd_set_deco_inventory(
+    deco_counts: HashMap<String, SkillSlotCount>,
+    cm: tauri::State<RwLock<CalcDataManager>>,
+    profile_state: tauri::State<ProfileState>,
+) -> bool {
+    let mut cm = cm.write().unwrap();
+
+    cm.set_deco_inventory(deco_counts.clone());
+
+    profile_state.update(|profile| profile.deco_inventory = deco_counts);
+
+    true
+}
+
+#[tauri::command]
+fn cmd_get_profile(profile_state: tauri::State<ProfileState>) -> Profile {
+    profile_state.active.lock().unwrap().profile.clone()
+}
+
+#[tauri::command]
+fn cmd_get_profiles(profile_state: tauri::State<ProfileState>) -> ProfileList {
+    profile_state.get_list()
+}
+
+#[tauri::command]
+fn cmd_create_profile(
+    name: String,
+    profile_state: tauri::State<ProfileState>,
+) -> Result<ProfileList, String> {
+    profile_state
+        .store
+        .create(&name)
+        .map_err(|e| format!("{:#}", e))?;
+
+    Ok(profile_state.get_list())
+}
+
+#[tauri::command]
+fn cmd_switch_profile(
+    name: String,
+    dm: tauri::State<RwLock<DataManager>>,
+    cm: tauri::State<RwLock<CalcDataManager>>,
+    report_state: tauri::State<ParseReportState>,
+    profile_state: tauri::State<ProfileState>,
+) -> Result<SwitchedProfile, String> {
+    let mut dm = dm.write().unwrap();
+    let mut cm = cm.write().unwrap();
+
+    let store = &profile_state.store;
+
+    if !store.exists(&name) {
+        return Err(format!("Profile {} doesn't exist", name));
+    }
+
+    let profile = store.load(&name).map_err(|e| format!("{:#}", e))?;
+
+    store
+        .set_active_name(&name)
+        .map_err(|e| format!("{:#}", e))?;
+
+    let file_errors = apply_profile(&name, &profile, &mut dm, &mut cm, &report_state);
+
+    for error in file_errors.iter() {
+        warn!("Profile {} {}", name, error);
+    }
+
+    info!("Switched to profile {}", name);
+
+    *profile_state.active.lock().unwrap() = ActiveProfile { name, profile };
+
+    profile_state.wake_file_watch();
+
+    Ok(SwitchedProfile {
+        profiles: profile_state.get_list(),
+        file_errors,
+    })
+}
+
+#[tauri::command]
+fn cmd_rename_profile(
+    name: String,
+    new_name: String,
+    report_state: tauri::State<ParseReportState>,
+    profile_state: tauri::State<ProfileState>,
+) -> Result<ProfileList, String> {
+    let mut active = profile_state.active.lock().unwrap();
+
+    profile_state
+        .store
+        .rename(&name, &new_name)
+        .map_err(|e| format!("{:#}", e))?;
+
+    let mut profile_files = report_state.profile_files.lock().unwrap();
+
+    if let Some(files) = profile_files.remove(&name) {
+        profile_files.insert(new_name.clone(), files);
+    }
+
+    drop(profile_files);
+
+    if active.name == name {
+        active.name = new_name;
+    }
+
+    drop(active);
+
+    Ok(profile_state.get_list())
+}
+
+#[tauri::command]
+fn cmd_duplicate_profile(
+    name: String,
+    new_name: String,
+    profile_state: tauri::State<ProfileState>,
+) -> Result<ProfileList, String> {
+    profile_state
+        .store
+        .duplicate(&name, &new_name)
+        .map_err(|e| format!("{:#}", e))?;
+
+    Ok(profile_state.get_list())
+}
+
+#[tauri::command]
+fn cmd_delete_profile(
+    name: String,
+    report_state: tauri::State<ParseReportState>,
+    profile_state: tauri::State<ProfileState>,
+) -> Result<ProfileList, String> {
+    if profile_state.active.lock().unwrap().name == name {
+        return Err("Active profile can't be deleted".to_string());
+    }
+
+    profile_state
+        .store
+        .delete(&name)
+        .map_err(|e| format!("{:#}", e))?;
+
+    report_state.profile_files.lock().unwrap().remove(&name);
+
+    Ok(profile_state.get_list())
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct CalculateSkillsetReturn {
@@ -312,10 +981,8 @@ struct CalculateSkillsetReturn {
 #[serde(rename_all = "camelCase")]
 struct CalculateAdditionalSkillsReturn {
     log: String,
-    equipments_count: usize,
-    calc_time: f32,
-    skills: HashMap<String, (SkillSlotCount, SkillSlotCount)>,
-    slots: Vec<SkillSlotCount>,
+    #[serde(flatten)]
+    result: AdditionalSkillsResult,
 }
 
 #[tauri::command]
@@ -327,11 +994,24 @@ async fn cmd_calculate_skillset(
     selected_skills: HashMap<String, SkillSlotCount>,
     free_slots: Vec<SkillSlotCount>,
     include_lte_equips: bool,
+    min_stats: MinStats,
+    pinned_equips: HashMap<ArmorPart, String>,
+    excluded_equips: HashSet<String>,
+    answer_limit: usize,
+    cursor: Option<CalcCursor>,
+    sort_keys: Vec<SortKey>,
+    pareto_only: bool,
     dm: tauri::State<'_, RwLock<DataManager>>,
     cm: tauri::State<'_, RwLock<CalcDataManager>>,
-) -> Result<CalculateSkillsetReturn, ()> {
+    calc_state: tauri::State<'_, CalcState>,
+    report_state: tauri::State<'_, ParseReportState>,
+    profile_state: tauri::State<'_, ProfileState>,
+    window: Window,
+) -> Result<CalculateSkillsetReturn, String> {
     info!("Start calculating...");
 
+    let calc_guard = calc_state.start().await;
+
     let selected_skills_uid;
 
     {
@@ -341,27 +1021,35 @@ async fn cmd_calculate_skillset(
             .collect::<IntMap<_, _>>();
     }
 
-    {
+    // Cursor is only valid for the equipments it was created with
+    if cursor.is_none() {
         let mut dm = dm.write().unwrap();
         let mut cm = cm.write().unwrap();
 
-        let anomalies = parse_anomaly(
-            anomaly_filename.as_ref(),
-            dm.get_armors(),
-            dm.get_armor_name_dict(),
-            dm.get_skill_name_dict(),
-        );
+        reload_file_anomalies(&anomaly_filename, &mut dm, &mut cm, &report_state);
+        reload_file_talismans(&talisman_filename, &mut dm, &mut cm, &report_state);
 
-        let talismans = parse_talisman(talisman_filename.as_ref(), dm.get_skill_name_dict());
+        sync_deco_settings(&mut dm, &cm);
 
-        dm.set_file_anomalies(anomalies);
-        dm.set_file_talismans(talismans);
-
-        cm.load_anomalies(&dm);
-        cm.load_talismans(&dm);
-
-        let banned_decos = cm.get_banned_decos();
-        dm.set_banned_decos(banned_decos);
+        let search = SearchParams {
+            sex_type: sex_type.clone(),
+            weapon_slots: weapon_slots.clone(),
+            selected_skills: selected_skills.clone(),
+            free_slots: free_slots.clone(),
+            include_lte_equips,
+            min_stats: min_stats.clone(),
+            pinned_equips: pinned_equips.clone(),
+            excluded_equips: excluded_equips.clone(),
+            answer_limit,
+            sort_keys: sort_keys.clone(),
+            pareto_only,
+        };
+
+        profile_state.update(|profile| {
+            profile.anomaly_filename = anomaly_filename;
+            profile.talisman_filename = talisman_filename;
+            profile.last_search = Some(search);
+        });
     }
 
     let log;
@@ -375,17 +1063,37 @@ async fn cmd_calculate_skillset(
 
         cm.refresh_infos(&dm, &req_skills);
 
-        (log, result) = Calculator::calculate(
+        let mut request = CalculateRequest::new(
             weapon_slots,
             selected_skills_uid,
             free_slots,
             sex_type,
             include_lte_equips,
+        );
+
+        request.min_stats = min_stats;
+        request.pinned_equips = pinned_equips;
+        request.excluded_equips = excluded_equips;
+        request.answer_limit = answer_limit;
+        request.cursor = cursor;
+        request.sort_keys = sort_keys;
+        request.pareto_only = pareto_only;
+
+        (log, result) = Calculator::calculate(
+            &request,
             &dm,
             &cm,
-        );
+            calc_state.cancel_token(),
+            &WindowReporter::new(window, true),
+        )
+        .map_err(|e| format!("{:#}", e))?;
+
+        calc_guard.set_result(&result);
     }
 
+    // Caches the calculation filled are kept for the next start
+    dm.write().unwrap().save_deco_table();
+
     Ok(CalculateSkillsetReturn { log, result })
 }
 
@@ -398,11 +1106,22 @@ async fn cmd_calculate_additional_skills(
     selected_skills: HashMap<String, SkillSlotCount>,
     free_slots: Vec<SkillSlotCount>,
     include_lte_equips: bool,
+    min_stats: MinStats,
+    pinned_equips: HashMap<ArmorPart, String>,
+    excluded_equips: HashSet<String>,
+    answer_limit: usize,
+    cursor: Option<CalcCursor>,
     dm: tauri::State<'_, RwLock<DataManager>>,
     cm: tauri::State<'_, RwLock<CalcDataManager>>,
-) -> Result<CalculateAdditionalSkillsReturn, ()> {
+    calc_state: tauri::State<'_, CalcState>,
+    report_state: tauri::State<'_, ParseReportState>,
+    profile_state: tauri::State<'_, ProfileState>,
+    window: Window,
+) -> Result<CalculateAdditionalSkillsReturn, String> {
     info!("Start calculating...");
 
+    let _calc_guard = calc_state.start().await;
+
     let selected_skills_uid;
 
     {
@@ -412,31 +1131,24 @@ async fn cmd_calculate_additional_skills(
             .collect::<IntMap<_, _>>();
     }
 
-    {
+    // Cursor is only valid for the equipments it was created with
+    if cursor.is_none() {
         let mut dm = dm.write().unwrap();
         let mut cm = cm.write().unwrap();
 
-        let anomalies = parse_anomaly(
-            anomaly_filename.as_ref(),
-            dm.get_armors(),
-            dm.get_armor_name_dict(),
-            dm.get_skill_name_dict(),
-        );
-
-        let talismans = parse_talisman(talisman_filename.as_ref(), dm.get_skill_name_dict());
+        reload_file_anomalies(&anomaly_filename, &mut dm, &mut cm, &report_state);
+        reload_file_talismans(&talisman_filename, &mut dm, &mut cm, &report_state);
 
-        dm.set_file_anomalies(anomalies);
-        dm.set_file_talismans(talismans);
+        sync_deco_settings(&mut dm, &cm);
 
-        cm.load_anomalies(&dm);
-        cm.load_talismans(&dm);
+        profile_state.update(|profile| {
+            profile.anomaly_filename = anomaly_filename;
+            profile.talisman_filename = talisman_filename;
+        });
     }
 
     let log;
-    let calc_time;
-    let equipments_count;
-    let skills;
-    let slots;
+    let result;
 
     {
         let dm = dm.read().unwrap();
@@ -446,24 +1158,125 @@ async fn cmd_calculate_additional_skills(
 
         cm.refresh_infos(&dm, &req_skills);
 
-        (log, equipments_count, calc_time, skills, slots) = Calculator::calculate_additional_skills(
+        let mut request = CalculateRequest::new(
             weapon_slots,
             selected_skills_uid,
             free_slots,
             sex_type,
             include_lte_equips,
+        );
+
+        request.min_stats = min_stats;
+        request.pinned_equips = pinned_equips;
+        request.excluded_equips = excluded_equips;
+        request.answer_limit = answer_limit;
+        request.cursor = cursor;
+
+        (log, result) = Calculator::calculate_additional_skills(
+            &request,
             &dm,
             &cm,
-        );
+            calc_state.cancel_token(),
+            &WindowReporter::new(window, false),
+        )
+        .map_err(|e| format!("{:#}", e))?;
     }
 
-    Ok(CalculateAdditionalSkillsReturn {
-        log,
-        equipments_count,
-        calc_time,
-        skills,
-        slots,
-    })
+    dm.write().unwrap().save_deco_table();
+
+    Ok(CalculateAdditionalSkillsReturn { log, result })
+}
+
+// Saved search condition used as a target build in anomaly ranking
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TargetBuild {
+    sex_type: SexType,
+    weapon_slots: Vec<SkillSlotCount>,
+    req_skills: HashMap<String, SkillSlotCount>,
+    req_slots: Vec<SkillSlotCount>,
+}
+
+#[tauri::command]
+async fn cmd_rank_anomalies(
+    anomaly_filename: String,
+    talisman_filename: String,
+    builds: Vec<TargetBuild>,
+    dm: tauri::State<'_, RwLock<DataManager>>,
+    cm: tauri::State<'_, RwLock<CalcDataManager>>,
+    calc_state: tauri::State<'_, CalcState>,
+    report_state: tauri::State<'_, ParseReportState>,
+    profile_state: tauri::State<'_, ProfileState>,
+) -> Result<Vec<AnomalyRanking>, String> {
+    info!("Start ranking anomalies...");
+
+    let _calc_guard = calc_state.start().await;
+
+    {
+        let mut dm = dm.write().unwrap();
+        let mut cm = cm.write().unwrap();
+
+        reload_file_anomalies(&anomaly_filename, &mut dm, &mut cm, &report_state);
+        reload_file_talismans(&talisman_filename, &mut dm, &mut cm, &report_state);
+
+        sync_deco_settings(&mut dm, &cm);
+    }
+
+    profile_state.update(|profile| {
+        profile.anomaly_filename = anomaly_filename;
+        profile.talisman_filename = talisman_filename;
+    });
+
+    let rankings = {
+        let dm = dm.read().unwrap();
+        let mut cm = cm.write().unwrap();
+
+        let requests = builds
+            .into_iter()
+            .map(|build| {
+                let selected_skills = build
+                    .req_skills
+                    .iter()
+                    .map(|(id, level)| (dm.get_skill_uid(id), *level))
+                    .collect::<IntMap<_, _>>();
+
+                CalculateRequest::new(
+                    build.weapon_slots,
+                    selected_skills,
+                    build.req_slots,
+                    build.sex_type,
+                    false,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        AnomalyRanker::rank(&dm, &mut cm, &requests, calc_state.cancel_token())
+            .map_err(|e| format!("{:#}", e))?
+    };
+
+    dm.write().unwrap().save_deco_table();
+
+    info!("Ranked {} anomalies", rankings.len());
+
+    Ok(rankings)
+}
+
+#[tauri::command]
+async fn cmd_cancel_calculation(
+    calc_state: tauri::State<'_, CalcState>,
+) -> Result<Option<CalculateResult>, ()> {
+    info!("Cancel calculating...");
+
+    Ok(calc_state.cancel().await)
+}
+
+// Window shows the report and nothing else, a panic would exit before the window opens
+fn run_data_report_app<A: tauri::Assets>(context: tauri::Context<A>, data_report: DataReport) {
+    tauri::Builder::default()
+        .manage(data_report)
+        .invoke_handler(tauri::generate_handler![cmd_get_data_report])
+        .run(context)
+        .expect("error while running tauri application");
 }
 
 #[tokio::main]
@@ -479,41 +1292,123 @@ async fn main() {
         .build_global()
         .unwrap();
 
-    let dm = DataManager::from_sources(
-        "./data/armor.json".as_ref(),
-        "./data/skill.json".as_ref(),
-        "./data/deco.json".as_ref(),
+    let context = tauri::generate_context!();
+
+    let armors_filename = Path::new("./data/armor.json");
+    let skills_filename = Path::new("./data/skill.json");
+    let decos_filename = Path::new("./data/deco.json");
+
+    let data_dir = tauri::api::path::app_data_dir(context.config()).unwrap_or_default();
+
+    let mut dm = match DataManager::from_sources(
+        armors_filename,
+        skills_filename,
+        decos_filename,
+        Some(&data_dir.join(DECO_TABLES_DIRNAME)),
     )
-    .await;
+    .await
+    {
+        Ok(dm) => dm,
+        Err(e) => {
+            error!("Game data load failed: {:#}", e);
+
+            run_data_report_app(
+                context,
+                DataReport::check_sources(armors_filename, skills_filename, decos_filename),
+            );
+
+            return;
+        }
+    };
+
+    // Next start loads this table instead of building it again
+    dm.save_deco_table();
+
+    let data_report = dm.get_data_report().clone();
+
+    info!("Game data checked, {} warnings", data_report.issues.len());
 
     dm.debug_deco_combs();
 
     let mut cm = CalcDataManager::new(&dm);
     cm.load_base_armors(&dm);
 
+    let store = ProfileStore::new(&data_dir);
+    let name = store.get_active_name();
+    let profile = store.load_or_default(&name);
+
+    // First start has no profile file yet, create it so it shows up in the profile list
+    if !store.exists(&name) {
+        if let Err(e) = store.save(&name, &profile) {
+            warn!("Profile save failed: {:?}", e);
+        }
+    }
+
+    info!("Profile loaded: {}", name);
+
+    let report_state = ParseReportState::default();
+
+    for error in apply_profile(&name, &profile, &mut dm, &mut cm, &report_state) {
+        warn!("Profile {} {}", name, error);
+    }
+
+    let (file_watch_sender, file_watch_receiver) = mpsc::channel();
+
+    // First wake up watches the mod files of the loaded profile
+    let _ = file_watch_sender.send(());
+
     tauri::Builder::default()
         .manage(RwLock::new(dm))
         .manage(RwLock::new(cm))
+        .manage(CalcState::default())
+        .manage(report_state)
+        .manage(data_report)
+        .manage(ProfileState {
+            store,
+            active: Mutex::new(ActiveProfile { name, profile }),
+            file_watch_sender: Mutex::new(file_watch_sender.clone()),
+        })
+        .setup(move |app| {
+            let handle = app.handle();
+
+            thread::spawn(move || watch_files(handle, file_watch_receiver, file_watch_sender));
+
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             cmd_get_file_anomalies,
             cmd_parse_anomaly,
+            cmd_get_anomaly_parse_report,
             cmd_clear_file_anomalies,
             cmd_add_manual_anomaly,
             cmd_clear_manual_anomalies,
             cmd_set_manual_anomalies,
             cmd_get_file_talismans,
             cmd_parse_talisman,
+            cmd_get_talisman_parse_report,
             cmd_add_manual_talisman,
             cmd_clear_file_talismans,
             cmd_delete_manual_talisman,
             cmd_set_manual_talismans,
             cmd_clear_manual_talismans,
+            cmd_get_dominated_talismans,
             cmd_get_skill_names,
             cmd_get_armor_names,
+            cmd_get_data_report,
             cmd_set_banned_decos,
+            cmd_set_deco_inventory,
+            cmd_get_profile,
+            cmd_get_profiles,
+            cmd_create_profile,
+            cmd_switch_profile,
+            cmd_rename_profile,
+            cmd_duplicate_profile,
+            cmd_delete_profile,
             cmd_calculate_skillset,
-            cmd_calculate_additional_skills
+            cmd_calculate_additional_skills,
+            cmd_rank_anomalies,
+            cmd_cancel_calculation
         ])
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
@@ -12,6 +12,42 @@ export type SexType = "" | "male" | "female";
 export interface CalculateResult {
     fullEquipments: ResultFullEquipments[];
     calcTime: number;
+    nextCursor: CalcCursor | null;
+}
+
+export interface MinStats {
+    defense: number | null;
+    fireRes: number | null;
+    waterRes: number | null;
+    iceRes: number | null;
+    elecRes: number | null;
+    dragonRes: number | null;
+}
+
+export type PinnedEquips = { [part: string]: string };
+
+export type SortKind = "defense" | "fireRes" | "waterRes" | "iceRes" | "elecRes" | "dragonRes" | "leftoverSlots" | "anomalyCount" | "commonLeftoverSkills";
+
+export interface SortKey {
+    kind: SortKind;
+    isDescending: boolean;
+}
+
+export interface CalcCursor {
+    fingerprint: string;
+    candidateIndex: number;
+    partsState: { probeIds: [number, number][] } | null;
+    leState: { probeIds: [number, number][] } | null;
+    answerUids: number[][];
+}
+
+export interface CalcProgress {
+    processedCandidates: number;
+    candidatesCount: number;
+    allCandidateLen: number;
+    answersCount: number;
+    elapsed: number;
+    isDone: boolean;
 }
 
 export interface CalculateAdditionalSkillsResult {
@@ -19,6 +55,7 @@ export interface CalculateAdditionalSkillsResult {
     equipmentsCount: number;
     skills: MinMaxSkills;
     slots: Slots;
+    nextCursor: CalcCursor | null;
 }
 
 export interface FullEquipmentsCommon {
@@ -33,6 +70,7 @@ export interface ResultFullEquipments extends FullEquipmentsCommon {
 }
 
 export interface ResultArmor {
+    id: string;
     baseId: string;
     isAnomaly: boolean;
 
@@ -80,6 +118,15 @@ export interface AnomalyArmorInfo {
     skillDiffs: { [key: string]: ArmorFinalSkillInfo },
 }
 
+export type AnomalyRecommendation = "keep" | "optional" | "discard";
+
+export interface AnomalyRanking {
+    anomaly: AnomalyArmorInfo;
+    usedCount: number;
+    requiredCount: number;
+    recommendation: AnomalyRecommendation;
+}
+
 export interface CalcChoices {
     sexType: SexType;
     weaponSlots: EquipSlots;
@@ -87,12 +134,88 @@ export interface CalcChoices {
     freeSlots: Slots;
 }
 
+export interface SearchParams extends CalcChoices {
+    includeLteEquips: boolean;
+    minStats: MinStats;
+    pinnedEquips: PinnedEquips;
+    excludedEquips: string[];
+    answerLimit: number;
+    sortKeys: SortKey[];
+    paretoOnly: boolean;
+}
+
+export interface ProfileInfo {
+    version: number;
+    manualAnomalies: AnomalyArmorInfo[];
+    manualTalismans: TalismanInfo[];
+    bannedDecos: string[];
+    decoInventory: { [key: string]: number };
+    anomalyFilename: string;
+    talismanFilename: string;
+    lastSearch: SearchParams | null;
+}
+
+export interface ProfileList {
+    names: string[];
+    active: string;
+}
+
+export interface SwitchedProfile {
+    profiles: ProfileList;
+    fileErrors: string[];
+}
+
 export interface TalismanInfo {
     id: string;
     skills: { id: string, level: number }[],
     slotSizes: number[]
 }
 
+export interface DominatedTalismanInfo {
+    talisman: TalismanInfo;
+    dominatedBy: TalismanInfo;
+}
+
+export type ParseErrorKind =
+    { type: "invalidRecord", message: string } |
+    { type: "missingValue" } |
+    { type: "invalidNumber" } |
+    { type: "outOfRange", min: number, max: number } |
+    { type: "unknownArmorName", suggestions: string[] } |
+    { type: "unknownSkillName", suggestions: string[] };
+
+export interface ParseError {
+    line: number;
+    column: number;
+    value: string;
+    kind: ParseErrorKind;
+}
+
+export interface FileParseReport {
+    fileError: string | null;
+    errors: ParseError[];
+}
+
+export type DataIssueKind =
+    { type: "invalidFile", message: string } |
+    { type: "tooManySkills", count: number, maxCount: number } |
+    { type: "unknownSkill", skillId: string } |
+    { type: "decoWithoutSkill", skillId: string } |
+    { type: "invalidSkillLevel", skillId: string, level: number, maxLevel: number } |
+    { type: "invalidSlotSize", slotSize: number } |
+    { type: "duplicateName", name: string, otherId: string };
+
+export interface DataIssue {
+    file: "armor" | "skill" | "deco";
+    id: string;
+    isError: boolean;
+    kind: DataIssueKind;
+}
+
+export interface DataReport {
+    issues: DataIssue[];
+}
+
 export function getTotalStat(armors: { [key: string]: ResultArmor }) {
     const stat = getDefaultStat();
 
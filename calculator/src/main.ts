@@ -9,6 +9,12 @@ async function load() {
 		await import("../node_modules/ant-design-vue/dist/antd.dark.css");
 	}
 
+	try {
+		await CacheManager.moveToProfile();
+	} catch (e) {
+		console.error("Moving cached data to the profile failed, ", e);
+	}
+
 	createApp(App).mount("#app");
 }
 
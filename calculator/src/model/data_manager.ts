@@ -1,9 +1,10 @@
-import { ArmorParts } from "../definition/armor_define";
 import { AnomalyArmorInfo, CalcChoices, ResultFavorite, SearchFavorite, TalismanInfo } from "../definition/calculate_result";
 import { Language, LanguagesValue } from "../definition/language";
+import { InvokeManager } from "./invoke_manager";
 
 const setItem = window.localStorage.setItem.bind(window.localStorage);
 const getItem = window.localStorage.getItem.bind(window.localStorage);
+const removeItem = window.localStorage.removeItem.bind(window.localStorage);
 
 export class DataManager {
 
@@ -26,6 +27,7 @@ export class CacheManager {
 	static manualTalismansName = "manual_talismans";
 
 	static bannedDecosName = "banned_decos";
+	static decoInventoryName = "deco_inventory";
 
 	static defaultCalcChoices: CalcChoices = {
 		sexType: "",
@@ -131,67 +133,65 @@ export class CacheManager {
 		}
 	}
 
-	static setAnomalyFilename(filename: string) {
-		setItem(this.anomalyFilenameName, filename);
-	}
-
-	static getAnomalyFilename() {
-		return getItem(this.anomalyFilenameName) ?? "";
-	}
+	// Files, manuals and decoration settings are saved in the backend profile since it was introduced,
+	// move what older versions left in the local storage there once
+	static async moveToProfile() {
+		const legacyNames = [
+			this.anomalyFilenameName,
+			this.talismanFilenameName,
+			this.manualAnomaliesName,
+			this.manualTalismansName,
+			this.bannedDecosName,
+			this.decoInventoryName,
+		];
+
+		if (legacyNames.every(name => getItem(name) === null)) {
+			return;
+		}
 
-	static setTalismanFilename(filename: string) {
-		setItem(this.talismanFilenameName, filename);
-	}
+		const profile = await InvokeManager.getProfile();
 
-	static getTalismanFilename() {
-		return getItem(this.talismanFilenameName) ?? "";
-	}
+		const getLegacyItem = <T>(name: string) => {
+			try {
+				return JSON.parse(getItem(name)!) as T | null;
+			} catch (e) {
+				return null;
+			}
+		};
 
-	static setManualAnomalies(anomalies: {
-		[key: string]: AnomalyArmorInfo[];
-	}) {
-		setItem(this.manualAnomaliesName, JSON.stringify(anomalies));
-	}
+		const anomalyFilename = getItem(this.anomalyFilenameName);
+		const talismanFilename = getItem(this.talismanFilenameName);
+		const manualAnomalies = getLegacyItem<{ [key: string]: AnomalyArmorInfo[] }>(this.manualAnomaliesName);
+		const manualTalismans = getLegacyItem<TalismanInfo[]>(this.manualTalismansName);
+		const bannedDecos = getLegacyItem<{ [key: string]: boolean }>(this.bannedDecosName);
+		const decoInventory = getLegacyItem<{ [key: string]: number }>(this.decoInventoryName);
 
-	static getManualAnomalies() {
-		try {
-			return JSON.parse(getItem(this.manualAnomaliesName)!) as { [key: string]: AnomalyArmorInfo[] } ?? this.getDefaultManualAnomalies();
-		} catch (e) {
-			return this.getDefaultManualAnomalies();
+		if (anomalyFilename && profile.anomalyFilename === "") {
+			await InvokeManager.parseFileAnomaly(anomalyFilename);
 		}
-	}
-
-	static getDefaultManualAnomalies() {
-		const ret = {} as { [key: string]: AnomalyArmorInfo[] };
 
-		for (const part of ArmorParts) {
-			ret[part] = [];
+		if (talismanFilename && profile.talismanFilename === "") {
+			await InvokeManager.parseFileTalisman(talismanFilename);
 		}
 
-		return ret;
-	}
+		if (manualAnomalies !== null && profile.manualAnomalies.length === 0) {
+			await InvokeManager.setManualAnomalies(manualAnomalies);
+		}
 
-	static setManualTalismans(talismans: TalismanInfo[]) {
-		setItem(this.manualTalismansName, JSON.stringify(talismans));
-	}
+		if (manualTalismans !== null && profile.manualTalismans.length === 0) {
+			await InvokeManager.setManualTalismans(manualTalismans);
+		}
 
-	static getManualTalismans() {
-		try {
-			return JSON.parse(getItem(this.manualTalismansName)!) as TalismanInfo[] ?? [];
-		} catch (e) {
-			return [];
+		if (bannedDecos !== null && profile.bannedDecos.length === 0) {
+			await InvokeManager.setBannedDecos(bannedDecos);
 		}
-	}
 
-	static setBannedDecos(decos: { [key: string]: boolean }) {
-		setItem(this.bannedDecosName, JSON.stringify(decos));
-	}
+		if (decoInventory !== null && Object.keys(profile.decoInventory).length === 0) {
+			await InvokeManager.setDecoInventory(decoInventory);
+		}
 
-	static getBannedDecos() {
-		try {
-			return JSON.parse(getItem(this.bannedDecosName)!) as { [key: string]: boolean } ?? {};
-		} catch (e) {
-			return {};
+		for (const name of legacyNames) {
+			removeItem(name);
 		}
 	}
 
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/tauri";
-import { AnomalyArmorInfo, TalismanInfo, EquipSlots, SexType, Skills, Slots, CalculateResult, CalculateAdditionalSkillsResult } from "../definition/calculate_result";
+import { AnomalyArmorInfo, TalismanInfo, EquipSlots, SexType, Skills, Slots, CalculateResult, CalculateAdditionalSkillsResult, CalcCursor, SortKey, MinStats, PinnedEquips, FileParseReport, DataReport, DominatedTalismanInfo, SearchFavorite, AnomalyRanking, ProfileInfo, ProfileList, SwitchedProfile } from "../definition/calculate_result";
 import { ArmorStatInfo } from "../definition/armor_define";
 
 export interface AnomalyAddInfo {
@@ -17,7 +17,7 @@ export class InvokeManager {
 		return invoke<T>(cmd, args);
 	}
 
-	public static async calculateSkillset(anomalyFilename: string, talismanFilename: string, sexType: SexType, weaponSlots: EquipSlots, selectedSkills: Skills, freeSlots: Slots, includeLteEquips: boolean) {
+	public static async calculateSkillset(anomalyFilename: string, talismanFilename: string, sexType: SexType, weaponSlots: EquipSlots, selectedSkills: Skills, freeSlots: Slots, includeLteEquips: boolean, minStats: MinStats, pinnedEquips: PinnedEquips, excludedEquips: string[], answerLimit: number, cursor: CalcCursor | null, sortKeys: SortKey[], paretoOnly: boolean) {
 		return this.invoke<{ result: CalculateResult, log: string }>("cmd_calculate_skillset", {
 			anomalyFilename,
 			talismanFilename,
@@ -26,11 +26,18 @@ export class InvokeManager {
 			selectedSkills,
 			freeSlots,
 			includeLteEquips,
+			minStats,
+			pinnedEquips,
+			excludedEquips,
+			answerLimit,
+			cursor,
+			sortKeys,
+			paretoOnly,
 		});
 	}
 
-	public static async calculateAdditionalSkillset(anomalyFilename: string, talismanFilename: string, sexType: SexType, weaponSlots: EquipSlots, selectedSkills: Skills, freeSlots: Slots, includeLteEquips: boolean) {
-		return this.invoke<CalculateAdditionalSkillsResult>("cmd_calculate_additional_skills", {
+	public static async calculateAdditionalSkillset(anomalyFilename: string, talismanFilename: string, sexType: SexType, weaponSlots: EquipSlots, selectedSkills: Skills, freeSlots: Slots, includeLteEquips: boolean, minStats: MinStats, pinnedEquips: PinnedEquips, excludedEquips: string[], answerLimit: number, cursor: CalcCursor | null) {
+		return this.invoke<CalculateAdditionalSkillsResult & { log: string }>("cmd_calculate_additional_skills", {
 			anomalyFilename,
 			talismanFilename,
 			sexType,
@@ -38,13 +45,34 @@ export class InvokeManager {
 			selectedSkills,
 			freeSlots,
 			includeLteEquips,
+			minStats,
+			pinnedEquips,
+			excludedEquips,
+			answerLimit,
+			cursor,
 		});
 	}
 
+	public static async rankAnomalies(anomalyFilename: string, talismanFilename: string, builds: SearchFavorite[]) {
+		return this.invoke<AnomalyRanking[]>("cmd_rank_anomalies", {
+			anomalyFilename,
+			talismanFilename,
+			builds,
+		});
+	}
+
+	public static async cancelCalculation() {
+		return this.invoke<CalculateResult | null>("cmd_cancel_calculation", {});
+	}
+
 	public static async parseFileAnomaly(filename: string) {
 		return this.invoke<AnomalyArmorInfo[]>("cmd_parse_anomaly", { filename });
 	}
 
+	public static async getAnomalyParseReport() {
+		return this.invoke<FileParseReport>("cmd_get_anomaly_parse_report", {});
+	}
+
 	public static async getFileAnomalies() {
 		return this.invoke<AnomalyArmorInfo[]>("cmd_get_file_anomalies", {});
 	}
@@ -74,6 +102,10 @@ export class InvokeManager {
 		return this.invoke<TalismanInfo[]>("cmd_parse_talisman", { filename });
 	}
 
+	public static async getTalismanParseReport() {
+		return this.invoke<FileParseReport>("cmd_get_talisman_parse_report", {});
+	}
+
 	public static async clearFileTalismans() {
 		return this.invoke<void>("cmd_clear_file_talismans", {});
 	}
@@ -94,7 +126,47 @@ export class InvokeManager {
 		return this.invoke<boolean>("cmd_clear_manual_talismans", {});
 	}
 
+	public static async getDominatedTalismans() {
+		return this.invoke<DominatedTalismanInfo[]>("cmd_get_dominated_talismans", {});
+	}
+
+	public static async getDataReport() {
+		return this.invoke<DataReport>("cmd_get_data_report", {});
+	}
+
 	public static async setBannedDecos(decoIds: { [key: string]: boolean }) {
 		return this.invoke<boolean>("cmd_set_banned_decos", { decoIds });
 	}
+
+	public static async setDecoInventory(decoCounts: { [key: string]: number }) {
+		return this.invoke<boolean>("cmd_set_deco_inventory", { decoCounts });
+	}
+
+	public static async getProfile() {
+		return this.invoke<ProfileInfo>("cmd_get_profile", {});
+	}
+
+	public static async getProfiles() {
+		return this.invoke<ProfileList>("cmd_get_profiles", {});
+	}
+
+	public static async createProfile(name: string) {
+		return this.invoke<ProfileList>("cmd_create_profile", { name });
+	}
+
+	public static async switchProfile(name: string) {
+		return this.invoke<SwitchedProfile>("cmd_switch_profile", { name });
+	}
+
+	public static async renameProfile(name: string, newName: string) {
+		return this.invoke<ProfileList>("cmd_rename_profile", { name, newName });
+	}
+
+	public static async duplicateProfile(name: string, newName: string) {
+		return this.invoke<ProfileList>("cmd_duplicate_profile", { name, newName });
+	}
+
+	public static async deleteProfile(name: string) {
+		return this.invoke<ProfileList>("cmd_delete_profile", { name });
+	}
 }
\ No newline at end of file